@@ -0,0 +1 @@
+ALTER TABLE cv DROP COLUMN cover_letter_path;
@@ -0,0 +1 @@
+ALTER TABLE cv DROP COLUMN compaction_level;
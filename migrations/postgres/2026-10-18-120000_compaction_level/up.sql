@@ -0,0 +1,2 @@
+-- The compaction level the page contract was met at, if the CV overflowed
+ALTER TABLE cv ADD COLUMN compaction_level VARCHAR;
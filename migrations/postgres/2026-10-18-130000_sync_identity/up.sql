@@ -0,0 +1,11 @@
+-- What `db sync` matches and compares rows by: a stable identity shared by
+-- every copy of an application, its last-modified time (RFC 3339, UTC) and
+-- the last-modified time both copies agreed on at the last sync.
+ALTER TABLE cv ADD COLUMN uuid VARCHAR NOT NULL DEFAULT '';
+UPDATE cv SET uuid = md5(random()::text || clock_timestamp()::text || id::text)::uuid::text;
+ALTER TABLE cv ALTER COLUMN uuid DROP DEFAULT;
+CREATE UNIQUE INDEX cv_uuid ON cv (uuid);
+ALTER TABLE cv ADD COLUMN updated_at VARCHAR NOT NULL DEFAULT '';
+UPDATE cv SET updated_at = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"');
+ALTER TABLE cv ALTER COLUMN updated_at DROP DEFAULT;
+ALTER TABLE cv ADD COLUMN synced_at VARCHAR;
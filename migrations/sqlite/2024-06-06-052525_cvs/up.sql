@@ -0,0 +1,11 @@
+-- SQLite flavour of the postgres migration: `INTEGER PRIMARY KEY` is what
+-- makes `id` an auto-assigned rowid (`SERIAL` is postgres-only).
+CREATE TABLE cv (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  application_date VARCHAR,
+  job_title VARCHAR NOT NULL,
+  company VARCHAR NOT NULL,
+  quote VARCHAR NOT NULL,
+  pdf_cv_path VARCHAR NOT NULL,
+  generated BOOLEAN NOT NULL DEFAULT TRUE
+)
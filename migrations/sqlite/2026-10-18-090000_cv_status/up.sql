@@ -0,0 +1,2 @@
+-- Application lifecycle stage (see rusty_cv_creator::status)
+ALTER TABLE cv ADD COLUMN status VARCHAR NOT NULL DEFAULT 'drafted'
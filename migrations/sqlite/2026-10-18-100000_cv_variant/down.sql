@@ -0,0 +1,2 @@
+ALTER TABLE cv DROP COLUMN template_ref;
+ALTER TABLE cv DROP COLUMN variant;
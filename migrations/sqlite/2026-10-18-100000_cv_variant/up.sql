@@ -0,0 +1,3 @@
+-- The CV variant that was built and the template revision it was built from
+ALTER TABLE cv ADD COLUMN variant VARCHAR;
+ALTER TABLE cv ADD COLUMN template_ref VARCHAR;
@@ -0,0 +1,2 @@
+-- Where the cover letter built alongside the CV was published, if any
+ALTER TABLE cv ADD COLUMN cover_letter_path VARCHAR;
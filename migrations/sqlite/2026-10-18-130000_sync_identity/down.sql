@@ -0,0 +1,4 @@
+DROP INDEX cv_uuid;
+ALTER TABLE cv DROP COLUMN synced_at;
+ALTER TABLE cv DROP COLUMN updated_at;
+ALTER TABLE cv DROP COLUMN uuid;
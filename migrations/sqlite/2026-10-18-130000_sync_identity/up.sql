@@ -0,0 +1,15 @@
+-- What `db sync` matches and compares rows by: a stable identity shared by
+-- every copy of an application, its last-modified time (RFC 3339, UTC) and
+-- the last-modified time both copies agreed on at the last sync. SQLite cannot
+-- add a NOT NULL column without a default; the program always writes both.
+ALTER TABLE cv ADD COLUMN uuid VARCHAR NOT NULL DEFAULT '';
+UPDATE cv SET uuid = lower(
+  hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+  substr(hex(randomblob(2)), 2) || '-' ||
+  substr('89ab', 1 + abs(random()) % 4, 1) || substr(hex(randomblob(2)), 2) || '-' ||
+  hex(randomblob(6))
+);
+CREATE UNIQUE INDEX cv_uuid ON cv (uuid);
+ALTER TABLE cv ADD COLUMN updated_at VARCHAR NOT NULL DEFAULT '';
+UPDATE cv SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
+ALTER TABLE cv ADD COLUMN synced_at VARCHAR;
@@ -0,0 +1,6 @@
+-- The applications deleted, by uuid, and when (RFC 3339, UTC): what `db sync`
+-- propagates as a deletion. A row missing on one side is otherwise new to it.
+CREATE TABLE cv_deleted (
+  uuid VARCHAR PRIMARY KEY NOT NULL,
+  deleted_at VARCHAR NOT NULL
+);
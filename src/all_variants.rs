@@ -0,0 +1,454 @@
+//! `insert --all-variants`: build every catalogued variant from one template
+//! checkout, report their page counts and keep one of them.
+//!
+//! By default the variants are built one after the other in the working
+//! directory (each driver writes its own `<prefix>-<variant>.pdf`). With
+//! `--parallel` each variant is built at the same time in its own copy of the
+//! working directory, in a temporary directory, and its PDF is copied back. The kept variant is the
+//! `--keep` one, else the user's pick among the builds that met the page
+//! contract (no question when only one did).
+
+use crate::command_runner::CommandRunner;
+use crate::file_handlers::{BuildConfig, built_page_count, run_builder};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// What `insert --all-variants` was asked to do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllVariantsRequest {
+    pub parallel: bool,
+    pub keep: Option<String>,
+}
+
+/// How one variant's build went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildStatus {
+    Built,
+    OverLimit,
+    Failed(String),
+}
+
+/// One variant's build, with the page count its transcript reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantBuild {
+    pub variant: String,
+    pub pages: Option<u32>,
+    pub status: BuildStatus,
+}
+
+/// Every variant's build, for printing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildReport {
+    pub max_pages: u32,
+    pub builds: Vec<VariantBuild>,
+}
+
+/// Build `variants` in `cv_dir`, sequentially or each in its own worker copy.
+/// A variant that fails is reported, not fatal: the others still build.
+pub fn build_variants(
+    runner: &(dyn CommandRunner + Sync),
+    cv_dir: &str,
+    variants: &[&str],
+    cfg: &BuildConfig,
+    parallel: bool,
+) -> BuildReport {
+    let builds = if parallel {
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = variants
+                .iter()
+                .map(|variant| {
+                    (
+                        *variant,
+                        scope.spawn(move || build_in_worker(runner, cv_dir, variant, cfg)),
+                    )
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|(variant, worker)| {
+                    worker.join().unwrap_or_else(|_| {
+                        failed(variant, "the build worker panicked".to_string())
+                    })
+                })
+                .collect()
+        })
+    } else {
+        variants
+            .iter()
+            .map(|variant| build_one(runner, cv_dir, variant, cfg))
+            .collect()
+    };
+    BuildReport {
+        max_pages: cfg.max_pages,
+        builds,
+    }
+}
+
+/// How the temporary directory of a `--parallel` worker is named:
+/// `<WORKER_PREFIX><variant>-<random>` under the system temp dir, holding the
+/// copy of the working directory as `<variant>`.
+pub const WORKER_PREFIX: &str = "rusty-cv-worker-";
+
+/// Build `variant` in a copy of `cv_dir` made in a temporary directory, copy
+/// its PDF back into `cv_dir` and remove the copy, unless the build failed:
+/// then the copy is kept, with its logs, and the failure says where.
+fn build_in_worker(
+    runner: &dyn CommandRunner,
+    cv_dir: &str,
+    variant: &str,
+    cfg: &BuildConfig,
+) -> VariantBuild {
+    let scratch = match tempfile::Builder::new()
+        .prefix(&format!("{WORKER_PREFIX}{variant}-"))
+        .tempdir()
+    {
+        Ok(scratch) => scratch,
+        Err(e) => return failed(variant, format!("could not create a worker directory: {e}")),
+    };
+    let worker = scratch.path().join(variant).display().to_string();
+    if let Err(e) = copy_dir::copy_dir(cv_dir, &worker) {
+        return failed(variant, format!("could not copy {cv_dir} to {worker}: {e}"));
+    }
+
+    let mut build = build_one(runner, &worker, variant, cfg);
+    if !matches!(build.status, BuildStatus::Failed(_)) {
+        let pdf = format!("{}-{variant}.pdf", cfg.prefix);
+        if let Err(e) = fs::copy(format!("{worker}/{pdf}"), format!("{cv_dir}/{pdf}")) {
+            build.status = BuildStatus::Failed(format!("could not copy {pdf} back: {e}"));
+        }
+    }
+    if let BuildStatus::Failed(reason) = &mut build.status {
+        let kept = scratch.keep();
+        reason.push_str(&format!(
+            "\nthe worker's files are kept in {}",
+            kept.join(variant).display()
+        ));
+    }
+    build
+}
+
+fn build_one(
+    runner: &dyn CommandRunner,
+    cv_dir: &str,
+    variant: &str,
+    cfg: &BuildConfig,
+) -> VariantBuild {
+    let outcome = match run_builder(runner, cv_dir, variant, cfg) {
+        Ok(outcome) => outcome,
+        Err(e) => return failed(variant, e.to_string()),
+    };
+    let pdf = Path::new(cv_dir).join(format!("{}-{variant}.pdf", cfg.prefix));
+    let pages = built_page_count(&outcome, &pdf, cfg.builder.page_count_strategy());
+    let status = match pages {
+        Some(pages) if pages <= cfg.max_pages => BuildStatus::Built,
+        Some(_) => BuildStatus::OverLimit,
+        None => {
+            BuildStatus::Failed("neither the PDF nor the transcript gave a page count".to_string())
+        }
+    };
+    VariantBuild {
+        variant: variant.to_string(),
+        pages,
+        status,
+    }
+}
+
+fn failed(variant: &str, reason: String) -> VariantBuild {
+    VariantBuild {
+        variant: variant.to_string(),
+        pages: None,
+        status: BuildStatus::Failed(reason),
+    }
+}
+
+impl BuildReport {
+    /// The variant to keep: `keep` when given (it must have met the page
+    /// contract), else the only compliant build, else `pick` among them.
+    pub fn choose<F>(
+        &self,
+        keep: Option<&str>,
+        pick: F,
+    ) -> Result<String, Box<dyn std::error::Error>>
+    where
+        F: FnOnce(&[&str]) -> String,
+    {
+        let built: Vec<&str> = self
+            .builds
+            .iter()
+            .filter(|build| build.status == BuildStatus::Built)
+            .map(|build| build.variant.as_str())
+            .collect();
+
+        if let Some(keep) = keep {
+            return match self.builds.iter().find(|build| build.variant == keep) {
+                Some(build) if build.status == BuildStatus::Built => Ok(keep.to_string()),
+                Some(_) => Err(format!(
+                    "--keep {keep}: that variant did not build within {} pages",
+                    self.max_pages
+                )
+                .into()),
+                None => Err(format!(
+                    "--keep {keep}: not one of the variants built {:?}",
+                    self.builds
+                        .iter()
+                        .map(|build| build.variant.as_str())
+                        .collect::<Vec<_>>()
+                )
+                .into()),
+            };
+        }
+
+        match built.as_slice() {
+            [] => Err(format!("No variant built within {} pages", self.max_pages).into()),
+            [only] => Ok((*only).to_string()),
+            candidates => {
+                let picked = pick(candidates);
+                let picked = picked.trim();
+                if candidates.contains(&picked) {
+                    Ok(picked.to_string())
+                } else {
+                    Err(
+                        format!("'{picked}' is not one of the built variants {candidates:?}")
+                            .into(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for BuildReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Variant builds (limit {} pages):", self.max_pages)?;
+        let width = self
+            .builds
+            .iter()
+            .map(|build| build.variant.len())
+            .max()
+            .unwrap_or(0);
+        for build in &self.builds {
+            let pages = match build.pages {
+                Some(1) => "1 page".to_string(),
+                Some(pages) => format!("{pages} pages"),
+                None => "-".to_string(),
+            };
+            let status = match &build.status {
+                BuildStatus::Built => "ok".to_string(),
+                BuildStatus::OverLimit => "over the limit".to_string(),
+                BuildStatus::Failed(reason) => format!("failed: {reason}"),
+            };
+            // A build failure's summary spans several lines: indent the rest.
+            let mut lines = status.lines();
+            let first = lines.next().unwrap_or_default();
+            writeln!(f, "  {:<width$}  {pages:>8}  {first}", build.variant)?;
+            for line in lines {
+                writeln!(f, "      {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_runner::CommandOutcome;
+    use std::io;
+    use tempfile::TempDir;
+
+    /// Writes `<prefix>-<variant>.pdf` in `cwd` and reports the page count
+    /// configured for the variant; a variant without one fails to build.
+    struct PagesRunner {
+        pages: Vec<(&'static str, u32)>,
+    }
+
+    impl CommandRunner for PagesRunner {
+        fn status(&self, _program: &str, _args: &[&str], _cwd: Option<&str>) -> io::Result<bool> {
+            Ok(true)
+        }
+        fn output(&self, _program: &str, _args: &[&str]) -> io::Result<(bool, String)> {
+            Ok((true, String::new()))
+        }
+        fn spawn(&self, _program: &str, _args: &[&str]) -> io::Result<()> {
+            Ok(())
+        }
+        fn run_capturing(
+            &self,
+            _program: &str,
+            args: &[&str],
+            cwd: Option<&str>,
+        ) -> io::Result<CommandOutcome> {
+            let variant = args.last().copied().unwrap_or_default();
+            let Some((_, pages)) = self.pages.iter().find(|(name, _)| *name == variant) else {
+                return Ok(CommandOutcome {
+                    success: false,
+                    stdout: String::new(),
+                    stderr: "! Undefined control sequence.".to_string(),
+                });
+            };
+            let pdf = format!("TestCV-{variant}.pdf");
+            if let Some(dir) = cwd {
+                fs::write(format!("{dir}/{pdf}"), b"%PDF-1.4")?;
+            }
+            Ok(CommandOutcome {
+                success: true,
+                stdout: format!("Output written on {pdf} ({pages} pages, 1000 bytes).\n"),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    fn cfg() -> BuildConfig {
+        BuildConfig {
+            prefix: "TestCV".to_string(),
+            builder: crate::builder::Builder::from_config("just", None, None, None).unwrap(),
+            max_pages: 2,
+            cover_letter_max_pages: 1,
+            compaction: None,
+        }
+    }
+
+    fn working_dir(variants: &[&str]) -> TempDir {
+        let td = TempDir::new().unwrap();
+        for variant in variants {
+            fs::write(td.path().join(format!("TestCV-{variant}.tex")), "x").unwrap();
+        }
+        td
+    }
+
+    const VARIANTS: [&str; 3] = ["sre", "devops", "manager"];
+
+    fn runner() -> PagesRunner {
+        PagesRunner {
+            pages: vec![("sre", 2), ("devops", 3)],
+        }
+    }
+
+    fn expected_builds() -> Vec<VariantBuild> {
+        vec![
+            VariantBuild {
+                variant: "sre".to_string(),
+                pages: Some(2),
+                status: BuildStatus::Built,
+            },
+            VariantBuild {
+                variant: "devops".to_string(),
+                pages: Some(3),
+                status: BuildStatus::OverLimit,
+            },
+            VariantBuild {
+                variant: "manager".to_string(),
+                pages: None,
+                status: BuildStatus::Failed(
+                    "Error building CV with: just build manager".to_string(),
+                ),
+            },
+        ]
+    }
+
+    /// `builds` are `expected_builds()`, the failure reporting the summary of
+    /// the failed build.
+    fn assert_expected_builds(builds: &[VariantBuild]) {
+        let expected = expected_builds();
+        assert_eq!(builds[..2], expected[..2]);
+        assert_eq!(
+            (builds[2].variant.as_str(), builds[2].pages),
+            ("manager", None)
+        );
+        assert!(
+            matches!(&builds[2].status, BuildStatus::Failed(reason)
+                if reason.starts_with("Error building CV with: just build manager\n")),
+            "got: {:?}",
+            builds[2].status
+        );
+    }
+
+    #[test]
+    fn test_sequential_builds_report_every_variant() {
+        let td = working_dir(&VARIANTS);
+        let dir = td.path().to_str().unwrap();
+
+        let report = build_variants(&runner(), dir, &VARIANTS, &cfg(), false);
+
+        assert_expected_builds(&report.builds);
+        assert!(td.path().join("TestCV-sre.pdf").is_file());
+        let printed = report.to_string();
+        assert!(printed.contains("sre       2 pages  ok"), "got: {printed}");
+        assert!(printed.contains("over the limit"), "got: {printed}");
+    }
+
+    #[test]
+    fn test_parallel_builds_use_worker_copies_and_bring_the_pdfs_back() {
+        let td = TempDir::new().unwrap();
+        let dir = td.path().join("work");
+        fs::create_dir(&dir).unwrap();
+        for variant in VARIANTS {
+            fs::write(dir.join(format!("TestCV-{variant}.tex")), "x").unwrap();
+        }
+        let dir = dir.to_str().unwrap();
+
+        let report = build_variants(&runner(), dir, &VARIANTS, &cfg(), true);
+
+        assert_expected_builds(&report.builds);
+        assert!(Path::new(dir).join("TestCV-sre.pdf").is_file());
+        assert!(Path::new(dir).join("TestCV-devops.pdf").is_file());
+        // No worker lands next to the working directory.
+        assert_eq!(fs::read_dir(td.path()).unwrap().count(), 1);
+        // The failed build's copy is kept, and the failure says where.
+        let BuildStatus::Failed(reason) = &report.builds[2].status else {
+            panic!("the manager variant failed")
+        };
+        let kept = reason
+            .lines()
+            .last()
+            .and_then(|line| line.strip_prefix("the worker's files are kept in "))
+            .map(Path::new)
+            .unwrap();
+        assert!(kept.join("TestCV-manager.tex").is_file());
+        fs::remove_dir_all(kept.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_choose_keeps_the_only_compliant_build_without_asking() {
+        let report = BuildReport {
+            max_pages: 2,
+            builds: expected_builds(),
+        };
+
+        let chosen = report
+            .choose(None, |_| panic!("nothing to pick between"))
+            .unwrap();
+
+        assert_eq!(chosen, "sre");
+    }
+
+    #[test]
+    fn test_choose_asks_between_compliant_builds_and_validates_keep() {
+        let mut builds = expected_builds();
+        builds[1].status = BuildStatus::Built;
+        let report = BuildReport {
+            max_pages: 3,
+            builds,
+        };
+
+        let chosen = report
+            .choose(None, |candidates| {
+                assert_eq!(candidates, ["sre", "devops"]);
+                "devops\n".to_string()
+            })
+            .unwrap();
+        assert_eq!(chosen, "devops");
+
+        assert_eq!(
+            report.choose(Some("sre"), |_| unreachable!()).unwrap(),
+            "sre"
+        );
+        let err = report
+            .choose(Some("manager"), |_| unreachable!())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("did not build within 3 pages"), "got: {err}");
+        assert!(report.choose(Some("data"), |_| unreachable!()).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! `application.tex`: the per-application data a template can `\input`.
+//!
+//! Written into the working copy of the template before every build, it
+//! defines `\company`, `\jobtitle`, `\applicationquote` and `\applicationdate`
+//! with the values of this application, escaped for LaTeX (the quote is not
+//! `\quote`: that name belongs to the `quote` environment). A template reads
+//! them with `\InputIfFileExists{application}{}{}`, so it still builds on its
+//! own, and uses e.g. `\jobtitle{} at \company{}` in a header.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the generated macro file, at the root of the working dir.
+pub const APPLICATION_TEX: &str = "application.tex";
+
+/// The application a CV is built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplicationDetails<'a> {
+    pub job_title: &'a str,
+    pub company: &'a str,
+    pub quote: &'a str,
+    pub application_date: &'a str,
+}
+
+/// The content of `application.tex` for `details`.
+///
+/// Each macro is `\providecommand`ed first and then `\renewcommand`ed, so the
+/// file neither clashes with a class that already defines, say, `\company`,
+/// nor fails when it does not.
+pub fn render_application_tex(details: &ApplicationDetails) -> String {
+    let macros = [
+        ("company", details.company),
+        ("jobtitle", details.job_title),
+        ("applicationquote", details.quote),
+        ("applicationdate", details.application_date.trim()),
+    ];
+    let mut tex = String::from("% Generated by rusty_cv_creator for this application.\n");
+    for (name, value) in macros {
+        tex.push_str(&macro_line(name, value));
+        tex.push('\n');
+    }
+    tex
+}
+
+/// `\providecommand` then `\renewcommand` of `\<name>` as `value`, escaped.
+fn macro_line(name: &str, value: &str) -> String {
+    format!(
+        "\\providecommand{{\\{name}}}{{}}\\renewcommand{{\\{name}}}{{{}}}",
+        escape_latex(value)
+    )
+}
+
+/// Set one more macro, `\<name>`, to `value` in the `application.tex` of
+/// `cv_dir`, replacing an earlier definition of it (or creating the file).
+pub fn write_application_macro(
+    cv_dir: &Path,
+    name: &str,
+    value: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = cv_dir.join(APPLICATION_TEX);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let definition = format!("\\providecommand{{\\{name}}}");
+    let mut tex: String = existing
+        .lines()
+        .filter(|line| !line.starts_with(&definition))
+        .flat_map(|line| [line, "\n"])
+        .collect();
+    tex.push_str(&macro_line(name, value));
+    tex.push('\n');
+    fs::write(&path, tex).map_err(|e| format!("Could not write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// Write `application.tex` into the working directory `cv_dir`.
+pub fn write_application_tex(
+    cv_dir: &Path,
+    details: &ApplicationDetails,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = cv_dir.join(APPLICATION_TEX);
+    fs::write(&path, render_application_tex(details))
+        .map_err(|e| format!("Could not write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// Escape `text` so LaTeX typesets it verbatim. Line breaks become spaces: a
+/// blank line would end the macro argument's paragraph.
+pub fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '<' => escaped.push_str(r"\textless{}"),
+            '>' => escaped.push_str(r"\textgreater{}"),
+            '\r' | '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_escape_latex_special_characters() {
+        assert_eq!(
+            escape_latex(r"R&D 100% $5 #1 a_b {x} ~ ^ \ <>"),
+            r"R\&D 100\% \$5 \#1 a\_b \{x\} \textasciitilde{} \textasciicircum{} \textbackslash{} \textless{}\textgreater{}"
+        );
+        assert_eq!(escape_latex("two\nlines"), "two lines");
+        assert_eq!(escape_latex("Zürich"), "Zürich");
+    }
+
+    #[test]
+    fn test_render_defines_every_macro() {
+        let tex = render_application_tex(&ApplicationDetails {
+            job_title: "C# Developer",
+            company: "Smith & Sons",
+            quote: "",
+            application_date: " 8-Oct-2026",
+        });
+
+        assert!(tex.contains(r"\providecommand{\company}{}\renewcommand{\company}{Smith \& Sons}"));
+        assert!(tex.contains(r"\renewcommand{\jobtitle}{C\# Developer}"));
+        assert!(tex.contains(r"\renewcommand{\applicationquote}{}"));
+        assert!(tex.contains(r"\renewcommand{\applicationdate}{8-Oct-2026}"));
+    }
+
+    #[test]
+    fn test_write_application_tex_into_the_working_dir() {
+        let td = TempDir::new().unwrap();
+        let details = ApplicationDetails {
+            job_title: "SRE",
+            company: "ACME",
+            quote: "Ship it",
+            application_date: "18-Oct-2026",
+        };
+
+        let path = write_application_tex(td.path(), &details).unwrap();
+
+        assert_eq!(path, td.path().join(APPLICATION_TEX));
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            render_application_tex(&details)
+        );
+        assert!(write_application_tex(&td.path().join("missing"), &details).is_err());
+    }
+
+    #[test]
+    fn test_write_application_macro_replaces_its_earlier_definition() {
+        let td = TempDir::new().unwrap();
+        let details = ApplicationDetails {
+            job_title: "SRE",
+            company: "ACME",
+            quote: "",
+            application_date: "18-Oct-2026",
+        };
+        write_application_tex(td.path(), &details).unwrap();
+
+        write_application_macro(td.path(), "compactlevel", "1").unwrap();
+        let path = write_application_macro(td.path(), "compactlevel", "2").unwrap();
+
+        let tex = fs::read_to_string(path).unwrap();
+        assert!(tex.starts_with(&render_application_tex(&details)));
+        assert_eq!(tex.matches(r"\providecommand{\compactlevel}").count(), 1);
+        assert!(tex.ends_with("\\renewcommand{\\compactlevel}{2}\n"));
+    }
+}
@@ -0,0 +1,225 @@
+//! What is kept of a working directory after its PDFs are published.
+//!
+//! By default the working copy of the template is removed once the PDFs are
+//! copied out. `[build] keep_artifacts` archives part of it first, as
+//! `<published pdf>-artifacts.tar.gz` next to the CV, so the exact sources
+//! and logs that produced a CV that was sent stay available for an audit:
+//!
+//! - `none` (default): nothing;
+//! - `log-only`: the `.log` files of the build;
+//! - `all`: the whole working directory (without `.git`): sources, `.aux`,
+//!   logs and the PDFs.
+//!
+//! `insert --keep-workdir` keeps the working directory itself.
+
+use crate::config_parse::get_variable_from_config_file;
+use crate::global_conf::AppContext;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `[build] keep_artifacts`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeepArtifacts {
+    #[default]
+    None,
+    LogOnly,
+    All,
+}
+
+impl KeepArtifacts {
+    pub fn from_context(ctx: &AppContext) -> Result<Self, Box<dyn std::error::Error>> {
+        match get_variable_from_config_file(ctx, "build", "keep_artifacts") {
+            Ok(value) => Self::parse(&value),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match value.trim() {
+            "" | "none" => Ok(Self::None),
+            "log-only" => Ok(Self::LogOnly),
+            "all" => Ok(Self::All),
+            other => Err(format!(
+                "[build] keep_artifacts must be none, log-only or all, not '{other}'"
+            )
+            .into()),
+        }
+    }
+
+    /// Whether `relative` (a file of the working directory) is archived.
+    fn keeps(self, relative: &Path) -> bool {
+        match self {
+            Self::None => false,
+            Self::LogOnly => relative.extension().is_some_and(|ext| ext == "log"),
+            Self::All => true,
+        }
+    }
+}
+
+/// The archive of the artifacts of the CV published at `published_pdf`.
+pub fn artifacts_archive_path(published_pdf: &str) -> String {
+    let stem = published_pdf.strip_suffix(".pdf").unwrap_or(published_pdf);
+    format!("{stem}-artifacts.tar.gz")
+}
+
+/// Archive the files of `working_dir` that `policy` keeps into `archive`, a
+/// gzipped tarball. Returns `None`, writing nothing, when no file is kept.
+pub fn archive_artifacts(
+    working_dir: &Path,
+    policy: KeepArtifacts,
+    archive: &Path,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    if policy == KeepArtifacts::None {
+        return Ok(None);
+    }
+    let mut files = Vec::new();
+    collect_files(working_dir, Path::new(""), &mut files)?;
+    files.retain(|relative| policy.keeps(relative));
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let gzip = GzEncoder::new(fs::File::create(archive)?, Compression::default());
+    let mut tar = tar::Builder::new(gzip);
+    for relative in &files {
+        tar.append_path_with_name(working_dir.join(relative), relative)?;
+    }
+    tar.into_inner()?.finish()?;
+    Ok(Some(archive.to_path_buf()))
+}
+
+/// The regular files under `dir`, relative to the working directory, sorted,
+/// without the template's `.git`.
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(root.join(dir))?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+    for entry in entries {
+        let relative = dir.join(entry.file_name());
+        let kind = entry.file_type()?;
+        if kind.is_dir() && entry.file_name() != ".git" {
+            collect_files(root, &relative, files)?;
+        } else if kind.is_file() {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    /// The names and contents of the files in the gzipped tarball `archive`.
+    fn read_archive(archive: &Path) -> Vec<(String, String)> {
+        let mut tar = tar::Archive::new(GzDecoder::new(fs::File::open(archive).unwrap()));
+        tar.entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().to_string_lossy().into_owned();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (name, content)
+            })
+            .collect()
+    }
+
+    fn working_dir() -> TempDir {
+        let td = TempDir::new().unwrap();
+        fs::write(td.path().join("CV-senior-sre.tex"), "\\documentclass{cv}").unwrap();
+        fs::write(td.path().join("CV-senior-sre.log"), "This is XeTeX").unwrap();
+        fs::write(td.path().join("CV-senior-sre.aux"), "\\relax").unwrap();
+        fs::create_dir_all(td.path().join("sections").join("deep")).unwrap();
+        fs::write(td.path().join("sections/deep/work.tex"), "work").unwrap();
+        fs::create_dir(td.path().join(".git")).unwrap();
+        fs::write(td.path().join(".git/HEAD"), "ref").unwrap();
+        td
+    }
+
+    #[test]
+    fn test_keep_artifacts_parses_the_policy() {
+        assert_eq!(KeepArtifacts::parse("none").unwrap(), KeepArtifacts::None);
+        assert_eq!(
+            KeepArtifacts::parse(" log-only").unwrap(),
+            KeepArtifacts::LogOnly
+        );
+        assert_eq!(KeepArtifacts::parse("all").unwrap(), KeepArtifacts::All);
+        assert!(
+            KeepArtifacts::parse("logs")
+                .unwrap_err()
+                .to_string()
+                .contains("none, log-only or all")
+        );
+    }
+
+    #[test]
+    fn test_archive_all_keeps_the_sources_but_not_git() {
+        let td = working_dir();
+        let out = TempDir::new().unwrap();
+        let archive = out.path().join("cv-artifacts.tar.gz");
+
+        let written = archive_artifacts(td.path(), KeepArtifacts::All, &archive).unwrap();
+
+        assert_eq!(written.as_deref(), Some(archive.as_path()));
+        assert_eq!(
+            read_archive(&archive),
+            [
+                ("CV-senior-sre.aux", "\\relax"),
+                ("CV-senior-sre.log", "This is XeTeX"),
+                ("CV-senior-sre.tex", "\\documentclass{cv}"),
+                ("sections/deep/work.tex", "work"),
+            ]
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_archive_log_only_and_none() {
+        let td = working_dir();
+        let out = TempDir::new().unwrap();
+        let archive = out.path().join("cv-artifacts.tar.gz");
+
+        archive_artifacts(td.path(), KeepArtifacts::LogOnly, &archive).unwrap();
+        assert_eq!(
+            read_archive(&archive),
+            [("CV-senior-sre.log".to_string(), "This is XeTeX".to_string())]
+        );
+
+        let none = out.path().join("none.tar.gz");
+        assert_eq!(
+            archive_artifacts(td.path(), KeepArtifacts::None, &none).unwrap(),
+            None
+        );
+        assert!(!none.exists());
+    }
+
+    #[test]
+    fn test_long_names_survive_the_archive() {
+        let td = TempDir::new().unwrap();
+        let dir = td.path().join("a".repeat(120));
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join(format!("{}.log", "b".repeat(160))), "log").unwrap();
+        let archive = td.path().join("out.tar.gz");
+
+        archive_artifacts(td.path(), KeepArtifacts::LogOnly, &archive).unwrap();
+
+        assert_eq!(
+            read_archive(&archive)[0].0,
+            format!("{}/{}.log", "a".repeat(120), "b".repeat(160))
+        );
+    }
+
+    #[test]
+    fn test_artifacts_archive_sits_next_to_the_pdf() {
+        assert_eq!(
+            artifacts_archive_path("/out/2026/2026-10-18-SRE-ACME.pdf"),
+            "/out/2026/2026-10-18-SRE-ACME-artifacts.tar.gz"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! The backends that turn a driver file into `<prefix>-<variant>.pdf`.
+//!
+//! `[build] builder` names the backend. `tectonic`, `latexmk` and `typst` are
+//! run directly on the driver; `command` runs the `[build] command` template;
+//! any other value is a recipe runner (`just` by default, `make`, ...) invoked
+//! as `<builder> [<page_count_probe>] <recipe> <variant>`, which is how the
+//! tool has always built. Every backend runs in the working directory and
+//! leaves the PDF next to the driver, so the rest of the build (page contract,
+//! publishing) is the same for all of them.
+
+use crate::command_runner::PlannedCommand;
+use std::fmt;
+
+/// The placeholders a `[build] command` template may use.
+pub const COMMAND_PLACEHOLDERS: [&str; 3] = ["{variant}", "{prefix}", "{outdir}"];
+
+/// How a backend's page count is read, see `built_page_count`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageCountStrategy {
+    /// Count the pages of the PDF; when it cannot be read, fall back to the
+    /// `Output written on ... (N pages` line of the TeX transcript.
+    PdfThenTranscript,
+    /// Only the PDF: the backend prints no TeX transcript (typst).
+    PdfOnly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Builder {
+    /// `<program> [<probe>] <recipe> <variant>`: a Justfile (or Makefile, ...)
+    /// recipe. The probe (`tectonic=tectonic --print` for the default Justfile)
+    /// routes the TeX transcript to the captured output; empty to pass none.
+    Recipe {
+        program: String,
+        recipe: String,
+        page_count_probe: String,
+    },
+    /// `tectonic --keep-logs --print <prefix>-<variant>.tex`: the `.log` is
+    /// kept for the diagnostics of a failed build.
+    Tectonic,
+    /// `latexmk -pdf -interaction=nonstopmode -halt-on-error <prefix>-<variant>.tex`
+    Latexmk,
+    /// `typst compile <prefix>-<variant>.typ <prefix>-<variant>.pdf`
+    Typst,
+    /// A command template, split on whitespace (no shell quoting), with
+    /// `{variant}`, `{prefix}` and `{outdir}` replaced in every argument.
+    Command(String),
+}
+
+impl Builder {
+    /// The backend named by `[build] builder`, with the settings it reads.
+    pub fn from_config(
+        name: &str,
+        recipe: Option<String>,
+        page_count_probe: Option<String>,
+        command: Option<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = name.trim();
+        Ok(match name {
+            "tectonic" => Self::Tectonic,
+            "latexmk" => Self::Latexmk,
+            "typst" => Self::Typst,
+            "command" => {
+                let template = command
+                    .filter(|command| !command.trim().is_empty())
+                    .ok_or("[build] builder = command needs a [build] command template")?;
+                validate_command_template(&template)?;
+                Self::Command(template)
+            }
+            "" => return Err("[build] builder is empty".into()),
+            program => Self::Recipe {
+                program: program.to_string(),
+                recipe: recipe.unwrap_or_else(|| "build".to_string()),
+                page_count_probe: page_count_probe
+                    .unwrap_or_else(|| "tectonic=tectonic --print".to_string()),
+            },
+        })
+    }
+
+    /// The driver file of `variant`, relative to the working directory.
+    pub fn driver_file(&self, prefix: &str, variant: &str) -> String {
+        match self {
+            Self::Typst => format!("{prefix}-{variant}.typ"),
+            _ => format!("{prefix}-{variant}.tex"),
+        }
+    }
+
+    /// The command that builds `variant` in the working directory `outdir`.
+    pub fn command(&self, prefix: &str, variant: &str, outdir: &str) -> PlannedCommand {
+        let driver = self.driver_file(prefix, variant);
+        let (program, args): (&str, Vec<String>) = match self {
+            Self::Recipe {
+                program,
+                recipe,
+                page_count_probe,
+            } => (
+                program,
+                [page_count_probe.as_str(), recipe, variant]
+                    .into_iter()
+                    .filter(|arg| !arg.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            ),
+            Self::Tectonic => (
+                "tectonic",
+                vec!["--keep-logs".to_string(), "--print".to_string(), driver],
+            ),
+            Self::Latexmk => (
+                "latexmk",
+                vec![
+                    "-pdf".to_string(),
+                    "-interaction=nonstopmode".to_string(),
+                    "-halt-on-error".to_string(),
+                    driver,
+                ],
+            ),
+            Self::Typst => (
+                "typst",
+                vec![
+                    "compile".to_string(),
+                    driver,
+                    format!("{prefix}-{variant}.pdf"),
+                ],
+            ),
+            Self::Command(template) => {
+                let mut words = template.split_whitespace().map(|word| {
+                    word.replace("{variant}", variant)
+                        .replace("{prefix}", prefix)
+                        .replace("{outdir}", outdir)
+                });
+                let program = words.next().unwrap_or_default();
+                let args: Vec<String> = words.collect();
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                return PlannedCommand::new(&program, &args, Some(outdir));
+            }
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        PlannedCommand::new(program, &args, Some(outdir))
+    }
+
+    /// The tools that must be on PATH before this backend can build, for
+    /// `ensure_tools_available`. The `just` recipe runs tectonic itself.
+    pub fn required_tools(&self) -> Vec<&str> {
+        match self {
+            Self::Recipe { program, .. } if program == "just" => vec!["just", "tectonic"],
+            Self::Recipe { program, .. } => vec![program.as_str()],
+            Self::Tectonic => vec!["tectonic"],
+            Self::Latexmk => vec!["latexmk"],
+            Self::Typst => vec!["typst"],
+            Self::Command(template) => template.split_whitespace().take(1).collect(),
+        }
+    }
+
+    pub fn page_count_strategy(&self) -> PageCountStrategy {
+        match self {
+            Self::Typst => PageCountStrategy::PdfOnly,
+            _ => PageCountStrategy::PdfThenTranscript,
+        }
+    }
+}
+
+/// A command template must name a program, build a different PDF per variant
+/// and use no placeholder but `COMMAND_PLACEHOLDERS`.
+fn validate_command_template(template: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !template.contains("{variant}") {
+        return Err(format!(
+            "[build] command '{template}' must contain {{variant}}: every variant builds its own PDF"
+        )
+        .into());
+    }
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end + 1)
+            .ok_or_else(|| format!("[build] command '{template}' has an unclosed '{{'"))?;
+        let placeholder = &rest[start..end];
+        if !COMMAND_PLACEHOLDERS.contains(&placeholder) {
+            return Err(format!(
+                "[build] command '{template}' uses the unknown placeholder {placeholder} \
+                 (expected one of {COMMAND_PLACEHOLDERS:?})"
+            )
+            .into());
+        }
+        rest = &rest[end..];
+    }
+    Ok(())
+}
+
+impl fmt::Display for Builder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Recipe {
+                program, recipe, ..
+            } => write!(f, "{program} {recipe}"),
+            Self::Tectonic => write!(f, "tectonic"),
+            Self::Latexmk => write!(f, "latexmk"),
+            Self::Typst => write!(f, "typst"),
+            Self::Command(template) => write!(f, "{template}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn just() -> Builder {
+        Builder::from_config("just", None, None, None).unwrap()
+    }
+
+    #[test]
+    fn test_recipe_builder_keeps_the_justfile_invocation() {
+        assert_eq!(
+            just().command("TestCV", "senior-sre", "/w").to_string(),
+            "just 'tectonic=tectonic --print' build senior-sre  (in /w)"
+        );
+        assert_eq!(just().required_tools(), ["just", "tectonic"]);
+
+        let make = Builder::from_config("make", Some("pdf".to_string()), Some(String::new()), None)
+            .unwrap();
+        assert_eq!(make.command("TestCV", "sre", "/w").args, ["pdf", "sre"]);
+        assert_eq!(make.required_tools(), ["make"]);
+    }
+
+    #[test]
+    fn test_direct_backends_build_the_driver() {
+        let tectonic = Builder::from_config("tectonic", None, None, None).unwrap();
+        assert_eq!(
+            tectonic.command("TestCV", "sre", "/w").to_string(),
+            "tectonic --keep-logs --print TestCV-sre.tex  (in /w)"
+        );
+        assert_eq!(tectonic.required_tools(), ["tectonic"]);
+
+        let latexmk = Builder::from_config("latexmk", None, None, None).unwrap();
+        assert_eq!(
+            latexmk.command("TestCV", "sre", "/w").args.last().unwrap(),
+            "TestCV-sre.tex"
+        );
+        assert_eq!(latexmk.required_tools(), ["latexmk"]);
+
+        let typst = Builder::from_config("typst", None, None, None).unwrap();
+        assert_eq!(typst.driver_file("TestCV", "sre"), "TestCV-sre.typ");
+        assert_eq!(
+            typst.command("TestCV", "sre", "/w").args,
+            ["compile", "TestCV-sre.typ", "TestCV-sre.pdf"]
+        );
+        assert_eq!(typst.page_count_strategy(), PageCountStrategy::PdfOnly);
+        assert_eq!(
+            tectonic.page_count_strategy(),
+            PageCountStrategy::PdfThenTranscript
+        );
+    }
+
+    #[test]
+    fn test_command_template_fills_its_placeholders() {
+        let builder = Builder::from_config(
+            "command",
+            None,
+            None,
+            Some("xelatex -output-directory={outdir} {prefix}-{variant}.tex".to_string()),
+        )
+        .unwrap();
+
+        let command = builder.command("TestCV", "sre", "/tmp/my cv");
+
+        assert_eq!(command.program, "xelatex");
+        assert_eq!(
+            command.args,
+            ["-output-directory=/tmp/my cv", "TestCV-sre.tex"]
+        );
+        assert_eq!(command.cwd.as_deref(), Some("/tmp/my cv"));
+        assert_eq!(builder.required_tools(), ["xelatex"]);
+    }
+
+    #[test]
+    fn test_command_template_is_validated() {
+        let load = |command: Option<&str>| {
+            Builder::from_config("command", None, None, command.map(str::to_string))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(load(None).contains("needs a [build] command"));
+        assert!(load(Some("make pdf")).contains("must contain {variant}"));
+        assert!(load(Some("make {variant} {outdir")).contains("unclosed"));
+        assert!(load(Some("make {variant} {job}")).contains("unknown placeholder {job}"));
+        assert!(Builder::from_config("", None, None, None).is_err());
+    }
+}
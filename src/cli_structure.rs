@@ -1,9 +1,20 @@
 use crate::config_parse::connect_db;
+use crate::db_command::run_db_command;
 use crate::global_conf::AppContext;
-use crate::{cv_insert::insert_cv, user_action::remove_cv};
-use chrono::NaiveDate;
-use clap::{Args, Parser, Subcommand};
+use crate::list_output::render_applications;
+use crate::preview::run_preview;
+use crate::{
+    cv_insert::insert_cv, cv_status::set_application_status, cv_update::update_cv,
+    user_action::remove_cv,
+};
+use chrono::{Datelike, Month, NaiveDate};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use log::error;
+use rusty_cv_creator::database::{ApplicationFilter, DateFilter};
+use rusty_cv_creator::status::ApplicationStatus;
+use rusty_cv_creator::sync::SyncSide;
+use rusty_cv_creator::transfer::TransferFormat;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug, Clone)]
 #[command(version, about, long_about = None)]
@@ -54,13 +65,22 @@ pub enum UserAction {
     Insert(InsertArgs),
 
     #[command(about = "Update CV", long_about = None)]
-    Update(FilterArgs),
+    Update(UpdateArgs),
 
     #[command(about = "Remove CV", long_about = None)]
-    Remove(FilterArgs),
+    Remove(RemoveArgs),
 
     #[command(about = "List CVs", long_about = None)]
-    List(FilterArgs),
+    List(ListArgs),
+
+    #[command(about = "Move an application to another status", long_about = None)]
+    Status(StatusArgs),
+
+    #[command(about = "Build a variant into a scratch dir and rebuild it on every template change", long_about = None)]
+    Preview(PreviewArgs),
+
+    #[command(about = "Manage the applications database", long_about = None)]
+    Db(DbArgs),
 }
 
 /// Required arguments for `insert`: a CV cannot be built without a job title and
@@ -78,11 +98,42 @@ pub struct InsertArgs {
     #[arg(short, long)]
     pub quote: Option<String>,
 
-    /// Which CV variant to build (senior-devops, senior-platform-engineer,
-    /// senior-sre, engineering-manager). When omitted, it is inferred from the
-    /// job title, falling back to the configured default.
+    /// Which CV variant to build, from the variant catalogue (built in:
+    /// senior-devops, senior-platform-engineer, senior-sre, engineering-manager).
+    /// When omitted, it is inferred from the job title, falling back to the
+    /// configured default.
     #[arg(long)]
     pub variant: Option<String>,
+
+    /// A saved job posting (plain text or HTML). Every variant is scored
+    /// against it and the best one is built (unless --variant is given); the
+    /// posting's key terms the CV does not mention are reported.
+    #[arg(long, value_name = "FILE")]
+    pub job_description: Option<PathBuf>,
+
+    /// Also build the cover letter (`<prefix>-cover-<variant>.tex` in the
+    /// template) and publish it next to the CV
+    #[arg(long, default_value_t = false)]
+    pub cover_letter: bool,
+
+    /// Build every variant of the catalogue, report their page counts and
+    /// keep one (--keep, else a pick among those within the page limit)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["variant", "job_description"])]
+    pub all_variants: bool,
+
+    /// With --all-variants: build the variants at the same time, each in its
+    /// own copy of the working directory
+    #[arg(long, default_value_t = false, requires = "all_variants")]
+    pub parallel: bool,
+
+    /// With --all-variants: the variant to keep and record
+    #[arg(long, value_name = "VARIANT", requires = "all_variants")]
+    pub keep: Option<String>,
+
+    /// Keep the working directory (the template copy the CV was built in)
+    /// instead of removing it once the PDFs are published
+    #[arg(long, default_value_t = false)]
+    pub keep_workdir: bool,
 }
 
 impl From<InsertArgs> for FilterArgs {
@@ -97,6 +148,247 @@ impl From<InsertArgs> for FilterArgs {
     }
 }
 
+/// Arguments for `update`: which stored application to edit (`--id`, or the
+/// usual filters followed by an interactive pick) and the new values to write.
+/// Every `--new-*` value is optional; only the ones given are changed.
+#[derive(Args, Debug, Clone, Default)]
+pub struct UpdateArgs {
+    /// Database id of the application to update; skips the interactive pick.
+    #[arg(long)]
+    pub id: Option<i32>,
+
+    #[command(flatten)]
+    pub filters: FilterArgs,
+
+    #[arg(long)]
+    pub new_job_title: Option<String>,
+
+    #[arg(long)]
+    pub new_company_name: Option<String>,
+
+    #[arg(long)]
+    pub new_quote: Option<String>,
+
+    /// New application date, as `YYYY-MM-DD` or `D-Mon-YYYY`.
+    #[arg(long)]
+    pub new_date: Option<String>,
+
+    /// Point the application at a different PDF file.
+    #[arg(long)]
+    pub new_pdf_path: Option<String>,
+
+    /// Variant to rebuild with (only used together with `--rebuild`).
+    #[arg(long, requires = "rebuild")]
+    pub new_variant: Option<String>,
+
+    /// Rebuild the PDF from the template with the updated job title / company
+    /// (and `--new-variant`), and store the new PDF path and variant.
+    #[arg(long, default_value_t = false, conflicts_with = "new_pdf_path")]
+    pub rebuild: bool,
+}
+
+/// How `list --format` prints the applications.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Aligned plain-text columns.
+    Table,
+    /// One JSON array of application objects.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Newline-delimited JSON: one application object per line.
+    Ndjson,
+}
+
+/// Arguments for `list`: the usual filters, and an optional output format.
+/// Without `--format` the interactive TUI is launched.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ListArgs {
+    #[command(flatten)]
+    pub filters: FilterArgs,
+
+    /// Print the matching applications to stdout instead of opening the TUI,
+    /// so they can be piped into jq, a spreadsheet or a script.
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+}
+
+/// Arguments for `remove`: the usual filters narrow the candidates; `--yes`
+/// removes a single unambiguous match without the interactive pick.
+#[derive(Args, Debug, Clone, Default)]
+pub struct RemoveArgs {
+    #[command(flatten)]
+    pub filters: FilterArgs,
+
+    /// Remove without asking when exactly one application matches the filters.
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+}
+
+/// Arguments for `status`: the stage to move to, and which stored application
+/// to move (`--id`, or the usual filters followed by an interactive pick).
+#[derive(Args, Debug, Clone, Default)]
+pub struct StatusArgs {
+    /// New status: drafted, sent, screening, interview, offer, rejected,
+    /// withdrawn or ghosted.
+    pub status: ApplicationStatus,
+
+    /// Database id of the application; skips the interactive pick.
+    #[arg(long)]
+    pub id: Option<i32>,
+
+    #[command(flatten)]
+    pub filters: FilterArgs,
+
+    /// Allow a move the lifecycle forbids (e.g. to correct a mistaken one).
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+}
+
+/// Arguments for `preview`: which variant to build, the sample application
+/// written to `application.tex`, and how to watch the template.
+#[derive(Args, Debug, Clone, Default)]
+pub struct PreviewArgs {
+    /// Variant to preview; when omitted it is inferred from the job title,
+    /// falling back to the configured default.
+    #[arg(long)]
+    pub variant: Option<String>,
+
+    #[arg(short, long, default_value_t = String::from("Job Title"))]
+    pub job_title: String,
+
+    #[arg(short, long, default_value_t = String::from("Company"))]
+    pub company_name: String,
+
+    #[arg(short, long)]
+    pub quote: Option<String>,
+
+    /// Build once and exit instead of watching the template.
+    #[arg(long, default_value_t = false)]
+    pub once: bool,
+
+    /// How often the template is checked for changes, in milliseconds.
+    #[arg(long, default_value_t = 500)]
+    pub poll_ms: u64,
+}
+
+impl From<PreviewArgs> for FilterArgs {
+    fn from(args: PreviewArgs) -> Self {
+        FilterArgs {
+            job_title: Some(args.job_title),
+            company_name: Some(args.company_name),
+            quote: args.quote,
+            date: None,
+            variant: args.variant,
+        }
+    }
+}
+
+/// Arguments for `db`: which database chore to run.
+#[derive(Args, Debug, Clone)]
+pub struct DbArgs {
+    #[command(subcommand)]
+    pub command: DbCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DbCommand {
+    #[command(about = "Create the SQLite database and its schema, and report where it lives", long_about = None)]
+    Init,
+
+    #[command(about = "Apply the pending schema migrations", long_about = None)]
+    Migrate(MigrateArgs),
+
+    #[command(about = "Reconcile the local SQLite database with the shared PostgreSQL one", long_about = None)]
+    Sync(SyncArgs),
+
+    #[command(about = "Save the applications whose database save failed earlier", long_about = None)]
+    FlushPending,
+
+    #[command(about = "Export every application as JSON or CSV, for a backup or another database", long_about = None)]
+    Export(ExportArgs),
+
+    #[command(about = "Import the applications of an export, skipping those already stored", long_about = None)]
+    Import(ImportArgs),
+}
+
+/// Arguments for `db migrate`.
+#[derive(Args, Debug, Clone, Default)]
+pub struct MigrateArgs {
+    /// List the migrations and whether each is applied, without applying any.
+    #[arg(long, default_value_t = false)]
+    pub status: bool,
+}
+
+/// Arguments for `db sync`. The global `--dry-run` reports without writing.
+#[derive(Args, Debug, Clone, Default)]
+pub struct SyncArgs {
+    /// Settle the conflicts (applications changed on both sides since the
+    /// last sync) in favour of this side; without it they are only reported.
+    #[arg(long, value_enum)]
+    pub prefer: Option<SyncPreference>,
+}
+
+/// Which copy wins a `db sync` conflict.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPreference {
+    /// The local SQLite copy.
+    Local,
+    /// The shared PostgreSQL copy.
+    Remote,
+}
+
+impl From<SyncPreference> for SyncSide {
+    fn from(preference: SyncPreference) -> Self {
+        match preference {
+            SyncPreference::Local => SyncSide::Local,
+            SyncPreference::Remote => SyncSide::Remote,
+        }
+    }
+}
+
+/// The file format of `db export` and `db import`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A versioned JSON document.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+impl From<ExportFormat> for TransferFormat {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Json => TransferFormat::Json,
+            ExportFormat::Csv => TransferFormat::Csv,
+        }
+    }
+}
+
+/// Arguments for `db export`.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ExportArgs {
+    /// The file format. Default: csv for an `--output` ending in `.csv`, else
+    /// json.
+    #[arg(short, long, value_enum)]
+    pub format: Option<ExportFormat>,
+
+    /// Write the export to this file instead of standard output.
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+/// Arguments for `db import`. The global `--dry-run` reports without writing.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ImportArgs {
+    /// The export to read.
+    pub file: String,
+
+    /// The file format. Default: csv for a file ending in `.csv`, else json.
+    #[arg(short, long, value_enum)]
+    pub format: Option<ExportFormat>,
+}
+
 #[derive(Args, Debug, Clone, Default)]
 pub struct FilterArgs {
     #[arg(short, long)]
@@ -108,16 +400,31 @@ pub struct FilterArgs {
     #[arg(short, long)]
     pub quote: Option<String>,
 
+    /// Application date: YYYY, YYYY-MM, YYYY-MM-DD or a month name (e.g. May).
     #[arg(short, long)]
     pub date: Option<String>,
 
-    /// Which CV variant to build (senior-devops, senior-platform-engineer,
-    /// senior-sre, engineering-manager). When omitted, it is inferred from the
-    /// job title, falling back to the configured default.
+    /// CV variant (senior-devops, senior-platform-engineer, senior-sre,
+    /// engineering-manager). `insert` builds it, inferring it from the job
+    /// title when omitted; other commands match the variant stored per row.
     #[arg(long)]
     pub variant: Option<String>,
 }
 
+impl FilterArgs {
+    /// Translate the CLI filters into the database query criteria, parsing
+    /// `--date` as a year, year-month, full date or month name.
+    pub fn to_application_filter(&self) -> Result<ApplicationFilter, String> {
+        Ok(ApplicationFilter {
+            job_title: self.job_title.clone(),
+            company: self.company_name.clone(),
+            quote: self.quote.clone(),
+            date: self.date.as_deref().map(parse_date).transpose()?,
+            variant: self.variant.clone(),
+        })
+    }
+}
+
 pub fn match_user_action(
     ctx: &AppContext,
     user_input: UserInput,
@@ -125,33 +432,54 @@ pub fn match_user_action(
     match user_input.action {
         UserAction::Insert(_) => insert_cv(ctx),
 
-        UserAction::Remove(filters) => {
-            if let Err(e) = remove_cv(ctx, &filters) {
-                error!("Could not remove the CV: {e:}");
-            }
-            let out = format!("filter args for LIST: {filters:?}");
-            println!("{out:?}");
-            Ok(out)
+        UserAction::Remove(args) => {
+            remove_cv(ctx, &args).inspect_err(|e| error!("Could not remove the CV: {e:}"))?;
+            Ok(String::new())
         }
-        UserAction::List(_filters) => {
-            run_list_tui(ctx)?;
-            Ok(String::from("tui: ok"))
+        UserAction::List(ListArgs {
+            filters,
+            format: Some(format),
+        }) => {
+            print_applications(ctx, &filters, format)?;
+            Ok(String::new())
         }
-        UserAction::Update(filters) => {
-            let out = format!("filter args for UPDATE: {filters:?}");
-            println!("{out:?}");
-            Ok(out)
+        UserAction::List(ListArgs {
+            filters,
+            format: None,
+        }) => {
+            run_list_tui(ctx, &filters)?;
+            Ok(String::from("tui: ok"))
         }
+        UserAction::Update(args) => update_cv(ctx, &args),
+        UserAction::Status(args) => set_application_status(ctx, &args),
+        UserAction::Preview(args) => run_preview(ctx, &args),
+        UserAction::Db(args) => run_db_command(ctx, &args),
     }
 }
 
+/// Print the applications matching `filters` to stdout in `format`. Never
+/// touches the terminal, so it works in pipes and scripts where the TUI cannot.
+fn print_applications(
+    ctx: &AppContext,
+    filters: &FilterArgs,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let filter = filters.to_application_filter()?;
+    let mut conn = connect_db(ctx)?;
+    let cvs = rusty_cv_creator::database::find_applications(&mut conn, &filter)?;
+    print!("{}", render_applications(&cvs, format)?);
+    Ok(())
+}
+
 /// Drive the interactive `list` TUI: probe the terminal first (so a non-TTY
-/// invocation fails fast without touching the DB), then load every stored
-/// application through the v5 `DbConnection` seam and hand it to the pure-UI TUI.
-fn run_list_tui(ctx: &AppContext) -> Result<(), Box<dyn std::error::Error>> {
+/// invocation fails fast without touching the DB), then load the stored
+/// applications matching `filters` through the v5 `DbConnection` seam and hand
+/// them to the pure-UI TUI.
+fn run_list_tui(ctx: &AppContext, filters: &FilterArgs) -> Result<(), Box<dyn std::error::Error>> {
     rusty_cv_creator::tui::probe::run_startup_probe()?;
+    let filter = filters.to_application_filter()?;
     let mut conn = connect_db(ctx)?;
-    let cvs = rusty_cv_creator::database::load_all_applications(&mut conn)?;
+    let cvs = rusty_cv_creator::database::find_applications(&mut conn, &filter)?;
     rusty_cv_creator::tui::run(cvs)
 }
 
@@ -162,25 +490,31 @@ pub struct UserFilters {
     pub date: Option<String>,
 }
 
-// TODO: this function should parse the user input when filtering or listing cv.
-// TODO Fix this should return ParseError when things do not work.
-#[allow(dead_code)]
-fn parse_date(input: &str) -> Result<String, String> {
-    let formats = [
-        "%Y",       // Year
-        "%Y-%m",    // Year-Month
-        "%Y-%m-%d", // Year-Month-Day
-        "%B",       // Full month name
-        "%b",       // Abbreviated month name
-    ];
-
-    for format in &formats {
-        if let Ok(parsed) = NaiveDate::parse_from_str(input, format) {
-            return Ok(parsed.to_string());
-        }
+/// Parse a `--date` filter: `YYYY`, `YYYY-MM`, `YYYY-MM-DD`, or a month name
+/// (`May`, `september`) matching that month in any year.
+fn parse_date(input: &str) -> Result<DateFilter, String> {
+    let input = input.trim();
+    let parse_err = || {
+        format!(
+            "Failed to parse date: {input} (expected YYYY, YYYY-MM, YYYY-MM-DD or a month name)"
+        )
+    };
+
+    if let Ok(day) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(DateFilter::Day(day));
+    }
+    // `%Y` / `%Y-%m` alone do not make a `NaiveDate`; complete them to the 1st.
+    if let Ok(day) = NaiveDate::parse_from_str(&format!("{input}-01"), "%Y-%m-%d") {
+        return Ok(DateFilter::YearMonth(day.year(), day.month()));
+    }
+    if input.len() == 4 && input.bytes().all(|b| b.is_ascii_digit()) {
+        return input.parse().map(DateFilter::Year).map_err(|_| parse_err());
+    }
+    if let Ok(month) = input.parse::<Month>() {
+        return Ok(DateFilter::Month(month.number_from_month()));
     }
 
-    Err(format!("Failed to parse date: {input}"))
+    Err(parse_err())
 }
 
 #[cfg(test)]
@@ -190,14 +524,48 @@ mod tests {
     #[test]
     fn test_parse_date_full_date() {
         let r = parse_date("2024-01-01");
-        assert!(r.is_ok());
-        assert_eq!(r.unwrap(), "2024-01-01");
+        assert_eq!(
+            r.unwrap(),
+            DateFilter::Day(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_date_year_year_month_and_month_name() {
+        assert_eq!(parse_date("2024").unwrap(), DateFilter::Year(2024));
+        assert_eq!(
+            parse_date("2024-05").unwrap(),
+            DateFilter::YearMonth(2024, 5)
+        );
+        assert_eq!(parse_date("May").unwrap(), DateFilter::Month(5));
+        assert_eq!(parse_date("september").unwrap(), DateFilter::Month(9));
+        assert_eq!(parse_date("Sep").unwrap(), DateFilter::Month(9));
     }
 
     #[test]
     fn test_parse_date_invalid() {
-        let r = parse_date("badstring");
-        assert!(r.is_err());
+        for input in ["badstring", "2024-13", "24", "2024-02-30"] {
+            assert!(parse_date(input).is_err(), "accepted {input}");
+        }
+    }
+
+    #[test]
+    fn test_filter_args_to_application_filter() {
+        let args = FilterArgs {
+            company_name: Some("ACME".to_string()),
+            date: Some("2024-05".to_string()),
+            ..FilterArgs::default()
+        };
+        let filter = args.to_application_filter().unwrap();
+        assert_eq!(filter.company.as_deref(), Some("ACME"));
+        assert_eq!(filter.date, Some(DateFilter::YearMonth(2024, 5)));
+        assert!(filter.job_title.is_none());
+
+        let bad = FilterArgs {
+            date: Some("someday".to_string()),
+            ..FilterArgs::default()
+        };
+        assert!(bad.to_application_filter().is_err());
     }
 
     #[test]
@@ -236,13 +604,16 @@ mod tests {
     // tests (tests/tui_job_applications_*), not by an in-process unit test.
 
     #[test]
-    fn test_match_user_action_update_arm() {
-        let ctx = context_with(UserAction::Update(FilterArgs::default()));
-        let out = match_user_action(
-            &ctx,
-            user_input_with(UserAction::Update(FilterArgs::default())),
-        )
-        .unwrap();
-        assert!(out.contains("UPDATE"));
+    fn test_match_user_action_update_without_changes_errors() {
+        // Nothing to change is rejected before any DB connection or picker.
+        let action = UserAction::Update(UpdateArgs {
+            id: Some(1),
+            ..UpdateArgs::default()
+        });
+        let ctx = context_with(action.clone());
+        let err = match_user_action(&ctx, user_input_with(action))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Nothing to update"), "got: {err}");
     }
 }
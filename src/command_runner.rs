@@ -13,6 +13,42 @@ pub struct CommandOutcome {
     pub stderr: String,
 }
 
+/// A command a [`CommandRunner`] would be asked to run, written down instead of
+/// executed (`insert --dry-run`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+}
+
+impl PlannedCommand {
+    pub fn new(program: &str, args: &[&str], cwd: Option<&str>) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|arg| (*arg).to_string()).collect(),
+            cwd: cwd.map(str::to_string),
+        }
+    }
+}
+
+impl std::fmt::Display for PlannedCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                write!(f, " '{arg}'")?;
+            } else {
+                write!(f, " {arg}")?;
+            }
+        }
+        if let Some(dir) = &self.cwd {
+            write!(f, "  (in {dir})")?;
+        }
+        Ok(())
+    }
+}
+
 /// Abstraction over running external programs so the build / view / Tailscale
 /// logic can be unit-tested with a fake instead of really shelling out.
 pub trait CommandRunner {
@@ -219,6 +255,53 @@ pub mod testing {
         }
     }
 
+    /// A `[build]` section for tests that build through `prepare_cv`: a
+    /// `command` builder whose program is the running test binary, so the tool
+    /// check passes whatever is installed. Pair it with
+    /// [`DocumentWritingRunner`], which does the actual "build".
+    pub fn hermetic_build_section() -> String {
+        let program = std::env::current_exe().unwrap();
+        format!(
+            "[build]\nbuilder = \"command\"\ncommand = \"{} {{variant}}\"\n",
+            program.display()
+        )
+    }
+
+    /// "Builds" the document a build command names (its last argument) as a
+    /// one-page `TestCV-<document>.pdf` in the working directory.
+    pub struct DocumentWritingRunner;
+
+    impl CommandRunner for DocumentWritingRunner {
+        fn status(&self, _program: &str, _args: &[&str], _cwd: Option<&str>) -> io::Result<bool> {
+            Ok(true)
+        }
+
+        fn output(&self, _program: &str, _args: &[&str]) -> io::Result<(bool, String)> {
+            Ok((true, String::new()))
+        }
+
+        fn spawn(&self, _program: &str, _args: &[&str]) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn run_capturing(
+            &self,
+            _program: &str,
+            args: &[&str],
+            cwd: Option<&str>,
+        ) -> io::Result<CommandOutcome> {
+            let pdf_name = format!("TestCV-{}.pdf", args.last().unwrap());
+            if let Some(dir) = cwd {
+                crate::pdf_pages::write_test_pdf(&std::path::Path::new(dir).join(&pdf_name), 1);
+            }
+            Ok(CommandOutcome {
+                success: true,
+                stdout: format!("Output written on {pdf_name} (1 page, 90000 bytes).\n"),
+                stderr: String::new(),
+            })
+        }
+    }
+
     #[test]
     fn test_fake_runner_records_and_returns() {
         let fake = FakeRunner::ok();
@@ -232,6 +315,19 @@ pub mod testing {
         let fake = FakeRunner::io_error();
         assert!(fake.spawn("zathura", &["a.pdf"]).is_err());
     }
+
+    #[test]
+    fn test_planned_command_display_quotes_arguments_with_spaces() {
+        let command = super::PlannedCommand::new(
+            "just",
+            &["tectonic=tectonic --print", "build", "senior-sre"],
+            Some("/tmp/cv"),
+        );
+        assert_eq!(
+            command.to_string(),
+            "just 'tectonic=tectonic --print' build senior-sre  (in /tmp/cv)"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,12 @@
 use crate::cli_structure::UserInput;
 use crate::global_conf::AppContext;
 use crate::helpers::{check_config_file_exists, clean_string_from_quotes, fix_home_directory_path};
+use crate::pending_saves::retry_pending_saves;
 use configparser::ini::Ini;
 use log::{debug, info};
+use rusty_cv_creator::migrations::{pending_migrations, run_pending_migrations};
 use std::fs;
+use std::path::PathBuf;
 
 /// Build the immutable [`AppContext`] for this run (ADR-0006).
 ///
@@ -52,8 +55,18 @@ pub fn get_variable_from_config_file(
 
 pub fn get_db_configurations(ctx: &AppContext) -> Result<String, Box<dyn std::error::Error>> {
     debug!("Getting DB Configuration");
-    let cfg_db_path = ctx.get_user_input_vars("db", "db_path")?;
-    let cfg_db_file = ctx.get_user_input_vars("db", "db_file")?;
+    let not_configured = |e: Box<dyn std::error::Error>| {
+        format!(
+            "The SQLite database is not configured ({e}): set [db] db_path and db_file, or \
+             DATABASE_URL, then run `rusty_cv_creator db init`"
+        )
+    };
+    let cfg_db_path = ctx
+        .get_user_input_vars("db", "db_path")
+        .map_err(not_configured)?;
+    let cfg_db_file = ctx
+        .get_user_input_vars("db", "db_file")
+        .map_err(not_configured)?;
 
     let mut db_path = fix_home_directory_path(&clean_string_from_quotes(&cfg_db_path));
     let db_file = clean_string_from_quotes(&cfg_db_file);
@@ -200,23 +213,29 @@ fn inject_db_password(
 ///   `sqlite://<configured-path>` URL built from the INI config.
 pub fn resolve_db_target(ctx: &AppContext) -> Result<(String, String), Box<dyn std::error::Error>> {
     let engine = ctx.get_user_input_db_engine()?;
+    let url = resolve_engine_url(ctx, &engine)?;
+    Ok((engine, url))
+}
 
+/// The connection URL of `engine`, whatever engine the run is configured
+/// for: `db sync` talks to both. A `DATABASE_URL` for another engine (the
+/// postgres setup exports one) is not a SQLite path.
+fn resolve_engine_url(
+    ctx: &AppContext,
+    engine: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
     match engine.trim() {
         "postgres" => {
             let base_url = ctx
                 .get_user_input_db_url()
                 .map_err(|e| -> Box<dyn std::error::Error> { e.to_string().into() })?;
-            let url = inject_db_password(&base_url, &read_db_password()?)?;
-            Ok((engine, url))
+            inject_db_password(&base_url, &read_db_password()?)
         }
-        "sqlite" => {
-            let url = match std::env::var("DATABASE_URL") {
-                Ok(value) => fix_home_directory_path(&value),
-                Err(_) => format!("sqlite://{}", get_db_configurations(ctx)?),
-            };
-            Ok((engine, url))
-        }
-        _ => Ok((engine, String::new())),
+        "sqlite" => match std::env::var("DATABASE_URL") {
+            Ok(value) if !value.starts_with("postgres") => Ok(fix_home_directory_path(&value)),
+            _ => Ok(format!("sqlite://{}", get_db_configurations(ctx)?)),
+        },
+        _ => Ok(String::new()),
     }
 }
 
@@ -250,30 +269,177 @@ fn redact_secret(message: &str, secret: &str) -> String {
 /// driver's error branches is a losing game across versions; removing the
 /// secret from whatever it produces is not. Every caller must connect through
 /// here rather than calling `establish_connection` directly.
+///
+/// The schema is brought up to date before the connection is handed out (see
+/// `ensure_schema`), so a fresh `SQLite` file works on the first run, and the
+/// applications whose save failed earlier are retried on it (see
+/// `pending_saves`).
 pub fn connect_db(
     ctx: &AppContext,
 ) -> Result<rusty_cv_creator::database::DbConnection, Box<dyn std::error::Error>> {
-    let (engine, url) = resolve_db_target(ctx)?;
+    let mut conn = connect_engine(ctx, &ctx.get_user_input_db_engine()?)?;
+    retry_pending_saves(ctx, &mut conn);
+    Ok(conn)
+}
+
+/// `connect_db` for `engine` rather than the configured one, without the retry
+/// of the pending saves.
+pub fn connect_engine(
+    ctx: &AppContext,
+    engine: &str,
+) -> Result<rusty_cv_creator::database::DbConnection, Box<dyn std::error::Error>> {
+    let mut conn = open_engine(ctx, engine)?;
+    ensure_schema(ctx, &mut conn)?;
+    Ok(conn)
+}
 
-    rusty_cv_creator::database::establish_connection(&engine, &url).map_err(
+/// `connect_db` without the schema check: for `db migrate`, which reports on
+/// and applies the migrations itself. A SQLite database that does not exist
+/// yet is created, and said so on stderr.
+pub fn open_db(
+    ctx: &AppContext,
+) -> Result<rusty_cv_creator::database::DbConnection, Box<dyn std::error::Error>> {
+    open_engine(ctx, &ctx.get_user_input_db_engine()?)
+}
+
+fn open_engine(
+    ctx: &AppContext,
+    engine: &str,
+) -> Result<rusty_cv_creator::database::DbConnection, Box<dyn std::error::Error>> {
+    let (conn, sqlite_file) = open_engine_creating(ctx, engine)?;
+    if let Some(SqliteFile {
+        path,
+        created: true,
+    }) = sqlite_file
+    {
+        eprintln!("Created a new SQLite database at {}", path.display());
+    }
+    Ok(conn)
+}
+
+/// The file of a SQLite database, and whether opening it just created it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteFile {
+    pub path: PathBuf,
+    pub created: bool,
+}
+
+/// Open the database, creating the directory of a SQLite file that does not
+/// exist yet (SQLite creates the file itself). Returns the SQLite file, if
+/// the engine uses one, for `db init` to report.
+pub fn open_db_creating(
+    ctx: &AppContext,
+) -> Result<
+    (rusty_cv_creator::database::DbConnection, Option<SqliteFile>),
+    Box<dyn std::error::Error>,
+> {
+    open_engine_creating(ctx, &ctx.get_user_input_db_engine()?)
+}
+
+fn open_engine_creating(
+    ctx: &AppContext,
+    engine: &str,
+) -> Result<
+    (rusty_cv_creator::database::DbConnection, Option<SqliteFile>),
+    Box<dyn std::error::Error>,
+> {
+    let url = resolve_engine_url(ctx, engine)?;
+
+    let sqlite_file = match engine.trim() {
+        "sqlite" => sqlite_file_path(&url)
+            .map(prepare_sqlite_file)
+            .transpose()?,
+        _ => None,
+    };
+    let conn = rusty_cv_creator::database::establish_connection(engine, &url).map_err(
         |e| -> Box<dyn std::error::Error> {
             let secret = std::env::var(DB_PASSWORD_ENV).unwrap_or_default();
             redact_secret(&e.to_string(), &secret).into()
         },
+    )?;
+    Ok((conn, sqlite_file))
+}
+
+/// The file behind a SQLite URL; `None` for an in-memory database.
+fn sqlite_file_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("sqlite://").unwrap_or(url);
+    (!path.is_empty() && path != ":memory:").then(|| PathBuf::from(path))
+}
+
+/// Create the parent directories of a SQLite file that does not exist yet.
+fn prepare_sqlite_file(path: PathBuf) -> Result<SqliteFile, Box<dyn std::error::Error>> {
+    if path.exists() {
+        return Ok(SqliteFile {
+            path,
+            created: false,
+        });
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| {
+            format!(
+                "Could not create the directory of the SQLite database {}: {e}",
+                path.display()
+            )
+        })?;
+    }
+    info!("Creating the SQLite database {}", path.display());
+    Ok(SqliteFile {
+        path,
+        created: true,
+    })
+}
+
+/// Apply the pending migrations when `auto_migrate` allows it. Otherwise a
+/// schema behind the program is an error pointing at `db migrate`, so a
+/// shared database is only migrated when someone decides to.
+fn ensure_schema(
+    ctx: &AppContext,
+    conn: &mut rusty_cv_creator::database::DbConnection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let is_sqlite = matches!(conn, rusty_cv_creator::database::DbConnection::Sqlite(_));
+    if auto_migrate(ctx, is_sqlite)? {
+        for name in run_pending_migrations(conn)? {
+            eprintln!("Applied database migration {name}");
+        }
+        return Ok(());
+    }
+
+    let pending = pending_migrations(conn)?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "The database schema is behind this program: {} pending migration(s) ({}). \
+         Run `rusty_cv_creator db migrate`, or set [db] auto_migrate = true",
+        pending.len(),
+        pending.join(", ")
     )
+    .into())
+}
+
+/// `[db] auto_migrate`. When unset, only a SQLite database (the user's own) is
+/// migrated on connect: the shared PostgreSQL one waits for `db migrate`.
+fn auto_migrate(ctx: &AppContext, is_sqlite: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    match get_variable_from_config_file(ctx, "db", "auto_migrate") {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| format!("[db] auto_migrate must be true or false, not '{value}'").into()),
+        Err(_) => Ok(is_sqlite),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli_structure::{FilterArgs, UserAction};
+    use crate::cli_structure::{ListArgs, UserAction};
     use chrono::Local;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
     fn empty_context() -> AppContext {
         let ui = UserInput {
-            action: UserAction::List(FilterArgs::default()),
+            action: UserAction::List(ListArgs::default()),
             save_to_database: false,
             view_generated_cv: false,
             dry_run: false,
@@ -297,7 +463,7 @@ mod tests {
         let mut f = NamedTempFile::new().unwrap();
         writeln!(f, "[db]\ndb_path = \"/tmp\"\ndb_file = \"test.db\"").unwrap();
         let ui = UserInput {
-            action: UserAction::List(FilterArgs::default()),
+            action: UserAction::List(ListArgs::default()),
             save_to_database: false,
             view_generated_cv: false,
             dry_run: false,
@@ -320,15 +486,77 @@ mod tests {
     #[test]
     fn test_get_db_configurations_error_if_missing() {
         let ctx = empty_context();
-        let result = get_db_configurations(&ctx);
-        assert!(result.is_err());
+        let error = get_db_configurations(&ctx).unwrap_err().to_string();
+        assert!(
+            error.contains("set [db] db_path and db_file"),
+            "got: {error}"
+        );
+        assert!(error.contains("db init"), "got: {error}");
+    }
+
+    #[test]
+    fn test_sqlite_file_path_skips_in_memory_databases() {
+        assert_eq!(
+            sqlite_file_path("sqlite:///data/apps.db"),
+            Some(PathBuf::from("/data/apps.db"))
+        );
+        assert_eq!(sqlite_file_path("apps.db"), Some(PathBuf::from("apps.db")));
+        assert_eq!(sqlite_file_path(":memory:"), None);
+        assert_eq!(sqlite_file_path("sqlite://"), None);
+    }
+
+    /// A new user's first command: neither the directory nor the file exist,
+    /// and the database comes out created and migrated.
+    #[test]
+    #[serial_test::serial]
+    fn test_connect_db_creates_a_missing_sqlite_database() {
+        let _restore = EnvVarGuard::capture("DATABASE_URL");
+        std::env::remove_var("DATABASE_URL");
+        let td = tempfile::TempDir::new().unwrap();
+        let db_dir = td.path().join("config").join("rusty-cv-creator");
+        let ctx = context_from(&format!(
+            "[db]\nengine = \"sqlite\"\ndb_path = \"{}\"\ndb_file = \"applications.db\"",
+            db_dir.display()
+        ));
+
+        let mut conn = connect_db(&ctx).unwrap();
+
+        assert!(db_dir.join("applications.db").is_file());
+        assert!(pending_migrations(&mut conn).unwrap().is_empty());
+        assert!(
+            rusty_cv_creator::database::load_all_applications(&mut conn)
+                .unwrap()
+                .is_empty()
+        );
+        drop(conn);
+
+        let (_, sqlite_file) = open_db_creating(&ctx).unwrap();
+        assert_eq!(
+            sqlite_file,
+            Some(SqliteFile {
+                path: db_dir.join("applications.db"),
+                created: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_auto_migrate_defaults_to_sqlite_only() {
+        let ctx = empty_context();
+        assert!(auto_migrate(&ctx, true).unwrap());
+        assert!(!auto_migrate(&ctx, false).unwrap());
+
+        let opted_in = context_from("[db]\nauto_migrate = true");
+        assert!(auto_migrate(&opted_in, false).unwrap());
+        let opted_out = context_from("[db]\nauto_migrate = false");
+        assert!(!auto_migrate(&opted_out, true).unwrap());
     }
 
     // ─── password injection ──────────────────────────────────────────────────
 
     fn context_from(config: &str) -> AppContext {
         let ui = UserInput {
-            action: UserAction::List(FilterArgs::default()),
+            action: UserAction::List(ListArgs::default()),
             save_to_database: false,
             view_generated_cv: false,
             dry_run: false,
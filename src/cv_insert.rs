@@ -1,46 +1,127 @@
-use crate::command_runner::SystemRunner;
-use crate::config_parse::connect_db;
+use crate::application_tex::ApplicationDetails;
+use crate::command_runner::{CommandRunner, SystemRunner};
+use crate::config_parse::{connect_db, connect_engine, get_db_configurations};
 use crate::global_conf::AppContext;
+use crate::insert_plan::plan_insert;
+use crate::pending_saves::{PendingSave, pending_saves_path, queue_save};
 use crate::prepare_cv;
 use log::{error, info, warn};
-use rusty_cv_creator::database::{DbConnection, save_new_cv_to_db};
-use rusty_cv_creator::models::Cv;
+use rusty_cv_creator::database::{
+    DbConnection, check_if_entry_exists, find_cv_by_id, save_application,
+};
+use rusty_cv_creator::models::{Cv, NewCv};
 
 pub fn insert_cv(ctx: &AppContext) -> Result<String, Box<dyn std::error::Error>> {
+    if ctx.is_dry_run() {
+        print!("{}", plan_insert(ctx)?);
+        return Ok(String::new());
+    }
+    insert_with(ctx, &SystemRunner)
+}
+
+/// `insert_cv` building through `runner`.
+fn insert_with(
+    ctx: &AppContext,
+    runner: &(dyn CommandRunner + Sync),
+) -> Result<String, Box<dyn std::error::Error>> {
     // These come from the UserInput, FilterArgs
     let job_title = ctx.get_job_title()?;
     let company_name = ctx.get_company_name()?;
     let quote = ctx.get_quote().ok();
     let variant = ctx.get_variant();
-
-    let destination_cv_file_full_path = prepare_cv(
-        ctx,
-        &SystemRunner,
-        &job_title,
-        &company_name,
-        variant.as_ref(),
-    )?;
+    let application_date = ctx.get_today_str();
+    let application = ApplicationDetails {
+        job_title: &job_title,
+        company: &company_name,
+        quote: quote.as_deref().unwrap_or(""),
+        application_date: &application_date,
+    };
 
     // This comes from the INI file.
     let save_to_db = ctx.get_user_input_save_to_db();
-    let application_date = ctx.get_today_str();
 
-    // A failed DB save must not discard a successfully generated CV — report and
-    // continue. Reported at `error!` so it is visible at env_logger's default
-    // level: at `warn!` a failed save printed nothing at all.
-    if let Err(e) = run_persistence(
+    // An application already stored keeps its row on save, so building it
+    // again would only publish numbered PDFs no row points at: stop first.
+    // An unreachable database is not checked here; the save below falls back.
+    let mut conn = None;
+    if save_to_db {
+        match connect_db(ctx) {
+            Ok(mut opened) => {
+                if let Some(id) =
+                    check_if_entry_exists(&mut opened, &job_title, &company_name, application.quote)
+                {
+                    let stored = find_cv_by_id(&mut opened, id)?;
+                    return Err(format!(
+                        "Application {id} ('{job_title}' at '{company_name}') is already stored \
+                         with {}; nothing was built. Run `rusty_cv_creator update --id {id} \
+                         --rebuild` to rebuild it",
+                        stored.pdf_cv_path
+                    )
+                    .into());
+                }
+                conn = Some(opened);
+            }
+            Err(e) => warn!("Could not check for an existing application: {e}"),
+        }
+    }
+
+    let built = prepare_cv(ctx, runner, &application, variant.as_ref())?;
+
+    let uuid = ctx.get_application_id().to_string();
+    let new_cv = NewCv {
+        application_date: Some(&application_date),
+        job_title: &job_title,
+        company: &company_name,
+        quote: quote.as_deref().unwrap_or(""),
+        pdf_cv_path: &built.pdf_path,
+        generated: true,
+        variant: Some(&built.variant),
+        template_ref: built.template_ref.as_deref(),
+        cover_letter_path: built.cover_letter_path.as_deref(),
+        compaction_level: built.compaction_level.as_deref(),
+        uuid: &uuid,
+    };
+
+    // A failed DB save must not discard a successfully generated CV — fall back
+    // to the local store when there is one, else queue the row in the pending
+    // saves journal and continue. Reported at `error!` so it is visible at
+    // env_logger's default level: at `warn!` a failed save printed nothing.
+    let saved = run_persistence(
         save_to_db,
-        || connect_db(ctx),
-        &destination_cv_file_full_path,
-        &job_title,
-        &company_name,
-        quote.as_ref(),
-        &application_date,
-    ) {
-        error!("The CV was generated but NOT saved to the database: {e:}");
+        || conn.map_or_else(|| connect_db(ctx), Ok),
+        &new_cv,
+    )
+    .or_else(|e| save_locally(ctx, &new_cv, e));
+    // The check above could not see the store the row went to (a fallback),
+    // or the application was saved in the meantime.
+    if let Ok(Some(stored)) = &saved {
+        if stored.pdf_cv_path != built.pdf_path {
+            error!(
+                "Application {} was already stored and still points at {}: the new {} is not \
+                 referenced by any application",
+                stored.id, stored.pdf_cv_path, built.pdf_path
+            );
+        }
+    }
+    if let Err(e) = saved {
+        let queued = pending_saves_path(ctx).and_then(|path| {
+            queue_save(&path, &PendingSave::new(&new_cv, &e.to_string()))?;
+            Ok(path)
+        });
+        match queued {
+            Ok(path) => error!(
+                "The CV was generated but NOT saved to the database: {e:}. It is queued in {} \
+                 and retried on the next connection, or with `rusty_cv_creator db flush-pending`",
+                path.display()
+            ),
+            Err(queue_error) => error!(
+                "The CV was generated but NOT saved to the database: {e:}; queueing it for a \
+                 retry failed too: {queue_error}"
+            ),
+        }
     }
 
-    Ok(destination_cv_file_full_path)
+    Ok(built.pdf_path)
 }
 
 /// Persist the generated CV only when the user opted in via `--save-to-database`.
@@ -51,11 +132,7 @@ pub fn insert_cv(ctx: &AppContext) -> Result<String, Box<dyn std::error::Error>>
 fn run_persistence<F>(
     save_to_db: bool,
     open_conn: F,
-    cv_path: &str,
-    job_title: &str,
-    company: &str,
-    quote: Option<&String>,
-    application_date: &str,
+    new_cv: &NewCv,
 ) -> Result<Option<Cv>, Box<dyn std::error::Error>>
 where
     F: FnOnce() -> Result<DbConnection, Box<dyn std::error::Error>>,
@@ -66,28 +143,70 @@ where
     }
 
     let mut conn = open_conn()?;
-    let cv = save_new_cv_to_db(
-        &mut conn,
-        cv_path,
-        job_title,
-        company,
-        quote,
-        application_date,
-    )?;
+    let cv = save_application(&mut conn, new_cv)?;
     info!("Saved CV to database");
     Ok(Some(cv))
 }
 
+/// When the shared PostgreSQL database cannot take the application, save it
+/// into the local SQLite store instead, if `[db] db_path`/`db_file` configure
+/// one, for `db sync` to push later.
+fn save_locally(
+    ctx: &AppContext,
+    new_cv: &NewCv,
+    remote_error: Box<dyn std::error::Error>,
+) -> Result<Option<Cv>, Box<dyn std::error::Error>> {
+    let has_local_store = ctx
+        .get_user_input_db_engine()
+        .is_ok_and(|engine| engine.trim() == "postgres")
+        && get_db_configurations(ctx).is_ok();
+    fall_back_to_local(
+        has_local_store.then_some(|| connect_engine(ctx, "sqlite")),
+        new_cv,
+        remote_error,
+    )
+}
+
+/// Save `new_cv` through `open_local` after the remote save failed with
+/// `remote_error`; without a local store that error stands.
+fn fall_back_to_local<F>(
+    open_local: Option<F>,
+    new_cv: &NewCv,
+    remote_error: Box<dyn std::error::Error>,
+) -> Result<Option<Cv>, Box<dyn std::error::Error>>
+where
+    F: FnOnce() -> Result<DbConnection, Box<dyn std::error::Error>>,
+{
+    let Some(open_local) = open_local else {
+        return Err(remote_error);
+    };
+    warn!("Could not save to the remote database: {remote_error}");
+
+    let cv = open_local()
+        .and_then(|mut conn| save_application(&mut conn, new_cv))
+        .map_err(|e| format!("{remote_error}; saving to the local database failed too: {e}"))?;
+    eprintln!(
+        "The remote database is unreachable: saved application {} locally; run \
+         `rusty_cv_creator db sync` to push it",
+        cv.id
+    );
+    Ok(Some(cv))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli_structure::{FilterArgs, UserAction, UserInput};
+    use crate::cli_structure::{InsertArgs, UpdateArgs, UserAction, UserInput};
+    use crate::command_runner::testing::{DocumentWritingRunner, hermetic_build_section};
+    use crate::config_parse::build_context;
     use configparser::ini::Ini;
-    use diesel::prelude::*;
+    use rusty_cv_creator::database::load_all_applications;
+    use rusty_cv_creator::migrations::migrated_in_memory_sqlite;
+    use std::path::Path;
 
     fn context_without_job_title() -> AppContext {
         let ui = UserInput {
-            action: UserAction::Update(FilterArgs::default()),
+            action: UserAction::Update(UpdateArgs::default()),
             save_to_database: false,
             view_generated_cv: false,
             dry_run: false,
@@ -99,16 +218,20 @@ mod tests {
         AppContext::new(Ini::new(), chrono::Local::now(), ui)
     }
 
-    fn sqlite_conn_with_table() -> DbConnection {
-        let mut conn = DbConnection::Sqlite(SqliteConnection::establish(":memory:").unwrap());
-        diesel::sql_query(
-            "CREATE TABLE cv (id INTEGER PRIMARY KEY AUTOINCREMENT, application_date VARCHAR, \
-             job_title VARCHAR NOT NULL, company VARCHAR NOT NULL, quote VARCHAR NOT NULL, \
-             pdf_cv_path VARCHAR NOT NULL, generated BOOLEAN NOT NULL DEFAULT 1)",
-        )
-        .execute(&mut conn)
-        .unwrap();
-        conn
+    fn new_cv() -> NewCv<'static> {
+        NewCv {
+            application_date: Some("2024-01-01"),
+            job_title: "Dev",
+            company: "ACME",
+            quote: "",
+            pdf_cv_path: "/tmp/cv.pdf",
+            generated: true,
+            variant: Some("senior-sre"),
+            template_ref: Some("0123abc"),
+            cover_letter_path: Some("/tmp/cover.pdf"),
+            compaction_level: Some("2"),
+            uuid: "5f0c6d1e-0a4b-4c2d-9e8f-1a2b3c4d5e6f",
+        }
     }
 
     #[test]
@@ -129,11 +252,7 @@ mod tests {
             || -> Result<DbConnection, Box<dyn std::error::Error>> {
                 panic!("connection must not be opened when --save-to-database is omitted")
             },
-            "/tmp/cv.pdf",
-            "Dev",
-            "ACME",
-            None,
-            "2024-01-01",
+            &new_cv(),
         )
         .unwrap();
         assert!(result.is_none());
@@ -143,16 +262,105 @@ mod tests {
     fn test_run_persistence_opt_in_writes_row() {
         let result = run_persistence(
             true,
-            || Ok::<_, Box<dyn std::error::Error>>(sqlite_conn_with_table()),
-            "/tmp/cv.pdf",
-            "Dev",
-            "ACME",
-            None,
-            "2024-01-01",
+            || Ok::<_, Box<dyn std::error::Error>>(migrated_in_memory_sqlite().unwrap()),
+            &new_cv(),
         )
         .unwrap();
         let cv = result.expect("a CV row should have been written when opted in");
         assert_eq!(cv.pdf_cv_path, "/tmp/cv.pdf");
         assert_eq!(cv.job_title, "Dev");
+        assert_eq!(cv.variant.as_deref(), Some("senior-sre"));
+        assert_eq!(cv.template_ref.as_deref(), Some("0123abc"));
+        assert_eq!(cv.cover_letter_path.as_deref(), Some("/tmp/cover.pdf"));
+        assert_eq!(cv.compaction_level.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_a_failed_remote_save_falls_back_to_the_local_store() {
+        let remote_error = || -> Box<dyn std::error::Error> { "connection refused".into() };
+
+        let saved = fall_back_to_local(
+            Some(|| Ok::<_, Box<dyn std::error::Error>>(migrated_in_memory_sqlite().unwrap())),
+            &new_cv(),
+            remote_error(),
+        )
+        .unwrap()
+        .expect("the row is saved locally");
+        assert_eq!(saved.uuid, new_cv().uuid);
+        assert_eq!(saved.synced_at, None);
+
+        let no_local_store = false.then_some(|| Ok(migrated_in_memory_sqlite().unwrap()));
+        let error = fall_back_to_local(no_local_store, &new_cv(), remote_error()).unwrap_err();
+        assert_eq!(error.to_string(), "connection refused");
+    }
+
+    /// Every PDF under `dir`, sorted.
+    fn pdfs_under(dir: &Path) -> Vec<String> {
+        let mut pdfs = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pdfs.extend(pdfs_under(&path));
+            } else if path.extension().is_some_and(|extension| extension == "pdf") {
+                pdfs.push(path.display().to_string());
+            }
+        }
+        pdfs.sort();
+        pdfs
+    }
+
+    #[test]
+    fn test_inserting_a_stored_application_again_publishes_nothing() {
+        let td = tempfile::TempDir::new().unwrap();
+        let base = td.path();
+        let template = base.join("template");
+        std::fs::create_dir_all(&template).unwrap();
+        for driver in ["TestCV-senior-sre.tex", "TestCV-cover-senior-sre.tex"] {
+            std::fs::write(template.join(driver), "x").unwrap();
+        }
+        let ini = format!(
+            "[cv]\ncv_template_path = \"{tpl}\"\ncv_file_prefix = \"TestCV\"\n\
+             {build}\
+             [destination]\ncv_path = \"{dest}\"\noutput_pdf = \"{out}\"\n\
+             [db]\nengine = \"sqlite\"\ndb_path = \"{base}\"\ndb_file = \"cvs.db\"\n",
+            tpl = template.display(),
+            build = hermetic_build_section(),
+            dest = base.join("dest").display(),
+            out = base.join("out").display(),
+            base = base.display(),
+        );
+        let ini_path = base.join("conf.ini");
+        std::fs::write(&ini_path, ini).unwrap();
+        let ctx = build_context(&UserInput {
+            action: UserAction::Insert(InsertArgs {
+                job_title: "Senior SRE".to_string(),
+                company_name: "ACME".to_string(),
+                cover_letter: true,
+                ..InsertArgs::default()
+            }),
+            save_to_database: true,
+            view_generated_cv: false,
+            dry_run: false,
+            config_ini: ini_path.to_str().unwrap().to_string(),
+            engine: "sqlite".to_string(),
+            repo: None,
+            branch: None,
+        });
+
+        insert_with(&ctx, &DocumentWritingRunner).unwrap();
+        let published = pdfs_under(base);
+        let error = insert_with(&ctx, &DocumentWritingRunner).unwrap_err();
+
+        assert!(error.to_string().contains("is already stored"), "{error}");
+        assert_eq!(pdfs_under(base), published);
+        let rows = load_all_applications(&mut connect_db(&ctx).unwrap()).unwrap();
+        assert_eq!(rows.len(), 1);
+        for pdf in pdfs_under(&base.join("out")) {
+            assert!(
+                rows.iter().any(|row| row.pdf_cv_path == pdf
+                    || row.cover_letter_path.as_deref() == Some(pdf.as_str())),
+                "{pdf} is published but no application points at it"
+            );
+        }
     }
 }
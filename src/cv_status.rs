@@ -0,0 +1,104 @@
+use crate::cli_structure::StatusArgs;
+use crate::config_parse::connect_db;
+use crate::global_conf::AppContext;
+use crate::user_action::pick_application;
+use log::{info, warn};
+use rusty_cv_creator::database::{DbConnection, update_cv_in_db};
+use rusty_cv_creator::models::{Cv, CvChanges};
+use rusty_cv_creator::status::ApplicationStatus;
+
+/// Move a stored application to another lifecycle stage.
+///
+/// The move is validated against the lifecycle in `rusty_cv_creator::status`;
+/// `--force` bypasses that check so a mistaken move can be corrected. Returns
+/// an empty path: there is no new PDF to show.
+pub fn set_application_status(
+    ctx: &AppContext,
+    args: &StatusArgs,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut conn = connect_db(ctx)?;
+    let current = pick_application(&mut conn, args.id, &args.filters)?;
+
+    let updated = run_status_change(&mut conn, &current, args.status, args.force)?;
+    println!(
+        "Application {} ({} at {}): {} -> {}",
+        updated.id, updated.job_title, updated.company, current.status, updated.status
+    );
+
+    Ok(String::new())
+}
+
+/// Validate `current -> target` and persist it. A stored status this version
+/// does not know is only overwritten with `force`.
+fn run_status_change(
+    conn: &mut DbConnection,
+    current: &Cv,
+    target: ApplicationStatus,
+    force: bool,
+) -> Result<Cv, Box<dyn std::error::Error>> {
+    let next = if force {
+        warn!(
+            "Forcing application {} from '{}' to '{target}'",
+            current.id, current.status
+        );
+        target
+    } else {
+        let from: ApplicationStatus = current.status.parse()?;
+        from.transition_to(target)?
+    };
+
+    let changes = CvChanges {
+        status: Some(next.as_str()),
+        ..CvChanges::default()
+    };
+    let updated = update_cv_in_db(conn, current.id, &changes)?;
+    info!("Application {} is now '{next}'", updated.id);
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusty_cv_creator::database::save_new_cv_to_db;
+    use rusty_cv_creator::migrations::migrated_in_memory_sqlite;
+
+    fn sqlite_conn_with_row() -> (DbConnection, Cv) {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let cv =
+            save_new_cv_to_db(&mut conn, "/cv.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+        (conn, cv)
+    }
+
+    #[test]
+    fn test_a_new_application_is_drafted() {
+        let (_conn, cv) = sqlite_conn_with_row();
+        assert_eq!(cv.status, "drafted");
+    }
+
+    #[test]
+    fn test_run_status_change_moves_forward() {
+        let (mut conn, cv) = sqlite_conn_with_row();
+        let sent = run_status_change(&mut conn, &cv, ApplicationStatus::Sent, false).unwrap();
+        assert_eq!(sent.status, "sent");
+
+        let interview =
+            run_status_change(&mut conn, &sent, ApplicationStatus::Interview, false).unwrap();
+        assert_eq!(interview.status, "interview");
+    }
+
+    #[test]
+    fn test_run_status_change_rejects_an_illegal_move() {
+        let (mut conn, cv) = sqlite_conn_with_row();
+        let err = run_status_change(&mut conn, &cv, ApplicationStatus::Offer, false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Cannot move"), "got: {err}");
+    }
+
+    #[test]
+    fn test_run_status_change_force_overrides_the_lifecycle() {
+        let (mut conn, cv) = sqlite_conn_with_row();
+        let offer = run_status_change(&mut conn, &cv, ApplicationStatus::Offer, true).unwrap();
+        assert_eq!(offer.status, "offer");
+    }
+}
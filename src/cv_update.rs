@@ -0,0 +1,334 @@
+use crate::application_tex::ApplicationDetails;
+use crate::cli_structure::UpdateArgs;
+use crate::command_runner::SystemRunner;
+use crate::config_parse::connect_db;
+use crate::global_conf::AppContext;
+use crate::user_action::pick_application;
+use crate::{BuiltCv, prepare_cv};
+use chrono::NaiveDate;
+use log::info;
+use rusty_cv_creator::database::{DbConnection, update_cv_in_db};
+use rusty_cv_creator::models::{Cv, CvChanges};
+
+/// The date format `insert` stores (`AppContext::get_today_str`), so an edited
+/// date reads exactly like one written at insert time.
+const STORED_DATE_FORMAT: &str = "%e-%b-%Y";
+
+/// Edit a stored application in place, so a typo no longer needs a remove +
+/// re-insert.
+///
+/// The row is picked by `--id`, or interactively among the rows matching the
+/// filters. With `--rebuild` the PDF is rebuilt through `prepare_cv` from the
+/// (possibly updated) job title, company, quote and date, and the new PDF
+/// path, variant, template revision and cover letter are stored (a revision or
+/// cover letter the rebuild no longer has is cleared).
+/// Returns the PDF path of the updated row.
+pub fn update_cv(
+    ctx: &AppContext,
+    args: &UpdateArgs,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if !has_requested_changes(args) {
+        return Err("Nothing to update: pass at least one --new-* value or --rebuild".into());
+    }
+
+    let new_date = args
+        .new_date
+        .as_deref()
+        .map(normalize_application_date)
+        .transpose()?;
+
+    let mut conn = connect_db(ctx)?;
+    let current = pick_application(&mut conn, args.id, &args.filters)?;
+
+    let updated = run_update(
+        &mut conn,
+        &current,
+        args,
+        new_date.as_deref(),
+        |application, variant| prepare_cv(ctx, &SystemRunner, application, variant),
+    )?;
+
+    Ok(updated.pdf_cv_path)
+}
+
+fn has_requested_changes(args: &UpdateArgs) -> bool {
+    args.rebuild
+        || args.new_job_title.is_some()
+        || args.new_company_name.is_some()
+        || args.new_quote.is_some()
+        || args.new_date.is_some()
+        || args.new_pdf_path.is_some()
+}
+
+/// Write the requested changes for `current`, rebuilding the PDF first when
+/// `--rebuild` is set.
+///
+/// The build is injected through `rebuild` (application details, variant →
+/// built CV) so the update logic is testable without a LaTeX toolchain.
+/// A failed rebuild leaves the row untouched.
+fn run_update<F>(
+    conn: &mut DbConnection,
+    current: &Cv,
+    args: &UpdateArgs,
+    new_date: Option<&str>,
+    rebuild: F,
+) -> Result<Cv, Box<dyn std::error::Error>>
+where
+    F: FnOnce(&ApplicationDetails, Option<&String>) -> Result<BuiltCv, Box<dyn std::error::Error>>,
+{
+    let application = ApplicationDetails {
+        job_title: args.new_job_title.as_deref().unwrap_or(&current.job_title),
+        company: args.new_company_name.as_deref().unwrap_or(&current.company),
+        quote: args.new_quote.as_deref().unwrap_or(&current.quote),
+        application_date: new_date
+            .or(current.application_date.as_deref())
+            .unwrap_or_default(),
+    };
+
+    let rebuilt = if args.rebuild {
+        let built = rebuild(&application, args.new_variant.as_ref())?;
+        info!(
+            "Rebuilt CV for application {} ({}): {}",
+            current.id, built.variant, built.pdf_path
+        );
+        info!("The previous PDF is kept at: {}", current.pdf_cv_path);
+        Some(built)
+    } else {
+        None
+    };
+
+    let changes = CvChanges {
+        application_date: new_date,
+        job_title: args.new_job_title.as_deref(),
+        company: args.new_company_name.as_deref(),
+        quote: args.new_quote.as_deref(),
+        pdf_cv_path: rebuilt
+            .as_ref()
+            .map(|built| built.pdf_path.as_str())
+            .or(args.new_pdf_path.as_deref()),
+        variant: rebuilt.as_ref().map(|built| built.variant.as_str()),
+        template_ref: rebuilt.as_ref().map(|built| built.template_ref.as_deref()),
+        cover_letter_path: rebuilt
+            .as_ref()
+            .map(|built| built.cover_letter_path.as_deref()),
+        compaction_level: rebuilt
+            .as_ref()
+            .map(|built| built.compaction_level.as_deref()),
+        ..CvChanges::default()
+    };
+
+    update_cv_in_db(conn, current.id, &changes)
+}
+
+/// Accept `YYYY-MM-DD` or the stored `D-Mon-YYYY` form and return the stored form.
+fn normalize_application_date(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    ["%Y-%m-%d", "%d-%b-%Y", STORED_DATE_FORMAT]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(input, format).ok())
+        .map(|date| date.format(STORED_DATE_FORMAT).to_string())
+        .ok_or_else(|| format!("Failed to parse date: {input} (expected YYYY-MM-DD or D-Mon-YYYY)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusty_cv_creator::database::{find_cv_by_id, save_application, save_new_cv_to_db};
+    use rusty_cv_creator::migrations::migrated_in_memory_sqlite;
+    use rusty_cv_creator::models::NewCv;
+
+    fn sqlite_conn_with_row() -> (DbConnection, Cv) {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let cv = save_new_cv_to_db(
+            &mut conn,
+            "/old.pdf",
+            "Senior DevOsp",
+            "ACME",
+            None,
+            "1-Jan-2024",
+        )
+        .unwrap();
+        (conn, cv)
+    }
+
+    fn no_rebuild(
+        _application: &ApplicationDetails,
+        _variant: Option<&String>,
+    ) -> Result<BuiltCv, Box<dyn std::error::Error>> {
+        panic!("the PDF must not be rebuilt without --rebuild")
+    }
+
+    #[test]
+    fn test_has_requested_changes() {
+        assert!(!has_requested_changes(&UpdateArgs::default()));
+        assert!(has_requested_changes(&UpdateArgs {
+            new_quote: Some("q".to_string()),
+            ..UpdateArgs::default()
+        }));
+        assert!(has_requested_changes(&UpdateArgs {
+            rebuild: true,
+            ..UpdateArgs::default()
+        }));
+    }
+
+    #[test]
+    fn test_run_update_fixes_a_typo_without_rebuilding() {
+        let (mut conn, current) = sqlite_conn_with_row();
+        let args = UpdateArgs {
+            new_job_title: Some("Senior DevOps".to_string()),
+            ..UpdateArgs::default()
+        };
+
+        let updated = run_update(&mut conn, &current, &args, None, no_rebuild).unwrap();
+
+        assert_eq!(updated.job_title, "Senior DevOps");
+        assert_eq!(updated.company, "ACME");
+        assert_eq!(updated.pdf_cv_path, "/old.pdf");
+    }
+
+    #[test]
+    fn test_run_update_rebuild_uses_the_new_values_and_stores_the_new_pdf() {
+        let (mut conn, current) = sqlite_conn_with_row();
+        let args = UpdateArgs {
+            new_company_name: Some("Globex".to_string()),
+            new_variant: Some("senior-sre".to_string()),
+            rebuild: true,
+            ..UpdateArgs::default()
+        };
+
+        let updated = run_update(&mut conn, &current, &args, None, |application, variant| {
+            assert_eq!(application.job_title, "Senior DevOsp");
+            assert_eq!(application.company, "Globex");
+            assert_eq!(application.application_date, "1-Jan-2024");
+            assert_eq!(variant.map(String::as_str), Some("senior-sre"));
+            Ok(BuiltCv {
+                pdf_path: "/new.pdf".to_string(),
+                variant: "senior-sre".to_string(),
+                template_ref: Some("0123abc".to_string()),
+                cover_letter_path: None,
+                compaction_level: Some("1".to_string()),
+            })
+        })
+        .unwrap();
+
+        assert_eq!(updated.company, "Globex");
+        assert_eq!(updated.pdf_cv_path, "/new.pdf");
+        assert_eq!(updated.variant.as_deref(), Some("senior-sre"));
+        assert_eq!(updated.compaction_level.as_deref(), Some("1"));
+        assert_eq!(updated.template_ref.as_deref(), Some("0123abc"));
+    }
+
+    fn rebuilt(template_ref: Option<&str>, cover_letter_path: Option<&str>) -> BuiltCv {
+        BuiltCv {
+            pdf_path: "/new.pdf".to_string(),
+            variant: "senior-sre".to_string(),
+            template_ref: template_ref.map(str::to_string),
+            cover_letter_path: cover_letter_path.map(str::to_string),
+            compaction_level: None,
+        }
+    }
+
+    #[test]
+    fn test_run_update_rebuild_clears_a_stale_revision_and_cover_letter() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let current = save_application(
+            &mut conn,
+            &NewCv {
+                application_date: Some("1-Jan-2024"),
+                job_title: "SRE",
+                company: "ACME",
+                quote: "",
+                pdf_cv_path: "/old.pdf",
+                generated: true,
+                variant: Some("senior-sre"),
+                template_ref: Some("0123abc"),
+                cover_letter_path: Some("/old-cover-letter.pdf"),
+                compaction_level: None,
+                uuid: "uuid-1",
+            },
+        )
+        .unwrap();
+        let args = UpdateArgs {
+            rebuild: true,
+            ..UpdateArgs::default()
+        };
+
+        let updated = run_update(&mut conn, &current, &args, None, |_, _| {
+            Ok(rebuilt(None, None))
+        })
+        .unwrap();
+
+        assert_eq!(updated.pdf_cv_path, "/new.pdf");
+        assert_eq!(updated.template_ref, None);
+        assert_eq!(updated.cover_letter_path, None);
+    }
+
+    #[test]
+    fn test_run_update_rebuild_stores_an_added_cover_letter() {
+        let (mut conn, current) = sqlite_conn_with_row();
+        let args = UpdateArgs {
+            rebuild: true,
+            ..UpdateArgs::default()
+        };
+
+        let updated = run_update(&mut conn, &current, &args, None, |_, _| {
+            Ok(rebuilt(Some("0123abc"), Some("/new-cover-letter.pdf")))
+        })
+        .unwrap();
+
+        assert_eq!(updated.template_ref.as_deref(), Some("0123abc"));
+        assert_eq!(
+            updated.cover_letter_path.as_deref(),
+            Some("/new-cover-letter.pdf")
+        );
+    }
+
+    #[test]
+    fn test_run_update_failed_rebuild_leaves_the_row_untouched() {
+        let (mut conn, current) = sqlite_conn_with_row();
+        let args = UpdateArgs {
+            new_company_name: Some("Globex".to_string()),
+            rebuild: true,
+            ..UpdateArgs::default()
+        };
+
+        let result = run_update(&mut conn, &current, &args, None, |_, _| {
+            Err("build failed".into())
+        });
+
+        assert!(result.is_err());
+        let stored = find_cv_by_id(&mut conn, current.id).unwrap();
+        assert_eq!(stored.company, "ACME");
+    }
+
+    #[test]
+    fn test_run_update_writes_the_normalized_date() {
+        let (mut conn, current) = sqlite_conn_with_row();
+        let args = UpdateArgs {
+            new_date: Some("2024-05-07".to_string()),
+            ..UpdateArgs::default()
+        };
+        let date = normalize_application_date("2024-05-07").unwrap();
+
+        let updated = run_update(&mut conn, &current, &args, Some(&date), no_rebuild).unwrap();
+
+        assert_eq!(updated.application_date.as_deref(), Some(" 7-May-2024"));
+    }
+
+    #[test]
+    fn test_normalize_application_date_formats() {
+        assert_eq!(
+            normalize_application_date("2024-05-17").unwrap(),
+            "17-May-2024"
+        );
+        assert_eq!(
+            normalize_application_date("17-May-2024").unwrap(),
+            "17-May-2024"
+        );
+        assert_eq!(
+            normalize_application_date(" 7-May-2024").unwrap(),
+            " 7-May-2024"
+        );
+        assert!(normalize_application_date("May 2024").is_err());
+    }
+}
@@ -1,8 +1,12 @@
 use crate::models::Cv;
+use crate::models::CvChanges;
 use crate::models::NewCv;
 use crate::schema::cv::{self};
+use crate::schema::cv_deleted;
+use chrono::{NaiveDate, SecondsFormat, Utc};
 use diesel::prelude::*;
 use log::{error, info};
+use uuid::Uuid;
 
 /// A backend-agnostic connection so the same query code runs against `Postgres`
 /// in production and `SQLite` in tests.
@@ -27,24 +31,29 @@ pub fn establish_connection(
     }
 }
 
-fn check_if_entry_exists(
+/// The `updated_at` of a row written now: RFC 3339 in UTC, to the
+/// microsecond.
+pub fn modified_now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// The id of the stored application with this job title, company and quote:
+/// what counts as the same application (`save_application`, imports).
+pub fn check_if_entry_exists(
     conn: &mut DbConnection,
     g_job_title: &str,
     g_company: &str,
-    g_quote: Option<&String>,
+    g_quote: &str,
 ) -> Option<i32> {
     use crate::schema::cv::dsl::cv;
     use crate::schema::cv::{company, job_title, quote};
 
-    let empty = String::new();
-    let my_quote = g_quote.unwrap_or(&empty);
-
     // NOTE: `MultiConnection` does not support `Selectable::as_select`, so we
     // rely on the default (all-columns) selection, which matches `Cv`'s fields.
     let selection = cv
         .filter(job_title.eq(g_job_title))
         .filter(company.eq(g_company))
-        .filter(quote.eq(my_quote))
+        .filter(quote.eq(g_quote))
         .first::<Cv>(conn)
         .optional();
 
@@ -75,25 +84,35 @@ pub fn save_new_cv_to_db(
     quote: Option<&String>,
     application_date: &str,
 ) -> Result<Cv, Box<dyn std::error::Error>> {
-    if let Some(id) = check_if_entry_exists(conn, job_title, company, quote) {
-        info!("Entry already exists with id: {id}");
-        return Ok(cv::table.find(id).first::<Cv>(conn)?);
-    }
-
-    let empty = String::new();
-    let my_quote = quote.unwrap_or(&empty);
-
     let new_cv = NewCv {
         application_date: Some(application_date),
         job_title,
         company,
-        quote: my_quote,
+        quote: quote.map_or("", String::as_str),
         pdf_cv_path: cv_path,
         generated: true,
+        variant: None,
+        template_ref: None,
+        cover_letter_path: None,
+        compaction_level: None,
+        uuid: &Uuid::new_v4().to_string(),
     };
+    save_application(conn, &new_cv)
+}
+
+/// Insert `new_cv`, unless an application with the same job title, company and
+/// quote is already stored, in which case that row is returned unchanged.
+pub fn save_application(
+    conn: &mut DbConnection,
+    new_cv: &NewCv,
+) -> Result<Cv, Box<dyn std::error::Error>> {
+    if let Some(id) = check_if_entry_exists(conn, new_cv.job_title, new_cv.company, new_cv.quote) {
+        info!("Entry already exists with id: {id}");
+        return Ok(cv::table.find(id).first::<Cv>(conn)?);
+    }
 
     Ok(diesel::insert_into(cv::table)
-        .values(&new_cv)
+        .values((new_cv, cv::updated_at.eq(modified_now())))
         .returning(cv::all_columns)
         .get_result::<Cv>(conn)?)
 }
@@ -123,30 +142,200 @@ pub fn load_all_applications(
     Ok(crate::schema::cv::dsl::cv.load::<Cv>(conn)?)
 }
 
+/// Which part of the calendar an application date must fall in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateFilter {
+    Year(i32),
+    YearMonth(i32, u32),
+    Day(NaiveDate),
+    /// A month of any year, e.g. `--date May`.
+    Month(u32),
+}
+
+const MONTH_ABBREVIATIONS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+impl DateFilter {
+    /// `LIKE` patterns matching this filter against a stored `application_date`.
+    ///
+    /// Dates are stored as `D-Mon-YYYY` by `insert` (day space-padded) and as
+    /// `YYYY-MM-DD` by older rows, so every filter matches both spellings.
+    fn like_patterns(self) -> Vec<String> {
+        let month_abbr = |month: u32| MONTH_ABBREVIATIONS[(month - 1) as usize];
+        match self {
+            DateFilter::Year(year) => vec![format!("%-{year}"), format!("{year}-__-__")],
+            DateFilter::YearMonth(year, month) => vec![
+                format!("%-{}-{year}", month_abbr(month)),
+                format!("{year}-{month:02}-__"),
+            ],
+            DateFilter::Day(day) => vec![
+                day.format("%e-%b-%Y").to_string(),
+                day.format("%d-%b-%Y").to_string(),
+                day.format("%Y-%m-%d").to_string(),
+            ],
+            DateFilter::Month(month) => vec![
+                format!("%-{}-____", month_abbr(month)),
+                format!("____-{month:02}-__"),
+            ],
+        }
+    }
+}
+
+/// Narrowing criteria for stored applications. Text fields match as
+/// case-insensitive substrings; every `None` field matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct ApplicationFilter {
+    pub job_title: Option<String>,
+    pub company: Option<String>,
+    pub quote: Option<String>,
+    pub date: Option<DateFilter>,
+    pub variant: Option<String>,
+}
+
+/// Whether `value` contains `needle`, ignoring case. Both sides are folded
+/// with Rust's Unicode `to_lowercase()`: SQL `lower()` only folds ASCII on
+/// `SQLite`, so an `Ü` in the filter would never match a stored `Ü`.
+fn contains_folded(value: &str, needle: &str) -> bool {
+    value.to_lowercase().contains(&needle.trim().to_lowercase())
+}
+
+/// Load the stored applications matching `filter`, oldest first.
+///
+/// The date narrows the query in SQL; the text filters are applied to the
+/// loaded rows (see [`contains_folded`]).
+pub fn find_applications(
+    conn: &mut DbConnection,
+    filter: &ApplicationFilter,
+) -> Result<Vec<Cv>, Box<dyn std::error::Error>> {
+    let mut query = cv::table.order(cv::id.asc()).into_boxed();
+
+    if let Some(date) = filter.date {
+        let mut patterns = date.like_patterns().into_iter();
+        if let Some(first) = patterns.next() {
+            let mut by_date: Box<
+                dyn BoxableExpression<
+                        cv::table,
+                        _,
+                        SqlType = diesel::sql_types::Nullable<diesel::sql_types::Bool>,
+                    >,
+            > = Box::new(cv::application_date.like(first));
+            for pattern in patterns {
+                by_date = Box::new(by_date.or(cv::application_date.like(pattern)));
+            }
+            query = query.filter(by_date);
+        }
+    }
+
+    let mut cvs = query.load::<Cv>(conn)?;
+    let matches = |needle: &Option<String>, value: &str| {
+        needle
+            .as_deref()
+            .is_none_or(|needle| contains_folded(value, needle))
+    };
+    cvs.retain(|cv| {
+        matches(&filter.job_title, &cv.job_title)
+            && matches(&filter.company, &cv.company)
+            && matches(&filter.quote, &cv.quote)
+    });
+    if let Some(variant) = &filter.variant {
+        // Variant names are slugs: match the whole name, not a substring, so
+        // `--variant senior-sre` does not also pick up a `senior-sre-lead`.
+        let variant = variant.trim().to_lowercase();
+        cvs.retain(|cv| {
+            cv.variant
+                .as_deref()
+                .is_some_and(|stored| stored.to_lowercase() == variant)
+        });
+    }
+    Ok(cvs)
+}
+
+/// Delete the application `cv_id`, returning whether a row was removed. The
+/// deletion is recorded (`record_deletion`) for `db sync` to propagate.
+pub fn delete_cv_by_id(
+    conn: &mut DbConnection,
+    cv_id: i32,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    conn.transaction(|conn| {
+        let Some(uuid) = cv::table
+            .find(cv_id)
+            .select(cv::uuid)
+            .first::<String>(conn)
+            .optional()?
+        else {
+            return Ok(false);
+        };
+        diesel::delete(cv::table.find(cv_id)).execute(conn)?;
+        record_deletion(conn, &uuid)?;
+        Ok(true)
+    })
+}
+
+/// Record that the application `uuid` was deleted, now.
+pub fn record_deletion(conn: &mut DbConnection, uuid: &str) -> QueryResult<()> {
+    diesel::delete(cv_deleted::table.find(uuid)).execute(conn)?;
+    diesel::insert_into(cv_deleted::table)
+        .values((
+            cv_deleted::uuid.eq(uuid),
+            cv_deleted::deleted_at.eq(modified_now()),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Fetch one stored application by its primary key.
+pub fn find_cv_by_id(
+    conn: &mut DbConnection,
+    cv_id: i32,
+) -> Result<Cv, Box<dyn std::error::Error>> {
+    cv::table
+        .find(cv_id)
+        .first::<Cv>(conn)
+        .optional()?
+        .ok_or_else(|| format!("No application with id {cv_id}").into())
+}
+
+/// Fetch the stored application whose PDF lives at exactly `path`.
+pub fn find_cv_by_pdf_path(
+    conn: &mut DbConnection,
+    path: &str,
+) -> Result<Cv, Box<dyn std::error::Error>> {
+    cv::table
+        .filter(cv::pdf_cv_path.eq(path))
+        .first::<Cv>(conn)
+        .optional()?
+        .ok_or_else(|| format!("No application stored for PDF {path}").into())
+}
+
+/// Apply `changes` to the application `cv_id` and return the updated row.
+///
+/// Only the `Some` fields of the changeset are written; an empty changeset is
+/// rejected up front rather than surfacing diesel's less helpful error.
+pub fn update_cv_in_db(
+    conn: &mut DbConnection,
+    cv_id: i32,
+    changes: &CvChanges,
+) -> Result<Cv, Box<dyn std::error::Error>> {
+    if changes.is_empty() {
+        return Err(format!("Nothing to update for application {cv_id}").into());
+    }
+
+    let updated = diesel::update(cv::table.find(cv_id))
+        .set((changes, cv::updated_at.eq(modified_now())))
+        .execute(conn)?;
+    if updated == 0 {
+        return Err(format!("No application with id {cv_id}").into());
+    }
+
+    info!("Updated application with id: {cv_id}");
+    find_cv_by_id(conn, cv_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    /// Build an in-memory `SQLite` connection with the `cv` table created.
-    fn sqlite_test_conn() -> DbConnection {
-        let mut conn = DbConnection::Sqlite(
-            SqliteConnection::establish(":memory:").expect("in-memory sqlite"),
-        );
-        diesel::sql_query(
-            "CREATE TABLE cv (\
-                id INTEGER PRIMARY KEY AUTOINCREMENT, \
-                application_date VARCHAR, \
-                job_title VARCHAR NOT NULL, \
-                company VARCHAR NOT NULL, \
-                quote VARCHAR NOT NULL, \
-                pdf_cv_path VARCHAR NOT NULL, \
-                generated BOOLEAN NOT NULL DEFAULT 1\
-            )",
-        )
-        .execute(&mut conn)
-        .expect("create cv table");
-        conn
-    }
+    use crate::migrations::migrated_in_memory_sqlite;
 
     #[test]
     fn test_establish_connection_sqlite_in_memory_ok() {
@@ -160,7 +349,7 @@ mod tests {
 
     #[test]
     fn test_save_new_cv_inserts_row() {
-        let mut conn = sqlite_test_conn();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
         let saved = save_new_cv_to_db(
             &mut conn,
             "/tmp/cv.pdf",
@@ -180,7 +369,7 @@ mod tests {
 
     #[test]
     fn test_save_new_cv_is_idempotent_on_duplicate() {
-        let mut conn = sqlite_test_conn();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
         let first =
             save_new_cv_to_db(&mut conn, "/a.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
         // Same job/company/quote -> returns the existing row instead of inserting.
@@ -197,7 +386,7 @@ mod tests {
 
     #[test]
     fn test_save_new_cv_stores_quote() {
-        let mut conn = sqlite_test_conn();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
         let quote = "stay hungry".to_string();
         let saved = save_new_cv_to_db(
             &mut conn,
@@ -213,7 +402,7 @@ mod tests {
 
     #[test]
     fn test_read_cv_paths_returns_paths() {
-        let mut conn = sqlite_test_conn();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
         save_new_cv_to_db(&mut conn, "/one.pdf", "A", "X", None, "2024-01-01").unwrap();
         save_new_cv_to_db(&mut conn, "/two.pdf", "B", "Y", None, "2024-01-01").unwrap();
 
@@ -224,14 +413,14 @@ mod tests {
 
     #[test]
     fn test_read_cv_paths_empty() {
-        let mut conn = sqlite_test_conn();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
         let rows = read_cv_paths(&mut conn).unwrap();
         assert!(rows.is_empty());
     }
 
     #[test]
     fn test_load_all_applications_returns_rows() {
-        let mut conn = sqlite_test_conn();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
         save_new_cv_to_db(
             &mut conn,
             "/cv.pdf",
@@ -252,8 +441,251 @@ mod tests {
 
     #[test]
     fn test_load_all_applications_empty() {
-        let mut conn = sqlite_test_conn();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
         let cvs = load_all_applications(&mut conn).unwrap();
         assert!(cvs.is_empty());
     }
+
+    #[test]
+    fn test_find_cv_by_id_and_pdf_path() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let saved =
+            save_new_cv_to_db(&mut conn, "/find.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+
+        assert_eq!(find_cv_by_id(&mut conn, saved.id).unwrap().company, "ACME");
+        assert_eq!(
+            find_cv_by_pdf_path(&mut conn, "/find.pdf").unwrap().id,
+            saved.id
+        );
+        assert!(find_cv_by_id(&mut conn, saved.id + 1).is_err());
+        assert!(find_cv_by_pdf_path(&mut conn, "/other.pdf").is_err());
+    }
+
+    #[test]
+    fn test_update_cv_in_db_changes_only_the_given_fields() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let saved =
+            save_new_cv_to_db(&mut conn, "/u.pdf", "Sre", "ACME", None, "2024-01-01").unwrap();
+
+        let changes = CvChanges {
+            job_title: Some("SRE"),
+            quote: Some("fixed"),
+            ..CvChanges::default()
+        };
+        let updated = update_cv_in_db(&mut conn, saved.id, &changes).unwrap();
+
+        assert_eq!(updated.job_title, "SRE");
+        assert_eq!(updated.quote, "fixed");
+        assert_eq!(updated.company, "ACME");
+        assert_eq!(updated.pdf_cv_path, "/u.pdf");
+        assert_eq!(updated.application_date.as_deref(), Some("2024-01-01"));
+    }
+
+    #[test]
+    fn test_update_cv_in_db_rejects_empty_changes_and_unknown_ids() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let saved =
+            save_new_cv_to_db(&mut conn, "/u.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+
+        assert!(update_cv_in_db(&mut conn, saved.id, &CvChanges::default()).is_err());
+        let changes = CvChanges {
+            company: Some("Other"),
+            ..CvChanges::default()
+        };
+        assert!(update_cv_in_db(&mut conn, saved.id + 1, &changes).is_err());
+    }
+
+    #[test]
+    fn test_find_applications_text_filters_are_case_insensitive_substrings() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_new_cv_to_db(
+            &mut conn,
+            "/a.pdf",
+            "Senior SRE",
+            "ACME Corp",
+            None,
+            "2024-01-01",
+        )
+        .unwrap();
+        save_new_cv_to_db(&mut conn, "/b.pdf", "DevOps", "Globex", None, "2024-01-01").unwrap();
+
+        let filter = ApplicationFilter {
+            job_title: Some("sre".to_string()),
+            company: Some("acme".to_string()),
+            ..ApplicationFilter::default()
+        };
+        let found = find_applications(&mut conn, &filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pdf_cv_path, "/a.pdf");
+
+        let all = find_applications(&mut conn, &ApplicationFilter::default()).unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    fn test_find_applications_matches_wildcards_literally() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        for (pdf, company) in [
+            ("/a.pdf", "100% Remote"),
+            ("/b.pdf", "100 Remote"),
+            ("/c.pdf", "A_B"),
+            ("/d.pdf", "AxB"),
+            ("/e.pdf", "C\\D"),
+        ] {
+            save_new_cv_to_db(&mut conn, pdf, "SRE", company, None, "2024-01-01").unwrap();
+        }
+        let by_company = |conn: &mut DbConnection, company: &str| -> Vec<String> {
+            let filter = ApplicationFilter {
+                company: Some(company.to_string()),
+                ..ApplicationFilter::default()
+            };
+            find_applications(conn, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|cv| cv.pdf_cv_path)
+                .collect()
+        };
+
+        assert_eq!(by_company(&mut conn, "100%"), ["/a.pdf"]);
+        assert_eq!(by_company(&mut conn, "a_b"), ["/c.pdf"]);
+        assert_eq!(by_company(&mut conn, "c\\d"), ["/e.pdf"]);
+        assert_eq!(by_company(&mut conn, "%"), ["/a.pdf"]);
+    }
+
+    #[test]
+    fn test_find_applications_folds_non_ascii_case() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_new_cv_to_db(
+            &mut conn,
+            "/u.pdf",
+            "Ingénieur",
+            "Ünïcode: Corp",
+            None,
+            "2024-01-01",
+        )
+        .unwrap();
+        save_new_cv_to_db(&mut conn, "/a.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+
+        for company in ["Ünïcode", "ünïcode", "ÜNÏCODE", "nïcode"] {
+            let filter = ApplicationFilter {
+                company: Some(company.to_string()),
+                ..ApplicationFilter::default()
+            };
+            let found = find_applications(&mut conn, &filter).unwrap();
+            assert_eq!(found.len(), 1, "{company}");
+            assert_eq!(found[0].pdf_cv_path, "/u.pdf");
+        }
+        let filter = ApplicationFilter {
+            job_title: Some("INGÉNIEUR".to_string()),
+            ..ApplicationFilter::default()
+        };
+        assert_eq!(find_applications(&mut conn, &filter).unwrap().len(), 1);
+    }
+
+    fn new_cv_with_variant<'a>(pdf_cv_path: &'a str, variant: Option<&'a str>) -> NewCv<'a> {
+        NewCv {
+            application_date: Some("2024-01-01"),
+            job_title: pdf_cv_path,
+            company: "ACME",
+            quote: "",
+            pdf_cv_path,
+            generated: true,
+            variant,
+            template_ref: Some("0123abc"),
+            cover_letter_path: None,
+            compaction_level: None,
+            uuid: pdf_cv_path,
+        }
+    }
+
+    #[test]
+    fn test_save_application_records_the_variant_and_template_ref() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let saved = save_application(
+            &mut conn,
+            &new_cv_with_variant("/a.pdf", Some("senior-sre")),
+        )
+        .unwrap();
+        assert_eq!(saved.variant.as_deref(), Some("senior-sre"));
+        assert_eq!(saved.template_ref.as_deref(), Some("0123abc"));
+
+        let again =
+            save_application(&mut conn, &new_cv_with_variant("/a.pdf", Some("other"))).unwrap();
+        assert_eq!(again.id, saved.id);
+        assert_eq!(again.variant.as_deref(), Some("senior-sre"));
+    }
+
+    #[test]
+    fn test_find_applications_variant_filter_matches_the_whole_name() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_application(
+            &mut conn,
+            &new_cv_with_variant("/sre.pdf", Some("senior-sre")),
+        )
+        .unwrap();
+        save_application(
+            &mut conn,
+            &new_cv_with_variant("/lead.pdf", Some("senior-sre-lead")),
+        )
+        .unwrap();
+        save_application(&mut conn, &new_cv_with_variant("/legacy.pdf", None)).unwrap();
+
+        let filter = ApplicationFilter {
+            variant: Some(" Senior-SRE ".to_string()),
+            ..ApplicationFilter::default()
+        };
+        let found = find_applications(&mut conn, &filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pdf_cv_path, "/sre.pdf");
+    }
+
+    #[test]
+    fn test_find_applications_date_filters_match_both_stored_formats() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_new_cv_to_db(&mut conn, "/iso.pdf", "A", "X", None, "2024-05-07").unwrap();
+        save_new_cv_to_db(&mut conn, "/ins.pdf", "B", "X", None, " 7-May-2024").unwrap();
+        save_new_cv_to_db(&mut conn, "/jun.pdf", "C", "X", None, "17-Jun-2024").unwrap();
+        save_new_cv_to_db(&mut conn, "/old.pdf", "D", "X", None, "17-May-2023").unwrap();
+
+        let paths = |conn: &mut DbConnection, date: DateFilter| -> Vec<String> {
+            let filter = ApplicationFilter {
+                date: Some(date),
+                ..ApplicationFilter::default()
+            };
+            find_applications(conn, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|cv| cv.pdf_cv_path)
+                .collect()
+        };
+
+        assert_eq!(
+            paths(&mut conn, DateFilter::Year(2024)),
+            ["/iso.pdf", "/ins.pdf", "/jun.pdf"]
+        );
+        assert_eq!(
+            paths(&mut conn, DateFilter::YearMonth(2024, 5)),
+            ["/iso.pdf", "/ins.pdf"]
+        );
+        let day = NaiveDate::from_ymd_opt(2024, 5, 7).unwrap();
+        assert_eq!(
+            paths(&mut conn, DateFilter::Day(day)),
+            ["/iso.pdf", "/ins.pdf"]
+        );
+        assert_eq!(
+            paths(&mut conn, DateFilter::Month(5)),
+            ["/iso.pdf", "/ins.pdf", "/old.pdf"]
+        );
+    }
+
+    #[test]
+    fn test_delete_cv_by_id() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let saved =
+            save_new_cv_to_db(&mut conn, "/d.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+
+        assert!(delete_cv_by_id(&mut conn, saved.id).unwrap());
+        assert!(!delete_cv_by_id(&mut conn, saved.id).unwrap());
+        assert!(load_all_applications(&mut conn).unwrap().is_empty());
+    }
 }
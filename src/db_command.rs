@@ -0,0 +1,180 @@
+use crate::cli_structure::{DbArgs, DbCommand, ExportFormat, MigrateArgs};
+use crate::config_parse::{SqliteFile, connect_db, connect_engine, open_db, open_db_creating};
+use crate::global_conf::AppContext;
+use crate::pending_saves::{flush_pending, pending_saves_path};
+use rusty_cv_creator::database::DbConnection;
+use rusty_cv_creator::migrations::{MigrationState, migration_status, run_pending_migrations};
+use rusty_cv_creator::sync::{SyncSide, sync_databases};
+use rusty_cv_creator::transfer::{
+    TransferFormat, export_applications, import_applications, parse_export,
+};
+use std::fs;
+
+/// Run a `db` subcommand. Returns an empty path: there is no PDF to show.
+pub fn run_db_command(
+    ctx: &AppContext,
+    args: &DbArgs,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match &args.command {
+        DbCommand::Init => {
+            let (mut conn, sqlite_file) = open_db_creating(ctx)?;
+            let location = match sqlite_file {
+                Some(file) => describe_sqlite_file(&file),
+                None => match ctx.get_user_input_db_url() {
+                    Ok(url) => format!("Using the PostgreSQL database {url}"),
+                    Err(_) => "Using an in-memory SQLite database".to_string(),
+                },
+            };
+            print!("{location}\n{}", apply_pending(&mut conn)?);
+        }
+        DbCommand::Migrate(migrate_args) => {
+            let mut conn = open_db(ctx)?;
+            print!("{}", migrate(&mut conn, migrate_args)?);
+        }
+        DbCommand::Sync(sync_args) => {
+            let mut local = connect_engine(ctx, "sqlite")?;
+            let mut remote = connect_engine(ctx, "postgres")?;
+            let report = sync_databases(
+                &mut local,
+                &mut remote,
+                sync_args.prefer.map(SyncSide::from),
+                ctx.is_dry_run(),
+            )?;
+            print!("{report}");
+        }
+        DbCommand::FlushPending => {
+            let path = pending_saves_path(ctx)?;
+            let mut conn = connect_engine(ctx, &ctx.get_user_input_db_engine()?)?;
+            print!("{}", flush_pending(&mut conn, &path, ctx.is_dry_run())?);
+        }
+        DbCommand::Export(export_args) => {
+            let format = transfer_format(export_args.format, export_args.output.as_deref());
+            let export = export_applications(&mut connect_db(ctx)?, format)?;
+            match &export_args.output {
+                Some(output) => {
+                    fs::write(output, export)
+                        .map_err(|e| format!("Could not write the export to {output}: {e}"))?;
+                    eprintln!("Exported the applications to {output}");
+                }
+                None => print!("{export}"),
+            }
+        }
+        DbCommand::Import(import_args) => {
+            let input = fs::read_to_string(&import_args.file)
+                .map_err(|e| format!("Could not read {}: {e}", import_args.file))?;
+            let format = transfer_format(import_args.format, Some(&import_args.file));
+            let applications = parse_export(&input, format)?;
+            let report =
+                import_applications(&mut connect_db(ctx)?, &applications, ctx.is_dry_run())?;
+            print!("{report}");
+        }
+    }
+    Ok(String::new())
+}
+
+/// `db migrate`: apply the pending migrations, or with `--status` only list
+/// every migration. Returns the report to print.
+fn migrate(
+    conn: &mut DbConnection,
+    args: &MigrateArgs,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if args.status {
+        return Ok(status_report(&migration_status(conn)?));
+    }
+
+    apply_pending(conn)
+}
+
+/// Apply the pending migrations and list them, one per line.
+fn apply_pending(conn: &mut DbConnection) -> Result<String, Box<dyn std::error::Error>> {
+    let applied = run_pending_migrations(conn)?;
+    if applied.is_empty() {
+        return Ok("The database schema is up to date\n".to_string());
+    }
+    Ok(applied
+        .iter()
+        .map(|name| format!("Applied {name}\n"))
+        .collect())
+}
+
+/// The format given, else the one the file name suggests.
+fn transfer_format(format: Option<ExportFormat>, file: Option<&str>) -> TransferFormat {
+    match format {
+        Some(format) => format.into(),
+        None if file.is_some_and(|file| file.to_lowercase().ends_with(".csv")) => {
+            TransferFormat::Csv
+        }
+        None => TransferFormat::Json,
+    }
+}
+
+/// The first line of the `db init` report.
+fn describe_sqlite_file(file: &SqliteFile) -> String {
+    if file.created {
+        format!("Created the SQLite database {}", file.path.display())
+    } else {
+        format!("The SQLite database {} already exists", file.path.display())
+    }
+}
+
+/// One line per migration, `[x]` when applied, then the pending count.
+fn status_report(states: &[MigrationState]) -> String {
+    let mut report: String = states
+        .iter()
+        .map(|state| {
+            let mark = if state.applied { 'x' } else { ' ' };
+            format!("[{mark}] {}\n", state.name)
+        })
+        .collect();
+    let pending = states.iter().filter(|state| !state.applied).count();
+    report.push_str(&format!("{pending} pending migration(s)\n"));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::prelude::*;
+
+    fn fresh_sqlite() -> DbConnection {
+        DbConnection::Sqlite(SqliteConnection::establish(":memory:").unwrap())
+    }
+
+    #[test]
+    fn test_transfer_format_follows_the_flag_then_the_extension() {
+        assert_eq!(transfer_format(None, None), TransferFormat::Json);
+        assert_eq!(
+            transfer_format(None, Some("backup.CSV")),
+            TransferFormat::Csv
+        );
+        assert_eq!(
+            transfer_format(Some(ExportFormat::Json), Some("backup.csv")),
+            TransferFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_migrate_status_lists_without_applying() {
+        let mut conn = fresh_sqlite();
+
+        let report = migrate(&mut conn, &MigrateArgs { status: true }).unwrap();
+
+        assert!(report.starts_with("[ ] 2024-06-06-052525_cvs\n"));
+        let total = migration_status(&mut conn).unwrap().len();
+        assert!(report.ends_with(&format!("{total} pending migration(s)\n")));
+    }
+
+    #[test]
+    fn test_migrate_applies_then_reports_up_to_date() {
+        let mut conn = fresh_sqlite();
+
+        let report = migrate(&mut conn, &MigrateArgs::default()).unwrap();
+        assert!(report.starts_with("Applied 2024-06-06-052525_cvs\n"));
+
+        let again = migrate(&mut conn, &MigrateArgs::default()).unwrap();
+        assert_eq!(again, "The database schema is up to date\n");
+        let status = migrate(&mut conn, &MigrateArgs { status: true }).unwrap();
+        assert!(status.starts_with("[x] 2024-06-06-052525_cvs\n"));
+        assert!(status.ends_with("\n0 pending migration(s)\n"));
+    }
+}
@@ -0,0 +1,296 @@
+//! Where an application's files go: the `[destination]` patterns.
+//!
+//! - `working_dir_pattern`, under `cv_path`: the working directory the
+//!   template is copied to and built in. Default `{year}/{date}_{company}_{job}`.
+//! - `output_dir_pattern`, under `output_pdf`: where the PDFs are published.
+//!   Default `{year}`.
+//! - `filename_pattern`: the name of the published CV, without `.pdf`; the
+//!   cover letter gets `-cover-letter` appended. Default `{date}-{job}-{company}`.
+//! - `sibling_copy`: also keep the PDFs next to the working directory, which
+//!   is removed after the build. Default `true`.
+//!
+//! The patterns are checked when the program starts, so a typo stops every
+//! command instead of the first insert after the build.
+
+use crate::config_parse::get_variable_from_config_file;
+use crate::global_conf::AppContext;
+use crate::helpers::fix_home_directory_path;
+use crate::path_slug::slugify;
+
+/// The placeholders a `[destination]` pattern may use.
+pub const LAYOUT_PLACEHOLDERS: [&str; 7] = [
+    "{date}",
+    "{year}",
+    "{company}",
+    "{job}",
+    "{variant}",
+    "{prefix}",
+    "{id}",
+];
+
+const DEFAULT_WORKING_DIR: &str = "{year}/{date}_{company}_{job}";
+const DEFAULT_OUTPUT_DIR: &str = "{year}";
+const DEFAULT_FILENAME: &str = "{date}-{job}-{company}";
+
+/// The validated `[destination]` patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestinationLayout {
+    pub working_dir: String,
+    pub output_dir: String,
+    pub filename: String,
+    pub sibling_copy: bool,
+}
+
+impl Default for DestinationLayout {
+    fn default() -> Self {
+        Self {
+            working_dir: DEFAULT_WORKING_DIR.to_string(),
+            output_dir: DEFAULT_OUTPUT_DIR.to_string(),
+            filename: DEFAULT_FILENAME.to_string(),
+            sibling_copy: true,
+        }
+    }
+}
+
+/// The values of the placeholders for one application. The variant is only
+/// known once the template is copied, so the working directory cannot use it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutValues {
+    pub date: String,
+    pub year: String,
+    pub company: String,
+    pub job: String,
+    pub variant: Option<String>,
+    pub prefix: String,
+    pub id: String,
+}
+
+impl LayoutValues {
+    /// The values for `job_title` at `company` in this run: today's date, the
+    /// configured `cv_file_prefix` and the first 8 hex digits of the
+    /// application id.
+    pub fn from_context(
+        ctx: &AppContext,
+        job_title: &str,
+        company: &str,
+        variant: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut id = ctx.get_application_id().simple().to_string();
+        id.truncate(8);
+        Ok(Self {
+            date: ctx.get_today_str_yyyy_mm_dd(),
+            year: ctx.get_year_str(),
+            company: company.to_string(),
+            job: job_title.to_string(),
+            variant: variant.map(str::to_string),
+            prefix: get_variable_from_config_file(ctx, "cv", "cv_file_prefix")?,
+            id,
+        })
+    }
+}
+
+impl DestinationLayout {
+    /// Read and validate the `[destination]` patterns; unset ones keep their
+    /// default.
+    pub fn from_context(ctx: &AppContext) -> Result<Self, Box<dyn std::error::Error>> {
+        let optional =
+            |variable: &str| get_variable_from_config_file(ctx, "destination", variable).ok();
+        let defaults = Self::default();
+        let sibling_copy = match optional("sibling_copy") {
+            Some(value) => value.trim().parse().map_err(|_| {
+                format!("[destination] sibling_copy must be true or false, not '{value}'")
+            })?,
+            None => defaults.sibling_copy,
+        };
+        let layout = Self {
+            working_dir: optional("working_dir_pattern").unwrap_or(defaults.working_dir),
+            output_dir: optional("output_dir_pattern").unwrap_or(defaults.output_dir),
+            filename: optional("filename_pattern").unwrap_or(defaults.filename),
+            sibling_copy,
+        };
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// Every pattern uses known placeholders only and stays inside its base
+    /// directory; the working directory does not depend on the variant and the
+    /// file name is a single path component.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        validate_pattern("working_dir_pattern", &self.working_dir)?;
+        validate_pattern("output_dir_pattern", &self.output_dir)?;
+        validate_pattern("filename_pattern", &self.filename)?;
+        if self.working_dir.contains("{variant}") {
+            return Err(format!(
+                "[destination] working_dir_pattern '{}' cannot use {{variant}}: the variant is \
+                 chosen after the template is copied into the working directory",
+                self.working_dir
+            )
+            .into());
+        }
+        if self.filename.contains('/') {
+            return Err(format!(
+                "[destination] filename_pattern '{}' is a file name and cannot contain '/'",
+                self.filename
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// The working directory under `cv_path` for `values`.
+    pub fn working_dir(&self, cv_path: &str, values: &LayoutValues) -> String {
+        fix_home_directory_path(&format!("{cv_path}/{}", fill(&self.working_dir, values)))
+    }
+
+    /// The directory under `output_pdf` the PDFs are published to.
+    pub fn output_dir(&self, output_pdf: &str, values: &LayoutValues) -> String {
+        format!("{output_pdf}/{}", fill(&self.output_dir, values))
+    }
+
+    /// The name the CV is published under.
+    pub fn pdf_name(&self, values: &LayoutValues) -> String {
+        format!("{}.pdf", fill(&self.filename, values))
+    }
+}
+
+/// The name of the cover letter published next to the CV `pdf_name`:
+/// `<CV name>-cover-letter.pdf`.
+pub fn cover_letter_name_of(pdf_name: &str) -> String {
+    let stem = pdf_name.strip_suffix(".pdf").unwrap_or(pdf_name);
+    format!("{stem}-cover-letter.pdf")
+}
+
+/// `pattern` with each placeholder replaced by the slug of its value.
+fn fill(pattern: &str, values: &LayoutValues) -> String {
+    let replacements = [
+        ("{date}", values.date.as_str()),
+        ("{year}", values.year.as_str()),
+        ("{company}", values.company.as_str()),
+        ("{job}", values.job.as_str()),
+        ("{variant}", values.variant.as_deref().unwrap_or_default()),
+        ("{prefix}", values.prefix.as_str()),
+        ("{id}", values.id.as_str()),
+    ];
+    replacements
+        .iter()
+        .fold(pattern.to_string(), |path, (placeholder, value)| {
+            path.replace(placeholder, &slugify(value))
+        })
+}
+
+/// A pattern must not be empty, absolute or climb out of its base directory,
+/// and may use no placeholder but `LAYOUT_PLACEHOLDERS`.
+fn validate_pattern(key: &str, pattern: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if pattern.trim().is_empty() {
+        return Err(format!("[destination] {key} is empty").into());
+    }
+    if pattern.starts_with('/') || pattern.split('/').any(|part| part == "..") {
+        return Err(format!(
+            "[destination] {key} '{pattern}' must stay inside its base directory \
+             (no leading '/', no '..')"
+        )
+        .into());
+    }
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end + 1)
+            .ok_or_else(|| format!("[destination] {key} '{pattern}' has an unclosed '{{'"))?;
+        let placeholder = &rest[start..end];
+        if !LAYOUT_PLACEHOLDERS.contains(&placeholder) {
+            return Err(format!(
+                "[destination] {key} '{pattern}' uses the unknown placeholder {placeholder} \
+                 (expected one of {LAYOUT_PLACEHOLDERS:?})"
+            )
+            .into());
+        }
+        rest = &rest[end..];
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> LayoutValues {
+        LayoutValues {
+            date: "2026-10-18".to_string(),
+            year: "2026".to_string(),
+            company: "ACME Corp".to_string(),
+            job: "Senior SRE".to_string(),
+            variant: Some("senior-sre".to_string()),
+            prefix: "PivaFrancesco".to_string(),
+            id: "1a2b3c4d".to_string(),
+        }
+    }
+
+    fn layout(working_dir: &str, output_dir: &str, filename: &str) -> DestinationLayout {
+        DestinationLayout {
+            working_dir: working_dir.to_string(),
+            output_dir: output_dir.to_string(),
+            filename: filename.to_string(),
+            sibling_copy: true,
+        }
+    }
+
+    #[test]
+    fn test_default_layout_keeps_the_historical_paths() {
+        let layout = DestinationLayout::default();
+
+        assert_eq!(
+            layout.working_dir("/cvs", &values()),
+            "/cvs/2026/2026-10-18_ACME-Corp_Senior-SRE"
+        );
+        assert_eq!(layout.output_dir("/out", &values()), "/out/2026");
+        assert_eq!(
+            layout.pdf_name(&values()),
+            "2026-10-18-Senior-SRE-ACME-Corp.pdf"
+        );
+        assert_eq!(
+            cover_letter_name_of(&layout.pdf_name(&values())),
+            "2026-10-18-Senior-SRE-ACME-Corp-cover-letter.pdf"
+        );
+    }
+
+    #[test]
+    fn test_patterns_fill_every_placeholder() {
+        let layout = layout(
+            "{company}/{id}",
+            "{year}/{company}",
+            "{prefix}_{variant}_{job}_{date}",
+        );
+
+        assert_eq!(
+            layout.working_dir("/cvs", &values()),
+            "/cvs/ACME-Corp/1a2b3c4d"
+        );
+        assert_eq!(layout.output_dir("/out", &values()), "/out/2026/ACME-Corp");
+        assert_eq!(
+            layout.pdf_name(&values()),
+            "PivaFrancesco_senior-sre_Senior-SRE_2026-10-18.pdf"
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_patterns() {
+        let error = |layout: DestinationLayout| layout.validate().unwrap_err().to_string();
+
+        assert!(
+            layout("{year}/{company}", "{year}", "{job}")
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            error(layout("{year}/{title}", "{year}", "{job}"))
+                .contains("unknown placeholder {title}")
+        );
+        assert!(error(layout("{year", "{year}", "{job}")).contains("unclosed"));
+        assert!(error(layout("{variant}", "{year}", "{job}")).contains("cannot use {variant}"));
+        assert!(error(layout("{year}", "{year}", "{year}/{job}")).contains("cannot contain '/'"));
+        assert!(error(layout("{year}", "/abs", "{job}")).contains("inside its base directory"));
+        assert!(error(layout("../{job}", "{year}", "{job}")).contains("inside its base"));
+        assert!(error(layout("{year}", "{year}", " ")).contains("filename_pattern is empty"));
+    }
+}
@@ -1,23 +1,24 @@
-use crate::command_runner::{CommandOutcome, CommandRunner};
+use crate::artifacts::{KeepArtifacts, archive_artifacts, artifacts_archive_path};
+use crate::builder::{Builder, PageCountStrategy};
+use crate::command_runner::{CommandOutcome, CommandRunner, PlannedCommand};
 use crate::config_parse::get_variable_from_config_file;
+use crate::destination_layout::{DestinationLayout, LayoutValues, cover_letter_name_of};
 use crate::global_conf::AppContext;
 use crate::helpers::{clean_string_from_quotes, fix_home_directory_path};
+use crate::job_description::{
+    JobDescription, JobMatchReport, VariantScore, best_variant, cv_sources_text,
+};
+use crate::overflow::{Compaction, PageOverflow, fit_page_contract};
+use crate::path_slug::{unique_dir_path, unique_file_path};
+use crate::pdf_pages::{last_page_text_lines, pdf_page_count};
 use crate::template_source::{AuthMode, resolve_template_for_config};
-use chrono::{DateTime, Local};
+use crate::tex_diagnostics::BuildFailure;
+use crate::variant_catalogue::VariantCatalogue;
 use log::{error, info, warn};
 use std::fs;
 use std::io::Error;
 use std::path::Path;
 
-/// The CV variants supported by the template repository. Each one maps to a
-/// driver file `<prefix>-<variant>.tex` and is built with `just build <variant>`.
-pub const CV_VARIANTS: [&str; 4] = [
-    "senior-devops",
-    "senior-platform-engineer",
-    "senior-sre",
-    "engineering-manager",
-];
-
 fn check_dir_exists(dir: &str) -> bool {
     Path::new(dir).is_dir()
 }
@@ -27,59 +28,109 @@ fn check_file_exists(dir: &str, file: &str) -> bool {
     Path::new(&full_path).is_file()
 }
 
-/// Infer the CV variant from keywords in the job title.
-///
-/// `engineering-manager` is checked first on purpose: the manager variant's
-/// title also contains "DevOps" and "Platform", so a job such as
-/// "Engineering Manager - DevOps" must not be classified as `senior-devops`.
-fn infer_variant_from_job_title(job_title: &str) -> Option<&'static str> {
-    let jt = job_title.to_lowercase();
-    if jt.contains("manager")
-        || jt.contains("management")
-        || jt.contains("head of")
-        || jt.contains("lead")
-    {
-        Some("engineering-manager")
-    } else if jt.contains("platform") {
-        Some("senior-platform-engineer")
-    } else if jt.contains("sre") || jt.contains("site reliability") || jt.contains("reliability") {
-        Some("senior-sre")
-    } else if jt.contains("devops") || jt.contains("dev ops") {
-        Some("senior-devops")
-    } else {
-        None
-    }
-}
-
-/// Resolve which CV variant to build.
+/// Resolve which CV variant to build from `catalogue`.
 ///
-/// Precedence: an explicit (and valid) `--variant` flag wins; otherwise the
-/// variant is inferred from the job title; otherwise the configured default is
-/// used.
+/// Precedence: an explicit (and catalogued) `--variant` flag wins; then the
+/// best match for the job description, when one scored at all; then the
+/// variant inferred from the job title; then the default, which must itself be
+/// in the catalogue.
 pub fn resolve_variant(
+    catalogue: &VariantCatalogue,
     variant_flag: Option<&String>,
+    best_match: Option<&VariantScore>,
     job_title: &str,
     default_variant: &str,
-) -> String {
+) -> Result<String, Box<dyn std::error::Error>> {
     if let Some(flag) = variant_flag {
         let flag = flag.trim();
-        if CV_VARIANTS.contains(&flag) {
+        if catalogue.contains(flag) {
             info!("✅ Using variant from --variant flag: {flag}");
-            return flag.to_string();
+            return Ok(flag.to_string());
         }
         warn!(
-            "Unknown variant '{flag}' (expected one of {CV_VARIANTS:?}); \
-             inferring from the job title instead"
+            "Unknown variant '{flag}' (expected one of {:?}); \
+             choosing one instead",
+            catalogue.names()
+        );
+    }
+
+    if let Some(best) = best_match {
+        info!(
+            "✅ Variant '{}' matches the job description best (score {})",
+            best.variant, best.score
         );
+        return Ok(best.variant.clone());
     }
 
-    if let Some(inferred) = infer_variant_from_job_title(job_title) {
+    if let Some(inferred) = catalogue.infer_from_job_title(job_title) {
         info!("✅ Inferred variant '{inferred}' from job title: {job_title}");
-        return inferred.to_string();
+        return Ok(inferred.to_string());
     }
 
+    if !catalogue.contains(default_variant) {
+        return Err(format!(
+            "Default variant '{default_variant}' is not one of the available variants {:?}; \
+             set [variant] default or pass --variant",
+            catalogue.names()
+        )
+        .into());
+    }
     info!("✅ Falling back to default variant: {default_variant}");
-    default_variant.to_string()
+    Ok(default_variant.to_string())
+}
+
+/// The variant to build and, with `insert --job-description`, how the posting
+/// scored against the catalogue.
+#[derive(Debug, Clone)]
+pub struct VariantChoice {
+    pub variant: String,
+    pub job_match: Option<JobMatchReport>,
+}
+
+/// Load the variant catalogue for `template_dir` and resolve the variant to
+/// build. The default is `[variant] default`, else the catalogue's
+/// lowest-priority variant (`senior-devops` for the built-in catalogue).
+///
+/// With a job description, the report's missing terms are read from the
+/// chosen variant's sources in `template_dir` (none without one).
+pub fn select_variant(
+    ctx: &AppContext,
+    template_dir: Option<&Path>,
+    prefix: &str,
+    variant_flag: Option<&String>,
+    job_title: &str,
+) -> Result<VariantChoice, Box<dyn std::error::Error>> {
+    let catalogue = VariantCatalogue::load(ctx, template_dir, prefix)?;
+    let posting = ctx
+        .get_job_description()
+        .map(|path| JobDescription::read(&path))
+        .transpose()?;
+    let scores = posting.as_ref().map(|posting| posting.score(&catalogue));
+    let default_variant = match get_variable_from_config_file(ctx, "variant", "default") {
+        Ok(value) => value,
+        Err(_) => catalogue
+            .names()
+            .last()
+            .map(|name| (*name).to_string())
+            .ok_or("The variant catalogue is empty")?,
+    };
+    let variant = resolve_variant(
+        &catalogue,
+        variant_flag,
+        scores.as_deref().and_then(best_variant),
+        job_title,
+        &default_variant,
+    )?;
+
+    let job_match = posting.map(|posting| {
+        let cv_text = template_dir.and_then(|dir| {
+            cv_sources_text(dir, prefix, &variant, &catalogue)
+                .map_err(|e| warn!("Could not read the CV sources in {}: {e}", dir.display()))
+                .ok()
+        });
+        posting.report(&catalogue, &variant, cv_text.as_deref())
+    });
+    Ok(VariantChoice { variant, job_match })
 }
 
 /// Build configuration read from the INI file: the driver/output filename
@@ -87,53 +138,148 @@ pub fn resolve_variant(
 #[derive(Debug, Clone)]
 pub struct BuildConfig {
     pub prefix: String,
-    pub builder: String,
-    pub recipe: String,
-    /// Page-count contract for the rendered PDF: a build whose transcript
-    /// reports more pages than this fails, even when the builder exited 0.
+    /// The backend named by `[build] builder` (a `just` recipe by default).
+    pub builder: Builder,
+    /// Page-count contract for the rendered PDF: a build whose PDF (or, when
+    /// it cannot be read, transcript) has more pages than this fails, even
+    /// when the builder exited 0.
     pub max_pages: u32,
-    /// Justfile variable override prepended to the build args (e.g.
-    /// `tectonic=tectonic --print`) so the TeX transcript — carrying the
-    /// `Output written on ... (N pages` line — reaches the captured streams.
-    pub page_count_probe: String,
+    /// The same contract for the cover letter (`insert --cover-letter`).
+    pub cover_letter_max_pages: u32,
+    /// The compaction levels to retry an overflowing CV at
+    /// (`[build] compaction_levels`, `compaction_macro`); `None` fails at once.
+    pub compaction: Option<Compaction>,
 }
 
 impl BuildConfig {
     pub fn from_context(ctx: &AppContext) -> Result<Self, Box<dyn std::error::Error>> {
+        let optional = |variable: &str| get_variable_from_config_file(ctx, "build", variable).ok();
         Ok(Self {
             prefix: get_variable_from_config_file(ctx, "cv", "cv_file_prefix")?,
-            builder: get_variable_from_config_file(ctx, "build", "builder")
-                .unwrap_or_else(|_| "just".to_string()),
-            recipe: get_variable_from_config_file(ctx, "build", "recipe")
-                .unwrap_or_else(|_| "build".to_string()),
-            max_pages: get_variable_from_config_file(ctx, "build", "max_pages")
-                .unwrap_or_else(|_| "2".to_string())
+            builder: Builder::from_config(
+                &optional("builder").unwrap_or_else(|| "just".to_string()),
+                optional("recipe"),
+                optional("page_count_probe"),
+                optional("command"),
+            )?,
+            max_pages: optional("max_pages")
+                .unwrap_or_else(|| "2".to_string())
+                .parse()?,
+            cover_letter_max_pages: optional("cover_letter_max_pages")
+                .unwrap_or_else(|| "1".to_string())
                 .parse()?,
-            page_count_probe: get_variable_from_config_file(ctx, "build", "page_count_probe")
-                .unwrap_or_else(|_| "tectonic=tectonic --print".to_string()),
+            compaction: Compaction::from_config(
+                optional("compaction_macro"),
+                optional("compaction_levels"),
+            )?,
         })
     }
+
+    /// The command that builds `variant` in the working directory `cv_dir`.
+    pub fn build_command(&self, cv_dir: &str, variant: &str) -> PlannedCommand {
+        self.builder.command(&self.prefix, variant, cv_dir)
+    }
+}
+
+/// The driver-name suffix of the cover letter for `variant`: its driver is
+/// `<prefix>-cover-<variant>.tex` (`.typ` for typst), built like a variant.
+pub fn cover_letter_document(variant: &str) -> String {
+    format!("cover-{variant}")
 }
 
-/// Build a single CV variant inside `cv_dir` using the project Justfile.
+/// Build a single CV variant inside `cv_dir` with the configured backend.
 ///
-/// Runs `<builder> <probe> <recipe> <variant>` (default
+/// Runs `cfg.builder` on the driver (default
 /// `just tectonic=tectonic --print build <variant>`) in the copied template
 /// directory, which produces `<prefix>-<variant>.pdf` next to the driver file.
-/// The probe is a Justfile variable override that routes the TeX transcript to
-/// the captured streams, so a successful build is additionally held to the
-/// page-count contract (`cfg.max_pages`) — see `enforce_page_contract`.
+/// A successful build is additionally held to the page-count contract
+/// (`cfg.max_pages`) on that PDF — see `enforce_page_contract`. A CV over it
+/// is rebuilt at each of `cfg.compaction`'s levels until it fits, and the
+/// level it fit at returned (`None` when it fit as built).
 /// All build artifacts (pdf, log, aux, ...) land in `cv_dir`, so there is no
 /// hard-coded output directory.
 ///
-/// Tool availability (`just`, `tectonic`) is checked by the caller before this
-/// runs; here we only validate the inputs and invoke the builder via `runner`.
+/// Tool availability (`cfg.builder.required_tools()`) is checked by the caller
+/// before this runs; here we only validate the inputs and invoke the builder
+/// via `runner`.
 pub fn compile_cv(
     runner: &dyn CommandRunner,
     cv_dir: &str,
     variant: &str,
     cfg: &BuildConfig,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    fit_page_contract(Path::new(cv_dir), cfg.compaction.as_ref(), || {
+        compile_document(runner, cv_dir, variant, cfg.max_pages, cfg)
+    })
+}
+
+/// Build the cover letter for `variant` (`<prefix>-cover-<variant>.tex`) the
+/// same way as the CV, held to `cfg.cover_letter_max_pages`.
+pub fn compile_cover_letter(
+    runner: &dyn CommandRunner,
+    cv_dir: &str,
+    variant: &str,
+    cfg: &BuildConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    compile_document(
+        runner,
+        cv_dir,
+        &cover_letter_document(variant),
+        cfg.cover_letter_max_pages,
+        cfg,
+    )?;
+    Ok(())
+}
+
+/// Build the driver `<prefix>-<variant>.tex` in `cv_dir` and check the page
+/// count against `max_pages`, returning it.
+fn compile_document(
+    runner: &dyn CommandRunner,
+    cv_dir: &str,
+    variant: &str,
+    max_pages: u32,
+    cfg: &BuildConfig,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let outcome = run_builder(runner, cv_dir, variant, cfg)?;
+    let pdf = Path::new(cv_dir).join(format!("{}-{variant}.pdf", cfg.prefix));
+    enforce_page_contract(&outcome, &pdf, max_pages, cfg.builder.page_count_strategy())
+}
+
+/// Run the builder for the driver `<prefix>-<variant>.tex` in `cv_dir` and
+/// return its transcript. A failed run is a `BuildFailure` summarising the
+/// TeX diagnostics, with `cv_dir` left in place; the page count is left to
+/// the caller.
+pub fn run_builder(
+    runner: &dyn CommandRunner,
+    cv_dir: &str,
+    variant: &str,
+    cfg: &BuildConfig,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let outcome = invoke_builder(runner, cv_dir, variant, cfg)?;
+    if !outcome.success {
+        let failure = BuildFailure::collect(
+            &format!("{} {variant}", cfg.builder),
+            Path::new(cv_dir),
+            &format!("{}-{variant}", cfg.prefix),
+            &outcome,
+        );
+        error!("{failure}");
+        return Err(failure.into());
+    }
+
+    info!("✅ CV compiled successfully");
+    Ok(outcome)
+}
+
+/// Check the working dir and driver file, then run the builder and return its
+/// outcome whether or not it succeeded, so the caller can read the transcript
+/// of a failed build.
+pub fn invoke_builder(
+    runner: &dyn CommandRunner,
+    cv_dir: &str,
+    variant: &str,
+    cfg: &BuildConfig,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
     info!("CV_DIR: {cv_dir}");
     info!("CV_VARIANT: {variant}");
 
@@ -144,7 +290,7 @@ pub fn compile_cv(
         return Err(format!("Directory does not exist: {cv_dir}").into());
     }
 
-    let driver_file = format!("{}-{variant}.tex", cfg.prefix);
+    let driver_file = cfg.builder.driver_file(&cfg.prefix, variant);
     if check_file_exists(cv_dir, &driver_file) {
         info!("✅ Driver file exists: {driver_file}");
     } else {
@@ -152,57 +298,71 @@ pub fn compile_cv(
         return Err(format!("Driver file does not exist: {cv_dir}/{driver_file}").into());
     }
 
-    info!(
-        "✅ Building CV: {} {} {variant} (in {cv_dir})",
-        cfg.builder, cfg.recipe
-    );
+    let command = cfg.build_command(cv_dir, variant);
+    info!("✅ Building CV: {command}");
 
-    let outcome = runner.run_capturing(
-        &cfg.builder,
-        &[&cfg.page_count_probe, &cfg.recipe, variant],
-        Some(cv_dir),
-    )?;
-    if !outcome.success {
-        error!(
-            "Error building CV with: {} {} {variant}",
-            cfg.builder, cfg.recipe
-        );
-        return Err(format!(
-            "Error building CV with: {} {} {variant}",
-            cfg.builder, cfg.recipe
-        )
-        .into());
-    }
-
-    info!("✅ CV compiled successfully");
-    enforce_page_contract(&outcome, cfg.max_pages)
+    let args: Vec<&str> = command.args.iter().map(String::as_str).collect();
+    Ok(runner.run_capturing(&command.program, &args, command.cwd.as_deref())?)
 }
 
-/// Enforce the page-count contract on a SUCCESSFUL build: the LAST
-/// `Output written on <file> (N pages` line across stdout+stderr combined
-/// decides the outcome (tectonic runs multiple passes and routes notes to
-/// stderr). No page line at all fails closed — a transcript format change
-/// must break loudly, never silently ship an oversized CV.
-fn enforce_page_contract(
+/// Enforce the page-count contract on a SUCCESSFUL build whose output is
+/// `pdf`, returning its page count: see `built_page_count` for where the
+/// count comes from. Too many pages is a `PageOverflow`, with the lines on
+/// the last page when the PDF can be read. No count at all fails closed — a
+/// transcript format change or an unreadable PDF must break loudly, never
+/// silently ship an oversized CV.
+pub fn enforce_page_contract(
     outcome: &CommandOutcome,
+    pdf: &Path,
     max_pages: u32,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let transcript = format!("{}\n{}", outcome.stdout, outcome.stderr);
-    match last_reported_page_count(&transcript) {
+    strategy: PageCountStrategy,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    match built_page_count(outcome, pdf, strategy) {
         Some(pages) if pages > max_pages => {
-            error!("CV is {pages} pages; the contract allows at most {max_pages}");
-            Err(format!("CV is {pages} pages; the contract allows at most {max_pages}").into())
+            let overflow = PageOverflow {
+                pages,
+                max_pages,
+                last_page_lines: last_page_text_lines(pdf).ok(),
+            };
+            error!("{overflow}");
+            Err(overflow.into())
         }
         Some(pages) => {
             info!("✅ Page-count contract met: {pages} <= {max_pages} pages");
-            Ok(())
+            Ok(pages)
         }
         None => {
-            error!("Build transcript reported no page count; failing closed");
-            Err("Build transcript reported no page count (expected an \
-                 'Output written on ... (N pages' line); failing closed \
-                 rather than shipping an unverified CV"
-                .into())
+            error!("No page count for {}; failing closed", pdf.display());
+            Err(format!(
+                "No page count for {}: the PDF could not be read and the build \
+                 transcript has no 'Output written on ... (N pages' line; \
+                 failing closed rather than shipping an unverified CV",
+                pdf.display()
+            )
+            .into())
+        }
+    }
+}
+
+/// The page count of a successful build: counted in the produced `pdf` when
+/// it can be read (whatever the builder), otherwise — unless the backend
+/// prints no TeX transcript — the LAST `Output written on <file> (N pages`
+/// line across stdout+stderr combined (tectonic runs multiple passes and
+/// routes notes to stderr).
+pub fn built_page_count(
+    outcome: &CommandOutcome,
+    pdf: &Path,
+    strategy: PageCountStrategy,
+) -> Option<u32> {
+    match pdf_page_count(pdf) {
+        Ok(pages) => Some(pages),
+        Err(e) if strategy == PageCountStrategy::PdfOnly => {
+            error!("{e}");
+            None
+        }
+        Err(e) => {
+            warn!("{e}; falling back to the build transcript");
+            last_reported_page_count(&format!("{}\n{}", outcome.stdout, outcome.stderr))
         }
     }
 }
@@ -210,7 +370,7 @@ fn enforce_page_contract(
 /// The page count reported by the LAST `Output written on <file> (N pages`
 /// transcript line, if any. Only the final pass's count reflects the artifact
 /// actually written.
-fn last_reported_page_count(transcript: &str) -> Option<u32> {
+pub fn last_reported_page_count(transcript: &str) -> Option<u32> {
     transcript
         .lines()
         .rev()
@@ -235,36 +395,25 @@ pub fn create_directory(
     job_title: &str,
     company_name: &str,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let var = get_variable_from_config_file(ctx, "destination", "cv_path")?;
-
-    let destination_folder = fix_home_directory_path(&var);
-    let now = ctx.get_today();
+    let (cv_template_path, full_destination_path) =
+        match prepare_path_for_new_cv(ctx, runner, job_title, company_name) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("{e:?}");
+                return Err(format!("{e:?}").to_string().into());
+            }
+        };
 
-    match prepare_year_dir(&destination_folder, now) {
-        Ok(y) => info!("✅ Year directory created successfully: {y:}"),
+    match prepare_parent_dir(&full_destination_path) {
+        Ok(parent) => info!("✅ Parent directory created successfully: {parent:}"),
         Err(e) => {
-            error!("Error creating year directory: {e:}");
-            return Err(format!("Error creating year directory: {e:}")
+            error!("Error creating the parent directory: {e:}");
+            return Err(format!("Error creating the parent directory: {e:}")
                 .to_string()
                 .into());
         }
     }
 
-    let (cv_template_path, full_destination_path) = match prepare_path_for_new_cv(
-        ctx,
-        runner,
-        job_title,
-        company_name,
-        &destination_folder,
-        now,
-    ) {
-        Ok(s) => s,
-        Err(e) => {
-            error!("{e:?}");
-            return Err(format!("{e:?}").to_string().into());
-        }
-    };
-
     match copy_dir::copy_dir(cv_template_path, full_destination_path.clone()) {
         Ok(_) => info!("✅ Directory created & copied successfully"),
         Err(e) => {
@@ -285,46 +434,23 @@ fn prepare_path_for_new_cv(
     runner: &dyn CommandRunner,
     job_title: &str,
     company_name: &str,
-    destination_folder: &str,
-    now: &DateTime<Local>,
 ) -> Result<(String, String), Box<dyn std::error::Error>> {
-    let var = get_variable_from_config_file(ctx, "cv", "cv_template_path")?;
-
-    // TS-05/D8: the `--repo` flag overrides the INI path (flag > INI) BEFORE the
-    // `~` expansion, so a `--repo` pointing at a local dir with a `~` is still
-    // home-expanded (AC4); with no flag the INI value is used byte-for-byte (AC3).
-    let repo_override = ctx.get_repo_override();
-    let configured_template: String =
-        fix_home_directory_path(&effective_template_path(repo_override.as_deref(), &var));
-
     // D1/D7: auto-detect a local dir vs a git URL and resolve to a local dir.
     // A git source resolves through the cache executor (probe → decide →
     // clone / fetch / reuse-stale / abort), threading the optional ref (TS-03)
     // and auth (TS-02). A local dir is an unchanged passthrough (backward compat,
     // TS-01/AC2). For token auth the secret is read from the GITHUB_TOKEN
     // environment variable via the askpass indirection — never from the INI.
-    let cache_dir = resolve_template_cache_dir(ctx);
-    // TS-05/D8: the `--branch` flag overrides the INI `cv_template_ref` (flag >
-    // INI); absent → the INI ref, which may itself be None (repo default branch).
-    let ini_ref = get_variable_from_config_file(ctx, "cv", "cv_template_ref").ok();
-    let branch_override = ctx.get_branch_override();
-    let git_ref = effective_template_ref(branch_override.as_deref(), ini_ref.as_deref());
-    let auth = resolve_template_auth(ctx)?;
+    let template = template_settings(ctx)?;
     let cv_template_path = resolve_template_for_config(
-        &configured_template,
-        &cache_dir,
-        git_ref.as_deref(),
-        auth,
+        &template.path,
+        &template.cache_dir,
+        template.git_ref.as_deref(),
+        template.auth,
         runner,
     )?;
 
-    let formatted_job_title = sanitize_for_path(job_title);
-    let formatted_company_name = sanitize_for_path(company_name);
-
-    let date_dir = now.format("%Y/%Y-%m-%d").to_string();
-    let full_destination_path = fix_home_directory_path(&format!(
-        "{destination_folder}/{date_dir}_{formatted_company_name}_{formatted_job_title}"
-    ));
+    let full_destination_path = working_dir_path(ctx, job_title, company_name)?;
 
     info!("✅ Creating directory: {full_destination_path}");
     info!("✅ Copying from: {}", cv_template_path.clone());
@@ -332,6 +458,60 @@ fn prepare_path_for_new_cv(
     Ok((cv_template_path, full_destination_path))
 }
 
+/// The configured template source, with the `--repo` / `--branch` overrides
+/// applied: everything `resolve_template_for_config` needs.
+pub struct TemplateSettings {
+    pub path: String,
+    pub cache_dir: String,
+    pub git_ref: Option<String>,
+    pub auth: AuthMode,
+}
+
+pub fn template_settings(ctx: &AppContext) -> Result<TemplateSettings, Box<dyn std::error::Error>> {
+    let var = get_variable_from_config_file(ctx, "cv", "cv_template_path")?;
+
+    // TS-05/D8: the `--repo` flag overrides the INI path (flag > INI) BEFORE the
+    // `~` expansion, so a `--repo` pointing at a local dir with a `~` is still
+    // home-expanded (AC4); with no flag the INI value is used byte-for-byte (AC3).
+    let repo_override = ctx.get_repo_override();
+    let path = fix_home_directory_path(&effective_template_path(repo_override.as_deref(), &var));
+
+    // TS-05/D8: the `--branch` flag overrides the INI `cv_template_ref` (flag >
+    // INI); absent → the INI ref, which may itself be None (repo default branch).
+    let ini_ref = get_variable_from_config_file(ctx, "cv", "cv_template_ref").ok();
+    let branch_override = ctx.get_branch_override();
+
+    Ok(TemplateSettings {
+        path,
+        cache_dir: resolve_template_cache_dir(ctx),
+        git_ref: effective_template_ref(branch_override.as_deref(), ini_ref.as_deref()),
+        auth: resolve_template_auth(ctx)?,
+    })
+}
+
+/// The configured `[destination] cv_path`, home-expanded: where the dated
+/// working directories are created.
+pub fn destination_folder(ctx: &AppContext) -> Result<String, Box<dyn std::error::Error>> {
+    let var = get_variable_from_config_file(ctx, "destination", "cv_path")?;
+    Ok(fix_home_directory_path(&var))
+}
+
+/// The working directory a CV for this application is built in:
+/// `[destination] working_dir_pattern` under `cv_path`, numbered (`-2`, ...)
+/// when a directory of that name is already there.
+pub fn working_dir_path(
+    ctx: &AppContext,
+    job_title: &str,
+    company_name: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let layout = DestinationLayout::from_context(ctx)?;
+    let values = LayoutValues::from_context(ctx, job_title, company_name, None)?;
+    let working_dir = layout.working_dir(&destination_folder(ctx)?, &values);
+    Ok(unique_dir_path(Path::new(&working_dir))
+        .display()
+        .to_string())
+}
+
 // TS-05 (DISCUSS delta L54 / D8) — CLI-override precedence `flag > INI > default`.
 // These two pure functions are the testable precedence seam. They are
 // `pub(crate)` so the in-crate `mod distill_specs_l54` specs below can drive them
@@ -373,64 +553,175 @@ fn resolve_template_auth(ctx: &AppContext) -> Result<AuthMode, Box<dyn std::erro
     }
 }
 
-fn prepare_year_dir(destination_folder: &String, now: &DateTime<Local>) -> Result<String, Error> {
-    let year_full_dir = format!("{}/{}", destination_folder, now.format("%Y"));
-    fs::create_dir_all(year_full_dir.clone())?;
-    Ok(clean_string_from_quotes(&year_full_dir.clone()))
+/// The template revision the CV in `cv_dir` is built from, recorded with the
+/// application: the commit SHA when the copied template is a git checkout,
+/// else the effective ref (`--branch` > `[cv] cv_template_ref`), if any.
+pub fn template_revision(
+    ctx: &AppContext,
+    runner: &dyn CommandRunner,
+    cv_dir: &str,
+) -> Option<String> {
+    if Path::new(cv_dir).join(".git").exists() {
+        match runner.output("git", &["-C", cv_dir, "rev-parse", "HEAD"]) {
+            Ok((true, sha)) if !sha.trim().is_empty() => return Some(sha.trim().to_string()),
+            Ok(_) => warn!("Could not read the template commit in {cv_dir}"),
+            Err(e) => warn!("Could not run git in {cv_dir}: {e:}"),
+        }
+    }
+    let ini_ref = get_variable_from_config_file(ctx, "cv", "cv_template_ref").ok();
+    effective_template_ref(ctx.get_branch_override().as_deref(), ini_ref.as_deref())
+}
+
+/// Create the directory the working directory `working_dir` is copied into.
+fn prepare_parent_dir(working_dir: &str) -> Result<String, Error> {
+    let parent = Path::new(working_dir)
+        .parent()
+        .ok_or_else(|| Error::other(format!("{working_dir} has no parent directory")))?;
+    fs::create_dir_all(parent)?;
+    Ok(clean_string_from_quotes(&parent.display().to_string()))
+}
+
+/// The PDFs `remove_created_dir_from_pro` placed in the `output_pdf` directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedPdfs {
+    pub cv: String,
+    pub cover_letter: Option<String>,
+    /// The `[build] keep_artifacts` archive, when one was written.
+    pub artifacts: Option<String>,
 }
 
-/// Make a value safe to embed in a path / file name (spaces become dashes).
-fn sanitize_for_path(value: &str) -> String {
-    value.replace(' ', "-")
+/// Where the PDFs of an application are published, from the `[destination]`
+/// layout. The name is that of the layout; `published_paths` numbers it when a
+/// file is already there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishTargets {
+    /// The directory under `output_pdf` the PDFs are copied to.
+    pub output_dir: String,
+    pub pdf_name: String,
+    /// Whether a copy is also kept next to the working directory.
+    pub sibling_copy: bool,
+}
+
+/// The publish targets of the CV built from `variant` for `job_title` at
+/// `company_name`.
+pub fn publish_targets(
+    ctx: &AppContext,
+    job_title: &str,
+    company_name: &str,
+    variant: &str,
+) -> Result<PublishTargets, Box<dyn std::error::Error>> {
+    let layout = DestinationLayout::from_context(ctx)?;
+    let values = LayoutValues::from_context(ctx, job_title, company_name, Some(variant))?;
+    let output_pdf = get_variable_from_config_file(ctx, "destination", "output_pdf")?;
+    Ok(PublishTargets {
+        output_dir: layout.output_dir(&output_pdf, &values),
+        pdf_name: layout.pdf_name(&values),
+        sibling_copy: layout.sibling_copy,
+    })
 }
 
-/// Copy the built PDF out of the working directory into the configured
-/// destinations, then remove the working directory so only the PDF remains.
+/// Copy the built PDF (and the cover letter, when one was built) out of the
+/// working directory into the configured destinations, archive the artifacts
+/// `[build] keep_artifacts` asks for next to the CV, then remove the working
+/// directory (unless `--keep-workdir`) so only the PDFs remain.
 ///
-/// Returns the path to the PDF placed in the configured `output_pdf` directory.
+/// Returns the paths of the PDFs placed in the configured `output_pdf` directory.
 pub fn remove_created_dir_from_pro(
     ctx: &AppContext,
     job_title: &str,
     company_name: &str,
+    variant: &str,
     created_cv_dir: &String,
     pdf_basename: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
+    cover_letter_basename: Option<&str>,
+) -> Result<PublishedPdfs, Box<dyn std::error::Error>> {
     let path_created_dir = Path::new(&created_cv_dir);
-    let application_date = ctx.get_today_str_yyyy_mm_dd();
-    let application_year = ctx.get_year_str();
 
-    // The PDF produced by `just build <variant>` inside the working directory.
+    // The PDFs produced by `just build <variant>` inside the working directory.
     let built_pdf = format!("{created_cv_dir}/{pdf_basename}");
-    if !Path::new(&built_pdf).is_file() {
-        error!("Built PDF not found: {built_pdf}");
-        return Err(format!("Built PDF not found: {built_pdf}").into());
+    let built_cover_letter =
+        cover_letter_basename.map(|basename| format!("{created_cv_dir}/{basename}"));
+    for built in std::iter::once(&built_pdf).chain(built_cover_letter.as_ref()) {
+        if !Path::new(built).is_file() {
+            error!("Built PDF not found: {built}");
+            return Err(format!("Built PDF not found: {built}").into());
+        }
     }
 
-    let job = sanitize_for_path(job_title);
-    let company = sanitize_for_path(company_name);
-    let final_name = format!("{application_date}-{job}-{company}.pdf");
+    let targets = publish_targets(ctx, job_title, company_name, variant)?;
+
+    // 1) The configured output location (per year by default). The cover
+    //    letter goes next to the CV, under the CV's (possibly numbered) name.
+    fs::create_dir_all(&targets.output_dir)?;
+    let with_cover_letter = built_cover_letter.is_some();
+    let (output_pdf, output_cover_letter) =
+        published_paths(&targets.output_dir, &targets.pdf_name, with_cover_letter);
+    copy_to_destination(&built_pdf, &output_pdf)?;
+    if let (Some(built), Some(output)) = (&built_cover_letter, &output_cover_letter) {
+        copy_to_destination(built, output)?;
+    }
 
-    // 1) A copy kept next to the dated working directory (survives cleanup).
+    // 2) Optionally, a copy kept next to the working directory (survives cleanup).
     let parent_dir = path_created_dir
         .parent()
         .and_then(Path::to_str)
         .ok_or("Could not determine parent directory of the CV working dir")?;
-    let sibling_pdf = format!("{parent_dir}/{final_name}");
-
-    // 2) The configured output location, organised per year.
-    let output_dir = get_variable_from_config_file(ctx, "destination", "output_pdf")?;
-    let output_year_dir = format!("{output_dir}/{application_year}");
-    fs::create_dir_all(&output_year_dir)?;
-    let output_pdf = format!("{output_year_dir}/{final_name}");
+    if targets.sibling_copy {
+        let (sibling_pdf, sibling_cover_letter) =
+            published_paths(parent_dir, &targets.pdf_name, with_cover_letter);
+        copy_to_destination(&built_pdf, &sibling_pdf)?;
+        if let (Some(built), Some(sibling)) = (&built_cover_letter, &sibling_cover_letter) {
+            copy_to_destination(built, sibling)?;
+        }
+    }
 
-    copy_to_destination(&built_pdf, &output_pdf)?;
-    copy_to_destination(&built_pdf, &sibling_pdf)?;
+    let artifacts = archive_artifacts(
+        path_created_dir,
+        KeepArtifacts::from_context(ctx)?,
+        Path::new(&artifacts_archive_path(&output_pdf)),
+    )?
+    .map(|archive| archive.display().to_string());
+    if let Some(archive) = &artifacts {
+        info!("✅ Build artifacts archived: {archive}");
+    }
 
     // Cleanup: remove the whole working directory, keeping only the PDFs above.
-    remove_cv_dir(path_created_dir)?;
-    info!("✅ Cleaned up working directory: {created_cv_dir}");
+    if ctx.keeps_workdir() {
+        println!("Working directory kept: {created_cv_dir}");
+    } else {
+        remove_cv_dir(path_created_dir)?;
+        info!("✅ Cleaned up working directory: {created_cv_dir}");
+    }
 
-    Ok(output_pdf)
+    Ok(PublishedPdfs {
+        cv: output_pdf,
+        cover_letter: output_cover_letter,
+        artifacts,
+    })
+}
+
+/// Where the CV `pdf_name` is published in `dir`, and with
+/// `with_cover_letter` its cover letter ([`cover_letter_name_of`]). When either
+/// file is already there both are numbered together (`<name>-2.pdf` and
+/// `<name>-2-cover-letter.pdf`, ...): an earlier application (or the PDF a
+/// rebuild replaces) is never overwritten, and a cover letter always carries
+/// the name of its own CV.
+pub fn published_paths(
+    dir: &str,
+    pdf_name: &str,
+    with_cover_letter: bool,
+) -> (String, Option<String>) {
+    let cover_letter_of = |cv: &Path| {
+        with_cover_letter.then(|| {
+            let name = cv.file_name().unwrap_or_default().to_string_lossy();
+            cv.with_file_name(cover_letter_name_of(&name))
+        })
+    };
+    let cv = unique_file_path(&Path::new(dir).join(pdf_name), cover_letter_of);
+    (
+        cv.display().to_string(),
+        cover_letter_of(&cv).map(|path| path.display().to_string()),
+    )
 }
 
 /// Copy a single file, returning the bytes copied on success.
@@ -447,6 +738,31 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_published_paths_number_the_cv_and_cover_letter_together() {
+        let td = TempDir::new().unwrap();
+        let dir = td.path().to_str().unwrap();
+        let path = |name: &str| td.path().join(name).display().to_string();
+        fs::write(path("X.pdf"), b"x").unwrap();
+        fs::write(path("X-2-cover-letter.pdf"), b"x").unwrap();
+
+        assert_eq!(
+            published_paths(dir, "X.pdf", true),
+            (path("X-3.pdf"), Some(path("X-3-cover-letter.pdf")))
+        );
+        assert_eq!(
+            published_paths(dir, "X.pdf", false),
+            (path("X-2.pdf"), None)
+        );
+
+        // A cover letter left by another application takes its CV's name too.
+        fs::write(path("Y-cover-letter.pdf"), b"x").unwrap();
+        assert_eq!(
+            published_paths(dir, "Y.pdf", true),
+            (path("Y-2.pdf"), Some(path("Y-2-cover-letter.pdf")))
+        );
+    }
+
     #[test]
     fn test_check_dir_exists_true_for_dir() {
         let td = TempDir::new().unwrap();
@@ -476,64 +792,80 @@ mod tests {
         ));
     }
 
-    #[test]
-    fn test_sanitize_for_path_replaces_spaces() {
-        assert_eq!(
-            sanitize_for_path("Senior DevOps Engineer"),
-            "Senior-DevOps-Engineer"
-        );
-        assert_eq!(sanitize_for_path("nospace"), "nospace");
+    fn resolve_builtin(flag: Option<&str>, job_title: &str, default: &str) -> String {
+        let flag = flag.map(str::to_string);
+        resolve_variant(
+            &VariantCatalogue::builtin(),
+            flag.as_ref(),
+            None,
+            job_title,
+            default,
+        )
+        .unwrap()
     }
 
     #[test]
-    fn test_infer_variant_manager_wins_over_devops() {
+    fn test_resolve_variant_flag_wins() {
         assert_eq!(
-            infer_variant_from_job_title("Engineering Manager - DevOps"),
-            Some("engineering-manager")
+            resolve_builtin(Some("senior-sre"), "Platform Engineer", "senior-devops"),
+            "senior-sre"
         );
     }
 
     #[test]
-    fn test_infer_variant_keywords() {
-        assert_eq!(
-            infer_variant_from_job_title("Senior Platform Engineer"),
-            Some("senior-platform-engineer")
-        );
-        assert_eq!(
-            infer_variant_from_job_title("Site Reliability Engineer"),
-            Some("senior-sre")
-        );
+    fn test_resolve_variant_invalid_flag_falls_back_to_inference() {
         assert_eq!(
-            infer_variant_from_job_title("DevOps Specialist"),
-            Some("senior-devops")
+            resolve_builtin(Some("bogus"), "Platform Engineer", "senior-devops"),
+            "senior-platform-engineer"
         );
-        assert_eq!(infer_variant_from_job_title("Accountant"), None);
     }
 
     #[test]
-    fn test_resolve_variant_flag_wins() {
-        let flag = "senior-sre".to_string();
-        assert_eq!(
-            resolve_variant(Some(&flag), "Platform Engineer", "senior-devops"),
-            "senior-sre"
+    fn test_resolve_variant_job_description_beats_the_job_title_but_not_the_flag() {
+        let catalogue = VariantCatalogue::builtin();
+        let posting = JobDescription::from_text(
+            "posting",
+            "Own our SLOs and error budgets, join the on-call rotation, run postmortems.",
         );
+        let scores = posting.score(&catalogue);
+        let best = best_variant(&scores);
+
+        let chosen =
+            resolve_variant(&catalogue, None, best, "Platform Engineer", "senior-devops").unwrap();
+        assert_eq!(chosen, "senior-sre");
+
+        let flag = "senior-devops".to_string();
+        let chosen = resolve_variant(
+            &catalogue,
+            Some(&flag),
+            best,
+            "Platform Engineer",
+            "senior-devops",
+        )
+        .unwrap();
+        assert_eq!(chosen, "senior-devops");
     }
 
     #[test]
-    fn test_resolve_variant_invalid_flag_falls_back_to_inference() {
-        let flag = "bogus".to_string();
+    fn test_resolve_variant_uses_default_when_nothing_matches() {
         assert_eq!(
-            resolve_variant(Some(&flag), "Platform Engineer", "senior-devops"),
-            "senior-platform-engineer"
+            resolve_builtin(None, "Accountant", "senior-devops"),
+            "senior-devops"
         );
     }
 
     #[test]
-    fn test_resolve_variant_uses_default_when_nothing_matches() {
-        assert_eq!(
-            resolve_variant(None, "Accountant", "senior-devops"),
-            "senior-devops"
-        );
+    fn test_resolve_variant_rejects_an_uncatalogued_default() {
+        let err = resolve_variant(
+            &VariantCatalogue::builtin(),
+            None,
+            None,
+            "Accountant",
+            "data",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("Default variant 'data'"), "got: {err}");
     }
 
     #[test]
@@ -557,10 +889,10 @@ mod tests {
     fn test_cfg() -> BuildConfig {
         BuildConfig {
             prefix: "TestCV".to_string(),
-            builder: "just".to_string(),
-            recipe: "build".to_string(),
+            builder: Builder::from_config("just", None, None, None).unwrap(),
             max_pages: 2,
-            page_count_probe: "tectonic=tectonic --print".to_string(),
+            cover_letter_max_pages: 1,
+            compaction: None,
         }
     }
 
@@ -581,6 +913,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compile_cover_letter_builds_its_own_driver_with_its_own_page_limit() {
+        let td = TempDir::new().unwrap();
+        let dir = td.path().to_str().unwrap();
+        fs::write(td.path().join("TestCV-cover-senior-sre.tex"), "x").unwrap();
+
+        let runner = crate::command_runner::testing::FakeRunner::with_stdout(
+            "Output written on TestCV-cover-senior-sre.xdv (1 page, 40000 bytes).\n",
+        );
+        assert!(compile_cover_letter(&runner, dir, "senior-sre", &test_cfg()).is_ok());
+        assert_eq!(
+            runner.calls.borrow()[0],
+            "just tectonic=tectonic --print build cover-senior-sre"
+        );
+
+        // Two pages meet the CV contract but not the cover letter's.
+        let runner = crate::command_runner::testing::FakeRunner::with_stdout(
+            "Output written on TestCV-cover-senior-sre.xdv (2 pages, 80000 bytes).\n",
+        );
+        assert!(compile_cover_letter(&runner, dir, "senior-sre", &test_cfg()).is_err());
+        // No cover-letter driver for this variant.
+        assert!(compile_cover_letter(&runner, dir, "senior-devops", &test_cfg()).is_err());
+    }
+
     #[test]
     fn test_compile_cv_missing_driver_errors() {
         let td = TempDir::new().unwrap();
@@ -606,8 +962,23 @@ mod tests {
     fn test_compile_cv_builder_failure_errors() {
         let td = TempDir::new().unwrap();
         fs::write(td.path().join("TestCV-x.tex"), "x").unwrap();
-        let runner = crate::command_runner::testing::FakeRunner::failing();
-        assert!(compile_cv(&runner, td.path().to_str().unwrap(), "x", &test_cfg()).is_err());
+        let runner = crate::command_runner::testing::FakeRunner {
+            stderr: "! LaTeX Error: File `moderncv.cls' not found.\nl.1 \\documentclass\n"
+                .to_string(),
+            ..crate::command_runner::testing::FakeRunner::failing()
+        };
+
+        let err = compile_cv(&runner, td.path().to_str().unwrap(), "x", &test_cfg())
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            err.starts_with("Error building CV with: just build x"),
+            "got: {err}"
+        );
+        assert!(err.contains("missing package moderncv.cls"), "got: {err}");
+        assert!(err.contains("The working directory is kept"), "got: {err}");
+        assert!(td.path().join("TestCV-x-transcript.log").is_file());
     }
 
     // Page-count contract regression net (RCA fix-3-page-cv-overflow items 1-5).
@@ -722,6 +1093,120 @@ mod tests {
         );
     }
 
+    /// The produced PDF is authoritative: a 3-page PDF breaks the contract
+    /// even when the transcript claims 2 pages.
+    #[test]
+    fn test_compile_cv_counts_the_pages_of_the_pdf() {
+        let td = sre_build_dir();
+        crate::pdf_pages::write_test_pdf(&td.path().join("TestCV-senior-sre.pdf"), 3);
+        let runner = crate::command_runner::testing::FakeRunner::with_stdout(
+            "Output written on TestCV-senior-sre.xdv (2 pages, 150000 bytes).\n",
+        );
+
+        let err = compile_cv(
+            &runner,
+            td.path().to_str().unwrap(),
+            "senior-sre",
+            &test_cfg(),
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("CV is 3 pages"), "got: {err}");
+    }
+
+    /// A builder that prints no TeX transcript (latexmk, typst, ...) passes
+    /// on the page count of its PDF alone.
+    #[test]
+    fn test_compile_cv_without_transcript_uses_the_pdf() {
+        let td = sre_build_dir();
+        crate::pdf_pages::write_test_pdf(&td.path().join("TestCV-senior-sre.pdf"), 2);
+        let runner = crate::command_runner::testing::FakeRunner::ok();
+        let cfg = BuildConfig {
+            builder: Builder::Latexmk,
+            ..test_cfg()
+        };
+
+        compile_cv(&runner, td.path().to_str().unwrap(), "senior-sre", &cfg).unwrap();
+    }
+
+    /// A builder whose CV only fits on two pages from `\compactlevel=2` on:
+    /// it writes a 3-page PDF below that level.
+    struct CompactingRunner;
+
+    impl CommandRunner for CompactingRunner {
+        fn status(&self, _: &str, _: &[&str], _: Option<&str>) -> std::io::Result<bool> {
+            Ok(true)
+        }
+        fn output(&self, _: &str, _: &[&str]) -> std::io::Result<(bool, String)> {
+            Ok((true, String::new()))
+        }
+        fn spawn(&self, _: &str, _: &[&str]) -> std::io::Result<()> {
+            Ok(())
+        }
+        fn run_capturing(
+            &self,
+            _: &str,
+            _: &[&str],
+            cwd: Option<&str>,
+        ) -> std::io::Result<CommandOutcome> {
+            let dir = Path::new(cwd.unwrap());
+            let macros = fs::read_to_string(dir.join(crate::application_tex::APPLICATION_TEX))
+                .unwrap_or_default();
+            let pages = if macros.contains(r"\renewcommand{\compactlevel}{2}") {
+                2
+            } else {
+                3
+            };
+            crate::pdf_pages::write_test_pdf_with_lines(
+                &dir.join("TestCV-senior-sre.pdf"),
+                pages,
+                5,
+            );
+            Ok(CommandOutcome {
+                success: true,
+                stdout: String::new(),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_compile_cv_overflow_reports_the_lines_on_the_last_page() {
+        let td = sre_build_dir();
+
+        let err = compile_cv(
+            &CompactingRunner,
+            td.path().to_str().unwrap(),
+            "senior-sre",
+            &test_cfg(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "CV is 3 pages; the contract allows at most 2: 1 page over, about 5 lines on page 3"
+        );
+    }
+
+    #[test]
+    fn test_compile_cv_retries_compaction_levels_until_it_fits() {
+        let td = sre_build_dir();
+        let cfg = BuildConfig {
+            compaction: Compaction::from_config(None, Some("1,2,3".to_string())).unwrap(),
+            ..test_cfg()
+        };
+
+        let level = compile_cv(
+            &CompactingRunner,
+            td.path().to_str().unwrap(),
+            "senior-sre",
+            &cfg,
+        )
+        .unwrap();
+
+        assert_eq!(level.as_deref(), Some("2"));
+    }
+
     #[test]
     fn test_remove_cv_dir_removes_directory() {
         let td = TempDir::new().unwrap();
@@ -849,10 +1334,9 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_create_directory_and_remove_flow() {
-        let td = TempDir::new().unwrap();
-        let base = td.path();
+    /// A template with one driver and a config publishing under `dest` and
+    /// `out` in `base`, with the extra `[destination]` lines `layout`.
+    fn publishing_context(base: &Path, layout: &str) -> AppContext {
         let template = base.join("template");
         fs::create_dir_all(&template).unwrap();
         fs::write(template.join("TestCV-senior-devops.tex"), "x").unwrap();
@@ -862,7 +1346,7 @@ mod tests {
 
         let ini = format!(
             "[cv]\ncv_template_path = \"{tpl}\"\ncv_file_prefix = \"TestCV\"\n\
-             [destination]\ncv_path = \"{dest}\"\noutput_pdf = \"{out}\"\n\
+             [destination]\ncv_path = \"{dest}\"\noutput_pdf = \"{out}\"\n{layout}\
              [db]\nengine = \"sqlite\"\ndb_file = \"x.db\"\n",
             tpl = template.display(),
             dest = dest.display(),
@@ -873,7 +1357,7 @@ mod tests {
 
         let ui = crate::cli_structure::UserInput {
             action: crate::cli_structure::UserAction::List(
-                crate::cli_structure::FilterArgs::default(),
+                crate::cli_structure::ListArgs::default(),
             ),
             save_to_database: false,
             view_generated_cv: false,
@@ -883,7 +1367,13 @@ mod tests {
             repo: None,
             branch: None,
         };
-        let ctx = crate::config_parse::build_context(&ui);
+        crate::config_parse::build_context(&ui)
+    }
+
+    #[test]
+    fn test_create_directory_and_remove_flow() {
+        let td = TempDir::new().unwrap();
+        let ctx = publishing_context(td.path(), "");
 
         // create_directory copies the template into a dated dir under dest.
         let runner = crate::command_runner::testing::FakeRunner::ok();
@@ -896,18 +1386,164 @@ mod tests {
 
         // Place a "built" PDF, then run the copy-out + cleanup.
         fs::write(format!("{created}/TestCV-senior-devops.pdf"), b"%PDF").unwrap();
-        let output_pdf = remove_created_dir_from_pro(
+        fs::write(format!("{created}/TestCV-cover-senior-devops.pdf"), b"%PDF").unwrap();
+        let published = remove_created_dir_from_pro(
+            &ctx,
+            "Senior DevOps",
+            "ACME",
+            "senior-devops",
+            &created,
+            "TestCV-senior-devops.pdf",
+            Some("TestCV-cover-senior-devops.pdf"),
+        )
+        .unwrap();
+
+        assert!(Path::new(&published.cv).is_file());
+        let cover_letter = published.cover_letter.unwrap();
+        assert!(cover_letter.ends_with("-Senior-DevOps-ACME-cover-letter.pdf"));
+        assert!(Path::new(&cover_letter).is_file());
+        assert_eq!(
+            Path::new(&cover_letter).parent(),
+            Path::new(&published.cv).parent()
+        );
+        assert!(!Path::new(&created).exists());
+    }
+
+    #[test]
+    fn test_destination_patterns_place_the_working_dir_and_the_pdf() {
+        let td = TempDir::new().unwrap();
+        let ctx = publishing_context(
+            td.path(),
+            "working_dir_pattern = \"{company}/{job}\"\n\
+             output_dir_pattern = \"{company}\"\n\
+             filename_pattern = \"{prefix}-{variant}-{job}\"\n\
+             sibling_copy = false\n",
+        );
+        let runner = crate::command_runner::testing::FakeRunner::ok();
+
+        let created = create_directory(&ctx, &runner, "Senior DevOps", "ACME").unwrap();
+        assert_eq!(
+            Path::new(&created),
+            td.path().join("dest/ACME/Senior-DevOps")
+        );
+        fs::write(format!("{created}/TestCV-senior-devops.pdf"), b"%PDF").unwrap();
+        let published = remove_created_dir_from_pro(
+            &ctx,
+            "Senior DevOps",
+            "ACME",
+            "senior-devops",
+            &created,
+            "TestCV-senior-devops.pdf",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Path::new(&published.cv),
+            td.path()
+                .join("out/ACME/TestCV-senior-devops-Senior-DevOps.pdf")
+        );
+        assert!(Path::new(&published.cv).is_file());
+        // No sibling copy: the working dir was the only entry of its parent.
+        assert_eq!(
+            fs::read_dir(td.path().join("dest/ACME")).unwrap().count(),
+            0
+        );
+
+        // The same application again is published next to it, not over it.
+        let created = create_directory(&ctx, &runner, "Senior DevOps", "ACME").unwrap();
+        fs::write(format!("{created}/TestCV-senior-devops.pdf"), b"%PDF").unwrap();
+        let again = remove_created_dir_from_pro(
             &ctx,
             "Senior DevOps",
             "ACME",
+            "senior-devops",
             &created,
             "TestCV-senior-devops.pdf",
+            None,
+        )
+        .unwrap();
+        assert!(
+            again
+                .cv
+                .ends_with("/TestCV-senior-devops-Senior-DevOps-2.pdf")
+        );
+    }
+
+    #[test]
+    fn test_publishing_archives_the_artifacts_next_to_the_cv() {
+        let td = TempDir::new().unwrap();
+        let ctx = publishing_context(td.path(), "[build]\nkeep_artifacts = \"log-only\"\n");
+        let runner = crate::command_runner::testing::FakeRunner::ok();
+        let created = create_directory(&ctx, &runner, "SRE", "ACME").unwrap();
+        fs::write(format!("{created}/TestCV-senior-devops.pdf"), b"%PDF").unwrap();
+        fs::write(format!("{created}/TestCV-senior-devops.log"), b"log").unwrap();
+
+        let published = remove_created_dir_from_pro(
+            &ctx,
+            "SRE",
+            "ACME",
+            "senior-devops",
+            &created,
+            "TestCV-senior-devops.pdf",
+            None,
         )
         .unwrap();
 
-        assert!(Path::new(&output_pdf).is_file());
+        let archive = published.artifacts.unwrap();
+        assert_eq!(archive, artifacts_archive_path(&published.cv));
+        assert!(Path::new(&archive).is_file());
         assert!(!Path::new(&created).exists());
     }
+
+    fn context_with_branch(branch: Option<&str>) -> AppContext {
+        let ui = crate::cli_structure::UserInput {
+            action: crate::cli_structure::UserAction::List(
+                crate::cli_structure::ListArgs::default(),
+            ),
+            save_to_database: false,
+            view_generated_cv: false,
+            dry_run: false,
+            config_ini: String::new(),
+            engine: "sqlite".to_string(),
+            repo: None,
+            branch: branch.map(str::to_string),
+        };
+        AppContext::new(configparser::ini::Ini::new(), chrono::Local::now(), ui)
+    }
+
+    #[test]
+    fn test_template_revision_reads_the_commit_of_a_git_checkout() {
+        let td = TempDir::new().unwrap();
+        fs::create_dir(td.path().join(".git")).unwrap();
+        let runner = crate::command_runner::testing::FakeRunner::with_stdout("0123abc\n");
+
+        let revision = template_revision(
+            &context_with_branch(Some("main")),
+            &runner,
+            td.path().to_str().unwrap(),
+        );
+
+        assert_eq!(revision.as_deref(), Some("0123abc"));
+        assert!(runner.calls.borrow()[0].starts_with("git -C"));
+    }
+
+    #[test]
+    fn test_template_revision_falls_back_to_the_configured_ref() {
+        let td = TempDir::new().unwrap();
+        let runner = crate::command_runner::testing::FakeRunner::ok();
+        let dir = td.path().to_str().unwrap();
+
+        assert_eq!(
+            template_revision(&context_with_branch(Some("v2")), &runner, dir).as_deref(),
+            Some("v2")
+        );
+        assert_eq!(
+            template_revision(&context_with_branch(None), &runner, dir),
+            None
+        );
+        assert!(runner.calls.borrow().is_empty());
+    }
 }
 
 #[cfg(test)]
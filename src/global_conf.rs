@@ -1,22 +1,26 @@
 use crate::{
     UserInput,
+    all_variants::AllVariantsRequest,
     cli_structure::{FilterArgs, UserAction},
     helpers::clean_string_from_quotes,
 };
 use chrono::{DateTime, Local};
 use configparser::ini::Ini;
+use std::path::PathBuf;
+use uuid::Uuid;
 
 /// Immutable, dependency-injected configuration value (ADR-0006).
 ///
 /// Replaces the former process-global config cell (`OnceCell`): the parsed INI
-/// config, the run timestamp (`today`) and the parsed `UserInput` are captured
-/// once in `main` and threaded by shared borrow (`&AppContext`). It exposes
+/// config, the run timestamp (`today`), the id of the application this run
+/// creates and the parsed `UserInput` are captured once in `main` and threaded by shared borrow (`&AppContext`). It exposes
 /// **read accessors only** — no setters, no interior mutability — so "a `&self`
 /// method silently mutates shared config" is non-representable.
 #[derive(Debug, Clone)]
 pub struct AppContext {
     config: Ini,
     today: DateTime<Local>,
+    application_id: Uuid,
     user_input: UserInput,
 }
 
@@ -25,13 +29,13 @@ impl AppContext {
         AppContext {
             config,
             today,
+            application_id: Uuid::new_v4(),
             user_input,
         }
     }
 
     // Part of the read-only accessor surface preserved from `GlobalVars`
-    // (ADR-0006). Currently exercised by tests / kept for parity.
-    #[allow(dead_code)]
+    // (ADR-0006).
     pub fn config(&self) -> &Ini {
         &self.config
     }
@@ -50,6 +54,12 @@ impl AppContext {
         &self.today
     }
 
+    /// A random id drawn once per run, naming the application an `insert`
+    /// creates (the `{id}` of the `[destination]` patterns).
+    pub fn get_application_id(&self) -> &Uuid {
+        &self.application_id
+    }
+
     pub fn get_today_str(&self) -> String {
         self.get_today().format("%e-%b-%Y").to_string()
     }
@@ -73,9 +83,12 @@ impl AppContext {
     pub fn get_user_input_action_filter_args(&self) -> FilterArgs {
         match self.get_user_input_action() {
             UserAction::Insert(insert_args) => insert_args.into(),
-            UserAction::Remove(filter_args)
-            | UserAction::List(filter_args)
-            | UserAction::Update(filter_args) => filter_args,
+            UserAction::Update(update_args) => update_args.filters,
+            UserAction::Status(status_args) => status_args.filters,
+            UserAction::Remove(remove_args) => remove_args.filters,
+            UserAction::List(list_args) => list_args.filters,
+            UserAction::Preview(preview_args) => preview_args.into(),
+            UserAction::Db(_) => FilterArgs::default(),
         }
     }
 
@@ -135,6 +148,41 @@ impl AppContext {
         self.get_user_input().save_to_database
     }
 
+    /// The `insert --job-description` file, if any.
+    pub fn get_job_description(&self) -> Option<PathBuf> {
+        match &self.user_input.action {
+            UserAction::Insert(insert_args) => insert_args.job_description.clone(),
+            _ => None,
+        }
+    }
+
+    /// `insert --cover-letter`: build the cover letter too.
+    pub fn wants_cover_letter(&self) -> bool {
+        matches!(&self.user_input.action, UserAction::Insert(insert_args) if insert_args.cover_letter)
+    }
+
+    /// Whether `insert --keep-workdir` asked to keep the working directory.
+    pub fn keeps_workdir(&self) -> bool {
+        matches!(&self.user_input.action, UserAction::Insert(insert_args) if insert_args.keep_workdir)
+    }
+
+    /// `insert --all-variants` and its options, when requested.
+    pub fn get_all_variants_request(&self) -> Option<AllVariantsRequest> {
+        match &self.user_input.action {
+            UserAction::Insert(insert_args) if insert_args.all_variants => {
+                Some(AllVariantsRequest {
+                    parallel: insert_args.parallel,
+                    keep: insert_args.keep.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.user_input.dry_run
+    }
+
     /// The `[db] engine` value, stripped of the surrounding quotes the
     /// documented config format uses (`engine = "postgres"`). Without the
     /// stripping the engine name never matches `postgres`/`sqlite` and the
@@ -158,6 +206,7 @@ impl AppContext {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli_structure::ListArgs;
     use chrono::{Local, TimeZone};
     use configparser::ini::Ini;
 
@@ -171,12 +220,15 @@ mod tests {
 
     fn dummy_user_input() -> UserInput {
         UserInput {
-            action: UserAction::List(FilterArgs {
-                job_title: Some("Dev".to_string()),
-                company_name: Some("Company".to_string()),
-                quote: Some("Quote".to_string()),
-                date: Some("2024-01-01".to_string()),
-                variant: Some("senior-devops".to_string()),
+            action: UserAction::List(ListArgs {
+                filters: FilterArgs {
+                    job_title: Some("Dev".to_string()),
+                    company_name: Some("Company".to_string()),
+                    quote: Some("Quote".to_string()),
+                    date: Some("2024-01-01".to_string()),
+                    variant: Some("senior-devops".to_string()),
+                },
+                format: None,
             }),
             save_to_database: true,
             view_generated_cv: false,
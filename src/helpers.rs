@@ -59,12 +59,6 @@ pub fn fix_home_directory_path(file_path: &str) -> String {
 pub fn check_config_file_exists(file_path: &str) -> Result<String, &str> {
     let fixed_file_path = fix_home_directory_path(file_path);
 
-    // TODO: MINOR
-    // if db file does not exist, create it
-    // if fs::metadata(file_path).is_err() {
-    //     panic!("File {} does not exist", file_path)
-    // };
-
     if fs::metadata(fixed_file_path.clone()).is_ok() {
         Ok(fixed_file_path)
     } else {
@@ -106,22 +100,12 @@ pub fn check_if_db_env_is_set_or_set_from_config(
             }
         }
     } else {
-        //TODO: fix unwrap
-        let db_path = match get_db_configurations(ctx) {
-            Ok(db) => db,
-            Err(e) => {
-                warn!("Could not get the db configuration: {e:}");
-                String::new()
-            }
-        };
-
-        if let Ok(val) = std::env::var("DATABASE_URL") {
-            drop(val);
-            Ok("If Let OKAY: Set the DATABASE_URL env variable".to_string())
-        } else {
-            std::env::set_var("DATABASE_URL", format!("sqlite://{db_path}"));
-            Ok("If Let NOT OKAY: Set the DATABASE_URL env variable".to_string())
+        if std::env::var("DATABASE_URL").is_ok() {
+            return Ok("DATABASE_URL is already set".to_string());
         }
+        let db_path = get_db_configurations(ctx)?;
+        std::env::set_var("DATABASE_URL", format!("sqlite://{db_path}"));
+        Ok(format!("Set DATABASE_URL to the SQLite database {db_path}"))
     }
 }
 
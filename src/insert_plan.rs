@@ -0,0 +1,657 @@
+//! `insert --dry-run`: work out everything an insert would do — config,
+//! variant, template source, working directory, PDF name, database row — and
+//! describe it step by step, without creating, running or writing anything.
+
+use crate::all_variants::WORKER_PREFIX;
+use crate::application_tex::APPLICATION_TEX;
+use crate::artifacts::{KeepArtifacts, artifacts_archive_path};
+use crate::command_runner::PlannedCommand;
+use crate::config_parse::resolve_db_target;
+use crate::file_handlers::{
+    BuildConfig, cover_letter_document, publish_targets, published_paths, select_variant,
+    template_settings, working_dir_path,
+};
+use crate::global_conf::AppContext;
+use crate::helpers::tool_on_path;
+use crate::job_description::JobMatchReport;
+use crate::pdf_metadata::PdfMetadata;
+use crate::template_source::{TemplatePlan, plan_template_for_config};
+use crate::variant_catalogue::VariantCatalogue;
+use std::fmt;
+use std::path::Path;
+
+/// Stands for the variant of an `--all-variants` run picked after the builds.
+const KEPT_VARIANT: &str = "<kept variant>";
+
+/// One side effect the real insert would perform, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanStep {
+    CreateDir(String),
+    Run(PlannedCommand),
+    CopyDir { from: String, to: String },
+    CopyFile { from: String, to: String },
+    WriteFile(String),
+    StampMetadata { pdf: String, metadata: PdfMetadata },
+    RemoveDir(String),
+    SaveRow { database: String, row: PlannedRow },
+}
+
+/// The `cv` row the insert would write. The template revision is only known
+/// once the template is resolved, so it is left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedRow {
+    pub application_date: String,
+    pub job_title: String,
+    pub company: String,
+    pub quote: String,
+    pub pdf_cv_path: String,
+    pub variant: String,
+    pub cover_letter_path: Option<String>,
+}
+
+/// Everything `insert` resolved for this run, and the steps it would take.
+#[derive(Debug, Clone)]
+pub struct InsertPlan {
+    pub job_title: String,
+    pub company: String,
+    pub variant: String,
+    /// How the `--job-description` posting scored, when one was given.
+    pub job_match: Option<JobMatchReport>,
+    pub template: TemplatePlan,
+    pub working_dir: String,
+    pub pdf_path: String,
+    pub cover_letter_path: Option<String>,
+    pub steps: Vec<PlanStep>,
+    /// Problems the real run would hit (missing tools).
+    pub warnings: Vec<String>,
+}
+
+/// Resolve the insert described by `ctx` into an [`InsertPlan`].
+///
+/// Mirrors `prepare_cv` and `insert_cv` step for step, but only reads: the
+/// config, the filesystem (does the template / cache entry exist?) and PATH.
+/// Configuration errors surface exactly as the real run would report them.
+pub fn plan_insert(ctx: &AppContext) -> Result<InsertPlan, Box<dyn std::error::Error>> {
+    let job_title = ctx.get_job_title()?;
+    let company = ctx.get_company_name()?;
+    let quote = ctx.get_quote().unwrap_or_default();
+    let cfg = BuildConfig::from_context(ctx)?;
+
+    let settings = template_settings(ctx)?;
+    let template = plan_template_for_config(
+        &settings.path,
+        &settings.cache_dir,
+        settings.git_ref.as_deref(),
+        settings.auth,
+    )?;
+    // A git template not cloned yet cannot be inspected: its manifest and driver
+    // files are only checked by the real run.
+    let inspectable = !matches!(template, TemplatePlan::Git { cached: false, .. });
+    let template_dir = inspectable.then(|| Path::new(template.template_dir()));
+    let all_variants = ctx.get_all_variants_request();
+    let (variant, job_match, builds) = match &all_variants {
+        // Which variant is kept is only known after the builds, unless --keep.
+        Some(request) => (
+            request
+                .keep
+                .clone()
+                .unwrap_or_else(|| KEPT_VARIANT.to_string()),
+            None,
+            VariantCatalogue::load(ctx, template_dir, &cfg.prefix)?
+                .names()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        ),
+        None => {
+            let choice = select_variant(
+                ctx,
+                template_dir,
+                &cfg.prefix,
+                ctx.get_variant().as_ref(),
+                &job_title,
+            )?;
+            let builds = vec![choice.variant.clone()];
+            (choice.variant, choice.job_match, builds)
+        }
+    };
+    let parallel = all_variants.is_some_and(|request| request.parallel);
+
+    let working_dir = working_dir_path(ctx, &job_title, &company)?;
+    let targets = publish_targets(ctx, &job_title, &company, &variant)?;
+    let output_dir = targets.output_dir.clone();
+    let with_cover_letter = ctx.wants_cover_letter();
+    let (pdf_path, output_cover_letter) =
+        published_paths(&output_dir, &targets.pdf_name, with_cover_letter);
+    let built_pdf = format!("{working_dir}/{}-{variant}.pdf", cfg.prefix);
+    let parent_dir = Path::new(&working_dir)
+        .parent()
+        .map(|parent| parent.display().to_string())
+        .ok_or("Could not determine parent directory of the CV working dir")?;
+    let (sibling_pdf, sibling_cover_letter) = if targets.sibling_copy {
+        let (pdf, cover_letter) =
+            published_paths(&parent_dir, &targets.pdf_name, with_cover_letter);
+        (Some(pdf), cover_letter)
+    } else {
+        (None, None)
+    };
+    let cover_letter = output_cover_letter.map(|output| {
+        (
+            cover_letter_document(&variant),
+            output,
+            sibling_cover_letter,
+        )
+    });
+
+    let mut warnings = Vec::new();
+    let mut tools = cfg.builder.required_tools();
+    if matches!(template, TemplatePlan::Git { .. }) {
+        tools.push("git");
+    }
+    for tool in tools.into_iter().filter(|tool| !tool_on_path(tool)) {
+        warnings.push(format!(
+            "`{tool}` is not on PATH; the build would stop here"
+        ));
+    }
+
+    let mut steps = vec![PlanStep::CreateDir(parent_dir.clone())];
+    if let TemplatePlan::Git {
+        entry, commands, ..
+    } = &template
+    {
+        if let Some(parent) = Path::new(entry).parent() {
+            steps.push(PlanStep::CreateDir(parent.display().to_string()));
+        }
+        steps.extend(commands.iter().cloned().map(PlanStep::Run));
+    }
+    steps.push(PlanStep::CopyDir {
+        from: template.template_dir().to_string(),
+        to: working_dir.clone(),
+    });
+    steps.push(PlanStep::WriteFile(format!(
+        "{working_dir}/{APPLICATION_TEX}"
+    )));
+    for build in &builds {
+        if parallel {
+            let scratch = std::env::temp_dir()
+                .join(format!("{WORKER_PREFIX}{build}-<random>"))
+                .display()
+                .to_string();
+            let worker = format!("{scratch}/{build}");
+            let pdf = format!("{}-{build}.pdf", cfg.prefix);
+            steps.push(PlanStep::CreateDir(scratch.clone()));
+            steps.push(PlanStep::CopyDir {
+                from: working_dir.clone(),
+                to: worker.clone(),
+            });
+            steps.push(PlanStep::Run(cfg.build_command(&worker, build)));
+            steps.push(PlanStep::CopyFile {
+                from: format!("{worker}/{pdf}"),
+                to: format!("{working_dir}/{pdf}"),
+            });
+            steps.push(PlanStep::RemoveDir(scratch));
+        } else {
+            steps.push(PlanStep::Run(cfg.build_command(&working_dir, build)));
+        }
+    }
+    if let Some((document, _, _)) = &cover_letter {
+        steps.push(PlanStep::Run(cfg.build_command(&working_dir, document)));
+    }
+    let metadata = PdfMetadata::from_context(ctx, &cfg.prefix, &job_title, &company, &variant);
+    let cover_letter_pdf = cover_letter
+        .as_ref()
+        .map(|(document, _, _)| format!("{working_dir}/{}-{document}.pdf", cfg.prefix));
+    for pdf in std::iter::once(built_pdf.clone()).chain(cover_letter_pdf) {
+        steps.push(PlanStep::StampMetadata {
+            pdf,
+            metadata: metadata.clone(),
+        });
+    }
+    if matches!(template, TemplatePlan::Git { .. })
+        || Path::new(template.template_dir()).join(".git").exists()
+    {
+        steps.push(PlanStep::Run(PlannedCommand::new(
+            "git",
+            &["-C", &working_dir, "rev-parse", "HEAD"],
+            None,
+        )));
+    }
+    steps.push(PlanStep::CreateDir(output_dir));
+    steps.push(PlanStep::CopyFile {
+        from: built_pdf.clone(),
+        to: pdf_path.clone(),
+    });
+    if let Some(sibling_pdf) = sibling_pdf {
+        steps.push(PlanStep::CopyFile {
+            from: built_pdf,
+            to: sibling_pdf,
+        });
+    }
+    if let Some((document, output, sibling)) = &cover_letter {
+        let built = format!("{working_dir}/{}-{document}.pdf", cfg.prefix);
+        steps.push(PlanStep::CopyFile {
+            from: built.clone(),
+            to: output.clone(),
+        });
+        if let Some(sibling) = sibling {
+            steps.push(PlanStep::CopyFile {
+                from: built,
+                to: sibling.clone(),
+            });
+        }
+    }
+    let cover_letter_path = cover_letter.map(|(_, output, _)| output);
+    if KeepArtifacts::from_context(ctx)? != KeepArtifacts::None {
+        steps.push(PlanStep::WriteFile(artifacts_archive_path(&pdf_path)));
+    }
+    if !ctx.keeps_workdir() {
+        steps.push(PlanStep::RemoveDir(working_dir.clone()));
+    }
+    if ctx.get_user_input_save_to_db() {
+        steps.push(PlanStep::SaveRow {
+            database: database_description(ctx),
+            row: PlannedRow {
+                application_date: ctx.get_today_str(),
+                job_title: job_title.clone(),
+                company: company.clone(),
+                quote,
+                pdf_cv_path: pdf_path.clone(),
+                variant: variant.clone(),
+                cover_letter_path: cover_letter_path.clone(),
+            },
+        });
+    }
+
+    Ok(InsertPlan {
+        job_title,
+        company,
+        variant,
+        job_match,
+        template,
+        working_dir,
+        pdf_path,
+        cover_letter_path,
+        steps,
+        warnings,
+    })
+}
+
+/// Where the row would go, without reading the `PostgreSQL` password: only the
+/// `SQLite` target is spelled out.
+fn database_description(ctx: &AppContext) -> String {
+    match ctx.get_user_input_db_engine() {
+        Ok(engine) if engine.trim() == "sqlite" => match resolve_db_target(ctx) {
+            Ok((_, url)) => url,
+            Err(e) => format!("sqlite (unresolved: {e})"),
+        },
+        Ok(engine) => engine,
+        Err(e) => format!("unknown engine ({e})"),
+    }
+}
+
+impl fmt::Display for PlanStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanStep::CreateDir(dir) => write!(f, "create directory {dir}"),
+            PlanStep::Run(command) => write!(f, "run {command}"),
+            PlanStep::CopyDir { from, to } => write!(f, "copy directory {from} -> {to}"),
+            PlanStep::CopyFile { from, to } => write!(f, "copy {from} -> {to}"),
+            PlanStep::WriteFile(path) => write!(f, "write {path}"),
+            PlanStep::StampMetadata { pdf, metadata } => write!(
+                f,
+                "stamp {pdf}: title '{}', author '{}', keywords '{}'",
+                metadata.title, metadata.author, metadata.keywords
+            ),
+            PlanStep::RemoveDir(dir) => write!(f, "remove directory {dir}"),
+            PlanStep::SaveRow { database, row } => {
+                write!(
+                    f,
+                    "save application to {database}: date '{}', job '{}', company '{}', \
+                     quote '{}', pdf '{}', variant '{}'",
+                    row.application_date.trim(),
+                    row.job_title,
+                    row.company,
+                    row.quote,
+                    row.pdf_cv_path,
+                    row.variant
+                )?;
+                match &row.cover_letter_path {
+                    Some(path) => write!(f, ", cover letter '{path}'"),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for InsertPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Dry run: insert '{}' at '{}' (nothing is created, run or written)",
+            self.job_title, self.company
+        )?;
+        writeln!(f, "  variant:     {}", self.variant)?;
+        match &self.template {
+            TemplatePlan::Local { path } => writeln!(f, "  template:    {path} (local directory)")?,
+            TemplatePlan::Git {
+                url,
+                git_ref,
+                entry,
+                cached,
+                ..
+            } => writeln!(
+                f,
+                "  template:    {url} @ {} ({} cache entry {entry})",
+                git_ref.as_deref().unwrap_or("default branch"),
+                if *cached { "refresh" } else { "new" }
+            )?,
+        }
+        writeln!(f, "  working dir: {}", self.working_dir)?;
+        writeln!(f, "  pdf:         {}", self.pdf_path)?;
+        if let Some(cover_letter) = &self.cover_letter_path {
+            writeln!(f, "  cover:       {cover_letter}")?;
+        }
+        writeln!(f, "Steps:")?;
+        for (number, step) in self.steps.iter().enumerate() {
+            writeln!(f, "  {:>2}. {step}", number + 1)?;
+        }
+        if !self
+            .steps
+            .iter()
+            .any(|s| matches!(s, PlanStep::SaveRow { .. }))
+        {
+            writeln!(
+                f,
+                "  (not saved to the database: --save-to-database is off)"
+            )?;
+        }
+        if let Some(job_match) = &self.job_match {
+            write!(f, "{job_match}")?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "warning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_structure::{InsertArgs, UserAction, UserInput};
+    use crate::config_parse::build_context;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn context(base: &Path, template: &str, save_to_database: bool) -> AppContext {
+        context_with_posting(base, template, save_to_database, None)
+    }
+
+    fn context_with_posting(
+        base: &Path,
+        template: &str,
+        save_to_database: bool,
+        job_description: Option<PathBuf>,
+    ) -> AppContext {
+        context_for(base, template, save_to_database, job_description, false)
+    }
+
+    fn context_for(
+        base: &Path,
+        template: &str,
+        save_to_database: bool,
+        job_description: Option<PathBuf>,
+        cover_letter: bool,
+    ) -> AppContext {
+        context_with_args(
+            base,
+            template,
+            save_to_database,
+            InsertArgs {
+                job_title: "Senior SRE".to_string(),
+                company_name: "ACME".to_string(),
+                quote: Some("Ship it".to_string()),
+                job_description,
+                cover_letter,
+                ..InsertArgs::default()
+            },
+        )
+    }
+
+    fn context_with_args(
+        base: &Path,
+        template: &str,
+        save_to_database: bool,
+        insert_args: InsertArgs,
+    ) -> AppContext {
+        let ini = format!(
+            "[cv]\ncv_template_path = \"{template}\"\ncv_file_prefix = \"TestCV\"\n\
+             cv_template_cache = \"{cache}\"\n\
+             [destination]\ncv_path = \"{dest}\"\noutput_pdf = \"{out}\"\n\
+             [db]\nengine = \"sqlite\"\ndb_path = \"{base}\"\ndb_file = \"x.db\"\n",
+            cache = base.join("cache").display(),
+            dest = base.join("dest").display(),
+            out = base.join("out").display(),
+            base = base.display(),
+        );
+        let ini_path = base.join("conf.ini");
+        fs::write(&ini_path, ini).unwrap();
+
+        build_context(&UserInput {
+            action: UserAction::Insert(insert_args),
+            save_to_database,
+            view_generated_cv: false,
+            dry_run: true,
+            config_ini: ini_path.to_str().unwrap().to_string(),
+            engine: "sqlite".to_string(),
+            repo: None,
+            branch: None,
+        })
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_plan_insert_local_template_has_no_side_effects() {
+        let td = TempDir::new().unwrap();
+        let template = td.path().join("template");
+        fs::create_dir(&template).unwrap();
+        fs::write(template.join("TestCV-senior-sre.tex"), "x").unwrap();
+        let ctx = context(td.path(), template.to_str().unwrap(), true);
+        let before = entries(td.path());
+
+        let plan = plan_insert(&ctx).unwrap();
+
+        assert_eq!(entries(td.path()), before);
+        assert_eq!(plan.variant, "senior-sre");
+        assert!(plan.pdf_path.ends_with("-Senior-SRE-ACME.pdf"));
+        assert!(plan.steps.contains(&PlanStep::CopyDir {
+            from: template.to_str().unwrap().to_string(),
+            to: plan.working_dir.clone(),
+        }));
+        let build = PlanStep::Run(PlannedCommand::new(
+            "just",
+            &["tectonic=tectonic --print", "build", "senior-sre"],
+            Some(&plan.working_dir),
+        ));
+        assert!(plan.steps.contains(&build));
+        assert!(plan.steps.contains(&PlanStep::WriteFile(format!(
+            "{}/application.tex",
+            plan.working_dir
+        ))));
+        assert!(matches!(
+            plan.steps.last(),
+            Some(PlanStep::SaveRow { row, .. }) if row.variant == "senior-sre" && row.quote == "Ship it"
+        ));
+        assert!(
+            plan.to_string()
+                .contains("Dry run: insert 'Senior SRE' at 'ACME'")
+        );
+    }
+
+    #[test]
+    fn test_plan_insert_scores_the_job_description() {
+        let td = TempDir::new().unwrap();
+        let template = td.path().join("template");
+        fs::create_dir(&template).unwrap();
+        fs::write(template.join("TestCV-senior-sre.tex"), "SLOs and on-call").unwrap();
+        fs::write(template.join("TestCV-senior-devops.tex"), "CI/CD pipelines").unwrap();
+        let posting = td.path().join("posting.html");
+        fs::write(
+            &posting,
+            "<html><body><p>Build CI/CD pipelines with Terraform.</p>\
+             <p>Terraform modules, Terraform Cloud.</p></body></html>",
+        )
+        .unwrap();
+        let ctx = context_with_posting(td.path(), template.to_str().unwrap(), false, Some(posting));
+
+        let plan = plan_insert(&ctx).unwrap();
+
+        // The posting wins over the "Senior SRE" job title.
+        assert_eq!(plan.variant, "senior-devops");
+        let job_match = plan.job_match.as_ref().unwrap();
+        assert_eq!(job_match.chosen, "senior-devops");
+        assert_eq!(job_match.missing, Some(vec!["terraform".to_string()]));
+        assert!(plan.to_string().contains("does not mention: terraform"));
+    }
+
+    #[test]
+    fn test_plan_insert_cover_letter_is_built_published_and_recorded() {
+        let td = TempDir::new().unwrap();
+        let template = td.path().join("template");
+        fs::create_dir(&template).unwrap();
+        fs::write(template.join("TestCV-senior-sre.tex"), "x").unwrap();
+        let ctx = context_for(td.path(), template.to_str().unwrap(), true, None, true);
+
+        let plan = plan_insert(&ctx).unwrap();
+
+        let cover_letter = plan.cover_letter_path.clone().unwrap();
+        assert!(cover_letter.ends_with("-Senior-SRE-ACME-cover-letter.pdf"));
+        assert!(plan.steps.contains(&PlanStep::Run(PlannedCommand::new(
+            "just",
+            &["tectonic=tectonic --print", "build", "cover-senior-sre"],
+            Some(&plan.working_dir),
+        ))));
+        assert!(plan.steps.contains(&PlanStep::CopyFile {
+            from: format!("{}/TestCV-cover-senior-sre.pdf", plan.working_dir),
+            to: cover_letter.clone(),
+        }));
+        assert!(plan.steps.iter().any(|step| matches!(
+            step,
+            PlanStep::StampMetadata { pdf, metadata }
+                if *pdf == format!("{}/TestCV-cover-senior-sre.pdf", plan.working_dir)
+                    && metadata.title == "Senior SRE — ACME"
+        )));
+        assert!(matches!(
+            plan.steps.last(),
+            Some(PlanStep::SaveRow { row, .. }) if row.cover_letter_path.as_ref() == Some(&cover_letter)
+        ));
+    }
+
+    #[test]
+    fn test_plan_insert_all_variants_builds_each_in_a_worker_copy() {
+        let td = TempDir::new().unwrap();
+        let template = td.path().join("template");
+        fs::create_dir(&template).unwrap();
+        fs::write(template.join("TestCV-senior-sre.tex"), "x").unwrap();
+        fs::write(template.join("TestCV-senior-devops.tex"), "x").unwrap();
+        let ctx = context_with_args(
+            td.path(),
+            template.to_str().unwrap(),
+            false,
+            InsertArgs {
+                job_title: "Senior SRE".to_string(),
+                company_name: "ACME".to_string(),
+                all_variants: true,
+                parallel: true,
+                ..InsertArgs::default()
+            },
+        );
+
+        let plan = plan_insert(&ctx).unwrap();
+
+        assert_eq!(plan.variant, KEPT_VARIANT);
+        for variant in ["senior-sre", "senior-devops"] {
+            let scratch = std::env::temp_dir()
+                .join(format!("{WORKER_PREFIX}{variant}-<random>"))
+                .display()
+                .to_string();
+            let worker = format!("{scratch}/{variant}");
+            assert!(plan.steps.contains(&PlanStep::Run(PlannedCommand::new(
+                "just",
+                &["tectonic=tectonic --print", "build", variant],
+                Some(&worker),
+            ))));
+            assert!(plan.steps.contains(&PlanStep::RemoveDir(scratch)));
+        }
+    }
+
+    #[test]
+    fn test_plan_insert_without_save_to_database_writes_no_row() {
+        let td = TempDir::new().unwrap();
+        let template = td.path().join("template");
+        fs::create_dir(&template).unwrap();
+        fs::write(template.join("TestCV-senior-sre.tex"), "x").unwrap();
+        let ctx = context(td.path(), template.to_str().unwrap(), false);
+
+        let plan = plan_insert(&ctx).unwrap();
+
+        assert!(
+            !plan
+                .steps
+                .iter()
+                .any(|step| matches!(step, PlanStep::SaveRow { .. }))
+        );
+        assert!(plan.to_string().contains("--save-to-database is off"));
+    }
+
+    #[test]
+    fn test_plan_insert_rejects_a_template_without_driver_files() {
+        let td = TempDir::new().unwrap();
+        let template = td.path().join("template");
+        fs::create_dir(&template).unwrap();
+        let ctx = context(td.path(), template.to_str().unwrap(), false);
+
+        let err = plan_insert(&ctx).unwrap_err().to_string();
+
+        assert!(err.contains("TestCV-<variant>.tex"), "got: {err}");
+    }
+
+    #[test]
+    fn test_plan_insert_git_template_plans_the_clone_without_cloning() {
+        let td = TempDir::new().unwrap();
+        let ctx = context(td.path(), "file:///nowhere/cv-template.git", false);
+
+        let plan = plan_insert(&ctx).unwrap();
+
+        let TemplatePlan::Git { entry, cached, .. } = &plan.template else {
+            panic!("a git URL must plan a git source");
+        };
+        assert!(!cached);
+        assert!(!Path::new(entry).exists());
+        assert!(!td.path().join("cache").exists());
+        assert!(plan.steps.iter().any(|step| matches!(
+            step,
+            PlanStep::Run(command) if command.args.iter().any(|arg| arg == "clone")
+        )));
+    }
+
+    #[test]
+    fn test_plan_insert_rejects_an_unusable_template_path() {
+        let td = TempDir::new().unwrap();
+        let ctx = context(td.path(), "/definitely/not/a/template", false);
+
+        let err = plan_insert(&ctx).unwrap_err().to_string();
+
+        assert!(err.contains("/definitely/not/a/template"), "got: {err}");
+    }
+}
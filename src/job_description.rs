@@ -0,0 +1,622 @@
+//! Scoring the variant catalogue against a job posting (`insert
+//! --job-description <file>`).
+//!
+//! The posting (plain text or a saved HTML page) is reduced to lowercase words.
+//! Each variant scores the weight of every catalogue keyword found in it, times
+//! its number of occurrences (capped, so one repeated word cannot decide on its
+//! own). The report also lists the posting's most frequent terms that the
+//! chosen CV never mentions.
+
+use crate::variant_catalogue::VariantCatalogue;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Occurrences of a keyword beyond this many add nothing to a score.
+const MAX_COUNTED_OCCURRENCES: usize = 3;
+
+/// How many of the posting's terms the keyword report considers.
+const IMPORTANT_KEYWORDS: usize = 15;
+
+/// Words too common in postings to say anything about the role.
+const STOPWORDS: &[&str] = &[
+    "about",
+    "across",
+    "all",
+    "also",
+    "and",
+    "any",
+    "apply",
+    "are",
+    "as",
+    "based",
+    "be",
+    "been",
+    "benefits",
+    "best",
+    "both",
+    "build",
+    "but",
+    "by",
+    "can",
+    "candidate",
+    "company",
+    "culture",
+    "day",
+    "do",
+    "each",
+    "environment",
+    "etc",
+    "every",
+    "experience",
+    "for",
+    "from",
+    "good",
+    "great",
+    "has",
+    "have",
+    "help",
+    "high",
+    "how",
+    "ideal",
+    "in",
+    "including",
+    "into",
+    "is",
+    "it",
+    "its",
+    "job",
+    "join",
+    "just",
+    "knowledge",
+    "like",
+    "looking",
+    "make",
+    "more",
+    "most",
+    "must",
+    "new",
+    "nice",
+    "not",
+    "of",
+    "on",
+    "one",
+    "opportunity",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "out",
+    "own",
+    "part",
+    "per",
+    "plus",
+    "position",
+    "preferred",
+    "related",
+    "required",
+    "requirements",
+    "responsibilities",
+    "role",
+    "salary",
+    "skills",
+    "so",
+    "some",
+    "strong",
+    "such",
+    "team",
+    "teams",
+    "than",
+    "that",
+    "the",
+    "their",
+    "them",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "time",
+    "to",
+    "understanding",
+    "up",
+    "us",
+    "use",
+    "using",
+    "very",
+    "want",
+    "we",
+    "well",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "why",
+    "will",
+    "with",
+    "within",
+    "work",
+    "working",
+    "would",
+    "year",
+    "years",
+    "you",
+    "your",
+];
+
+/// A job posting, reduced to its words.
+#[derive(Debug, Clone)]
+pub struct JobDescription {
+    source: String,
+    /// Normalised words, space separated.
+    text: String,
+}
+
+/// One catalogue keyword found in the posting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermMatch {
+    pub term: String,
+    pub weight: u32,
+    pub count: usize,
+}
+
+/// How well one variant matches the posting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantScore {
+    pub variant: String,
+    pub score: u32,
+    pub matched: Vec<TermMatch>,
+}
+
+/// What `--job-description` found: every variant's score, the variant built and
+/// the posting's terms its CV does not mention (`None` when the CV sources
+/// could not be read, e.g. a git template not cloned yet on a dry run).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobMatchReport {
+    pub source: String,
+    pub scores: Vec<VariantScore>,
+    pub chosen: String,
+    pub missing: Option<Vec<String>>,
+}
+
+impl JobDescription {
+    pub fn read(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let raw = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read the job description {}: {e}", path.display()))?;
+        let is_html = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+            || looks_like_html(&raw);
+        let text = if is_html { html_to_text(&raw) } else { raw };
+        Ok(Self::from_text(&path.display().to_string(), &text))
+    }
+
+    pub fn from_text(source: &str, text: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            text: normalise(text),
+        }
+    }
+
+    /// Whole-word occurrences of `term` (or its plural).
+    fn count(&self, term: &str) -> usize {
+        count_in(&self.text, term)
+    }
+
+    /// Every variant's score, best first; ties keep the catalogue order.
+    pub fn score(&self, catalogue: &VariantCatalogue) -> Vec<VariantScore> {
+        let mut scores: Vec<VariantScore> = catalogue
+            .rules()
+            .iter()
+            .map(|rule| {
+                let matched: Vec<TermMatch> = rule
+                    .keywords
+                    .iter()
+                    .filter_map(|keyword| {
+                        let count = self.count(&keyword.term);
+                        (count > 0).then(|| TermMatch {
+                            term: keyword.term.clone(),
+                            weight: keyword.weight,
+                            count,
+                        })
+                    })
+                    .collect();
+                let score = matched
+                    .iter()
+                    .map(|m| m.weight * m.count.min(MAX_COUNTED_OCCURRENCES) as u32)
+                    .sum();
+                VariantScore {
+                    variant: rule.name.clone(),
+                    score,
+                    matched,
+                }
+            })
+            .collect();
+        scores.sort_by_key(|score| std::cmp::Reverse(score.score));
+        scores
+    }
+
+    /// The posting's most telling terms: the catalogue keywords it contains and
+    /// the words it repeats, most frequent first.
+    pub fn important_keywords(&self, catalogue: &VariantCatalogue) -> Vec<String> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for word in self.text.split_whitespace() {
+            if word.len() >= 3
+                && !word.chars().all(|c| c.is_ascii_digit())
+                && !STOPWORDS.contains(&word)
+            {
+                *counts.entry(word.to_string()).or_default() += 1;
+            }
+        }
+        counts.retain(|_, count| *count >= 2);
+        for keyword in catalogue.rules().iter().flat_map(|rule| &rule.keywords) {
+            let count = self.count(&keyword.term);
+            if count > 0 {
+                counts.insert(keyword.term.clone(), count);
+            }
+        }
+
+        let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        // A word already covered by a longer phrase ("error" by "error budget")
+        // adds nothing to the report.
+        let is_phrase = |term: &str| normalise(term).contains(' ');
+        let phrases: Vec<String> = ranked
+            .iter()
+            .filter(|(term, _)| is_phrase(term))
+            .map(|(term, _)| normalise(term))
+            .collect();
+        ranked
+            .into_iter()
+            .map(|(term, _)| term)
+            .filter(|term| is_phrase(term) || !phrases.iter().any(|p| count_in(p, term) > 0))
+            .take(IMPORTANT_KEYWORDS)
+            .collect()
+    }
+
+    /// Score `catalogue`, and when `cv_text` is given, list the important
+    /// terms it does not mention.
+    pub fn report(
+        &self,
+        catalogue: &VariantCatalogue,
+        chosen: &str,
+        cv_text: Option<&str>,
+    ) -> JobMatchReport {
+        let missing = cv_text.map(|cv_text| {
+            let cv_text = normalise(cv_text);
+            self.important_keywords(catalogue)
+                .into_iter()
+                .filter(|term| count_in(&cv_text, term) == 0)
+                .collect()
+        });
+        JobMatchReport {
+            source: self.source.clone(),
+            scores: self.score(catalogue),
+            chosen: chosen.to_string(),
+            missing,
+        }
+    }
+}
+
+/// The best-scoring variant, unless no keyword matched at all.
+pub fn best_variant(scores: &[VariantScore]) -> Option<&VariantScore> {
+    scores.first().filter(|best| best.score > 0)
+}
+
+/// The LaTeX sources of `variant`'s CV in `template_dir`: every `.tex` file
+/// except the other variants' drivers and the cover letters.
+pub fn cv_sources_text(
+    template_dir: &Path,
+    prefix: &str,
+    variant: &str,
+    catalogue: &VariantCatalogue,
+) -> std::io::Result<String> {
+    let other_drivers: Vec<String> = catalogue
+        .names()
+        .into_iter()
+        .filter(|name| *name != variant)
+        .map(|name| format!("{prefix}-{name}.tex"))
+        .collect();
+
+    let cover_letters = format!("{prefix}-cover-");
+
+    let mut text = String::new();
+    let mut pending = vec![template_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if path.is_dir() {
+                if !name.starts_with('.') {
+                    pending.push(path);
+                }
+            } else if name.ends_with(".tex")
+                && !name.starts_with(&cover_letters)
+                && !other_drivers.iter().any(|d| d == name)
+            {
+                text.push_str(&fs::read_to_string(&path)?);
+                text.push('\n');
+            }
+        }
+    }
+    Ok(text)
+}
+
+impl fmt::Display for JobMatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Variant scores for {}:", self.source)?;
+        let width = self
+            .scores
+            .iter()
+            .map(|s| s.variant.len())
+            .max()
+            .unwrap_or(0);
+        for score in &self.scores {
+            let marker = if score.variant == self.chosen {
+                "*"
+            } else {
+                " "
+            };
+            write!(f, "{marker} {:<width$}  {:>3}", score.variant, score.score)?;
+            if !score.matched.is_empty() {
+                let terms: Vec<String> = score.matched.iter().map(TermMatch::describe).collect();
+                write!(f, "  matched: {}", terms.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        match &self.missing {
+            Some(missing) if missing.is_empty() => {
+                writeln!(
+                    f,
+                    "The {} CV mentions every key term of the posting.",
+                    self.chosen
+                )
+            }
+            Some(missing) => writeln!(
+                f,
+                "Key terms of the posting the {} CV does not mention: {}",
+                self.chosen,
+                missing.join(", ")
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+impl TermMatch {
+    fn describe(&self) -> String {
+        if self.count > 1 {
+            format!("{} x{} (w{})", self.term, self.count, self.weight)
+        } else {
+            format!("{} (w{})", self.term, self.weight)
+        }
+    }
+}
+
+/// Lowercase `text` and keep only its words: letters, digits, `+` and `#`
+/// (for "c++" and "c#"); everything else separates words.
+fn normalise(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '+' || c == '#' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whole-word occurrences of `term` (or `term` + "s") in a normalised `text`.
+fn count_in(text: &str, term: &str) -> usize {
+    let term = normalise(term);
+    let wanted: Vec<&str> = term.split(' ').collect();
+    let Some((last, head)) = wanted.split_last() else {
+        return 0;
+    };
+    if last.is_empty() {
+        return 0;
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words
+        .windows(wanted.len())
+        .filter(|window| {
+            let (window_last, window_head) = window.split_last().unwrap_or((&"", &[]));
+            window_head == head
+                && (window_last == last || window_last.strip_suffix('s') == Some(*last))
+        })
+        .count()
+}
+
+fn looks_like_html(text: &str) -> bool {
+    let head = text.trim_start().to_lowercase();
+    head.starts_with("<!doctype html") || head.starts_with("<html") || head.contains("<body")
+}
+
+/// The visible text of an HTML page: tags, scripts and styles dropped, the
+/// common entities decoded.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let lower = html.to_ascii_lowercase();
+    let mut i = 0;
+    while i < html.len() {
+        let rest = &html[i..];
+        if rest.starts_with('<') {
+            let skip_to = ["script", "style"]
+                .iter()
+                .find(|tag| lower[i + 1..].starts_with(*tag))
+                .and_then(|tag| lower[i..].find(&format!("</{tag}")))
+                .map(|end| i + end);
+            let from = skip_to.unwrap_or(i);
+            match html[from..].find('>') {
+                Some(end) => i = from + end + 1,
+                None => break,
+            }
+            text.push(' ');
+        } else if rest.starts_with('&') {
+            match rest.find(';').filter(|end| *end <= 10) {
+                Some(end) => {
+                    text.push_str(&decode_entity(&rest[1..end]));
+                    i += end + 1;
+                }
+                None => {
+                    text.push('&');
+                    i += 1;
+                }
+            }
+        } else {
+            let c = rest.chars().next().unwrap_or(' ');
+            text.push(c);
+            i += c.len_utf8();
+        }
+    }
+    text
+}
+
+fn decode_entity(entity: &str) -> String {
+    let numeric = entity
+        .strip_prefix("#x")
+        .or_else(|| entity.strip_prefix("#X"))
+        .map(|hex| u32::from_str_radix(hex, 16))
+        .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>));
+    if let Some(code) = numeric {
+        return code
+            .ok()
+            .and_then(char::from_u32)
+            .map(String::from)
+            .unwrap_or_default();
+    }
+    match entity {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" => " ",
+        _ => " ",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use configparser::ini::Ini;
+    use tempfile::TempDir;
+
+    fn catalogue() -> VariantCatalogue {
+        let mut ini = Ini::new();
+        ini.read(
+            "[variants]\n\
+             backend = 10: rust*3, api*2, postgres\n\
+             data = 20: spark*3, airflow*2, sql\n"
+                .to_string(),
+        )
+        .unwrap();
+        VariantCatalogue::from_ini(&ini, crate::variant_catalogue::CatalogueSource::Config)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_scores_weigh_and_cap_occurrences() {
+        let posting = JobDescription::from_text(
+            "posting",
+            "Rust rust RUST rust, REST APIs and Postgres. Some SQL.",
+        );
+
+        let scores = posting.score(&catalogue());
+
+        assert_eq!(scores[0].variant, "backend");
+        // rust: 3 x min(4, 3) + apis: 2 + postgres: 1
+        assert_eq!(scores[0].score, 12);
+        assert_eq!(scores[1].score, 1);
+        assert_eq!(best_variant(&scores).unwrap().variant, "backend");
+    }
+
+    #[test]
+    fn test_keywords_match_whole_words_only() {
+        let posting = JobDescription::from_text("posting", "Trusty sparkling apiary");
+
+        let scores = posting.score(&catalogue());
+
+        assert!(scores.iter().all(|s| s.score == 0));
+        assert!(best_variant(&scores).is_none());
+    }
+
+    #[test]
+    fn test_html_is_reduced_to_its_visible_text() {
+        let text = html_to_text(
+            "<html><head><style>.rust { color: red }</style>\
+             <script>var spark = 1;</script></head>\
+             <body><h1>Data&nbsp;Engineer</h1><p>Spark &amp; Airflow&#44; SQL</p></body></html>",
+        );
+        let posting = JobDescription::from_text("posting", &text);
+
+        assert_eq!(posting.text, "data engineer spark airflow sql");
+    }
+
+    #[test]
+    fn test_html_extension_or_content_selects_the_html_reader() {
+        let dir = TempDir::new().unwrap();
+        let saved = dir.path().join("posting.txt");
+        fs::write(
+            &saved,
+            "<!DOCTYPE html><html><body><b>Rust</b></body></html>",
+        )
+        .unwrap();
+
+        let posting = JobDescription::read(&saved).unwrap();
+
+        assert_eq!(posting.text, "rust");
+        assert!(JobDescription::read(&dir.path().join("missing.html")).is_err());
+    }
+
+    #[test]
+    fn test_report_lists_repeated_terms_the_cv_does_not_mention() {
+        let posting = JobDescription::from_text(
+            "posting",
+            "We want Rust and Kafka. Kafka streams, Kafka connect. \
+             Rust services with gRPC; gRPC everywhere. You will work with the team.",
+        );
+
+        let report = posting.report(&catalogue(), "backend", Some(r"\section{Rust} gRPC APIs"));
+
+        assert_eq!(report.chosen, "backend");
+        assert_eq!(report.missing, Some(vec!["kafka".to_string()]));
+        let printed = report.to_string();
+        assert!(printed.contains("* backend"), "got: {printed}");
+        assert!(printed.contains("rust x2 (w3)"), "got: {printed}");
+        assert!(
+            printed.contains("does not mention: kafka"),
+            "got: {printed}"
+        );
+    }
+
+    #[test]
+    fn test_cv_sources_skip_the_other_variants_drivers() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("CV-backend.tex"), "backend driver").unwrap();
+        fs::write(dir.path().join("CV-data.tex"), "data driver").unwrap();
+        fs::write(dir.path().join("CV-cover-backend.tex"), "cover letter").unwrap();
+        fs::create_dir(dir.path().join("sections")).unwrap();
+        fs::write(dir.path().join("sections/skills.tex"), "kafka").unwrap();
+
+        let text = cv_sources_text(dir.path(), "CV", "backend", &catalogue()).unwrap();
+
+        assert!(text.contains("backend driver"));
+        assert!(text.contains("kafka"));
+        assert!(!text.contains("data driver"));
+        assert!(!text.contains("cover letter"));
+    }
+}
@@ -3,6 +3,10 @@
 pub mod child_env;
 pub mod database;
 pub mod db_url;
+pub mod migrations;
 pub mod models;
 pub mod schema;
+pub mod status;
+pub mod sync;
+pub mod transfer;
 pub mod tui;
@@ -0,0 +1,159 @@
+use crate::cli_structure::OutputFormat;
+use rusty_cv_creator::models::Cv;
+use rusty_cv_creator::transfer::to_csv;
+
+const TABLE_HEADER: [&str; 7] = [
+    "ID",
+    "Date",
+    "Company",
+    "Job Title",
+    "Variant",
+    "Status",
+    "PDF Path",
+];
+
+/// Render `cvs` for `list --format`. An empty list still gets its header in
+/// `table` and `csv`; `ndjson` prints nothing for it.
+pub fn render_applications(
+    cvs: &[Cv],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(render_table(cvs)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(cvs)? + "\n"),
+        OutputFormat::Ndjson => {
+            let mut out = String::new();
+            for cv in cvs {
+                out.push_str(&serde_json::to_string(cv)?);
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        OutputFormat::Csv => to_csv(cvs),
+    }
+}
+
+/// The same columns as the TUI, padded to the widest cell. The last column is
+/// not padded so lines carry no trailing whitespace.
+fn render_table(cvs: &[Cv]) -> String {
+    let rows: Vec<[String; 7]> = cvs
+        .iter()
+        .map(|cv| {
+            [
+                cv.id.to_string(),
+                cv.application_date
+                    .as_deref()
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                cv.company.clone(),
+                cv.job_title.clone(),
+                cv.variant.clone().unwrap_or_default(),
+                cv.status.clone(),
+                cv.pdf_cv_path.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths = TABLE_HEADER.map(|title| title.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header = TABLE_HEADER.map(String::from);
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let last = row.len() - 1;
+        for (i, cell) in row.iter().enumerate() {
+            if i == last {
+                out.push_str(cell);
+            } else {
+                out.push_str(&format!("{cell:<width$}  ", width = widths[i]));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cv(id: i32, company: &str) -> Cv {
+        Cv {
+            id,
+            application_date: Some(" 7-May-2024".to_string()),
+            job_title: "DevOps, Senior".to_string(),
+            company: company.to_string(),
+            quote: "Ship it".to_string(),
+            pdf_cv_path: format!("/cv/{id}.pdf"),
+            generated: true,
+            status: "drafted".to_string(),
+            variant: Some("senior-sre".to_string()),
+            template_ref: None,
+            cover_letter_path: None,
+            compaction_level: None,
+            uuid: format!("00000000-0000-4000-8000-{id:012}"),
+            updated_at: "2024-05-07T09:30:00.000000Z".to_string(),
+            synced_at: None,
+        }
+    }
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let out = render_applications(&[cv(1, "ACME"), cv(12, "Globex Corp")], OutputFormat::Table)
+            .unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("ID  Date"));
+        let status_col = lines[0].find("Status").unwrap();
+        assert_eq!(&lines[1][status_col..status_col + 7], "drafted");
+        assert_eq!(&lines[2][status_col..status_col + 7], "drafted");
+        assert!(lines[1].ends_with("/cv/1.pdf"));
+    }
+
+    #[test]
+    fn test_json_is_an_array_of_objects() {
+        let out = render_applications(&[cv(1, "ACME")], OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(value[0]["company"], "ACME");
+        assert_eq!(value[0]["status"], "drafted");
+        assert_eq!(value[0]["variant"], "senior-sre");
+    }
+
+    #[test]
+    fn test_ndjson_is_one_object_per_line() {
+        let out =
+            render_applications(&[cv(1, "ACME"), cv(2, "Globex")], OutputFormat::Ndjson).unwrap();
+        let ids: Vec<i64> = out
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["id"]
+                    .as_i64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert!(
+            render_applications(&[], OutputFormat::Ndjson)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_csv_quotes_fields_and_keeps_the_header_when_empty() {
+        let out = render_applications(&[cv(1, "ACME")], OutputFormat::Csv).unwrap();
+        let mut lines = out.lines();
+        let header = lines.next().unwrap();
+        assert!(header.starts_with("id,application_date,job_title,company,"));
+        assert!(!header.contains("synced_at"));
+        assert!(lines.next().unwrap().contains("\"DevOps, Senior\""));
+
+        let empty = render_applications(&[], OutputFormat::Csv).unwrap();
+        assert_eq!(empty, format!("{header}\n"));
+    }
+}
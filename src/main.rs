@@ -4,29 +4,55 @@ use core::panic;
 
 use clap::Parser;
 use dotenvy::dotenv;
-use log::{error, info};
+use log::{error, info, warn};
 use std::io;
+use std::path::Path;
 
+mod all_variants;
+mod application_tex;
+mod artifacts;
+mod builder;
 mod cli_structure;
 mod command_runner;
 mod config_parse;
 mod cv_insert;
+mod cv_status;
+mod cv_update;
+mod db_command;
+mod destination_layout;
 mod file_handlers;
 mod global_conf;
 mod helpers;
+mod insert_plan;
+mod job_description;
+mod list_output;
+mod overflow;
+mod path_slug;
+mod pdf_metadata;
+mod pdf_pages;
+mod pending_saves;
+mod preview;
 mod template_source;
+mod tex_diagnostics;
 mod user_action;
+mod variant_catalogue;
 
+use crate::all_variants::build_variants;
+use crate::application_tex::{ApplicationDetails, write_application_tex};
 use crate::cli_structure::{UserAction, UserInput, match_user_action};
 use crate::command_runner::{CommandRunner, SystemRunner};
 use crate::config_parse::{build_context, get_variable_from_config_file};
+use crate::destination_layout::DestinationLayout;
 use crate::file_handlers::{
-    BuildConfig, compile_cv, create_directory, remove_created_dir_from_pro, resolve_variant,
+    BuildConfig, compile_cover_letter, compile_cv, cover_letter_document, create_directory,
+    remove_created_dir_from_pro, select_variant, template_revision,
 };
 use crate::global_conf::AppContext;
 use crate::helpers::{
-    check_if_db_env_is_set_or_set_from_config, ensure_tools_available, view_cv_file,
+    check_if_db_env_is_set_or_set_from_config, ensure_tools_available, my_fzf, view_cv_file,
 };
+use crate::pdf_metadata::{PdfMetadata, stamp_pdf_metadata};
+use crate::variant_catalogue::VariantCatalogue;
 
 #[cfg_attr(coverage_nightly, coverage(off))]
 fn main() {
@@ -36,7 +62,15 @@ fn main() {
     let user_input = UserInput::parse();
 
     let ctx = build_context(&user_input);
-    let _ = check_if_db_env_is_set_or_set_from_config(&ctx);
+    if let Err(e) = DestinationLayout::from_context(&ctx) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+    // Only the commands that open the database need it; they report the
+    // missing configuration themselves when they connect.
+    if let Err(e) = check_if_db_env_is_set_or_set_from_config(&ctx) {
+        warn!("The database is not set up: {e}");
+    }
 
     let _action: UserAction = ctx.get_user_input_action();
 
@@ -65,23 +99,28 @@ fn main() {
     }
 }
 
+/// A freshly built CV: where the PDF was placed and what it was built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BuiltCv {
+    pdf_path: String,
+    variant: String,
+    template_ref: Option<String>,
+    cover_letter_path: Option<String>,
+    compaction_level: Option<String>,
+}
+
 fn prepare_cv(
     ctx: &AppContext,
-    runner: &dyn CommandRunner,
-    job_title: &str,
-    company_name: &str,
+    runner: &(dyn CommandRunner + Sync),
+    application: &ApplicationDetails,
     variant_flag: Option<&String>,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let default_variant = get_variable_from_config_file(ctx, "variant", "default")
-        .unwrap_or_else(|_| "senior-devops".to_string());
-    let variant = resolve_variant(variant_flag, job_title, &default_variant);
-    info!("Selected CV variant: {variant}");
-
+) -> Result<BuiltCv, Box<dyn std::error::Error>> {
+    let job_title = application.job_title;
+    let company_name = application.company;
     let cfg = BuildConfig::from_context(ctx)?;
-    let pdf_basename = format!("{}-{variant}.pdf", cfg.prefix);
 
-    // Pre-usage check: the builder (`just`) drives `tectonic` via the Justfile.
-    ensure_tools_available(&[cfg.builder.as_str(), "tectonic"])?;
+    // Pre-usage check: the tools of the configured build backend.
+    ensure_tools_available(&cfg.builder.required_tools())?;
 
     let created_cv_dir = match create_directory(ctx, runner, job_title, company_name) {
         Ok(s) => s,
@@ -93,12 +132,80 @@ fn prepare_cv(
         }
     };
 
-    compile_cv(runner, &created_cv_dir, &variant, &cfg)?;
+    write_application_tex(Path::new(&created_cv_dir), application)?;
+
+    // The variant catalogue is checked against the driver files of the
+    // template actually copied for this build.
+    let (variant, compaction_level) = match ctx.get_all_variants_request() {
+        Some(request) => {
+            let catalogue =
+                VariantCatalogue::load(ctx, Some(Path::new(&created_cv_dir)), &cfg.prefix)?;
+            let report = build_variants(
+                runner,
+                &created_cv_dir,
+                &catalogue.names(),
+                &cfg,
+                request.parallel,
+            );
+            print!("{report}");
+            let variant = report.choose(request.keep.as_deref(), |candidates| {
+                my_fzf(candidates.iter().map(|c| format!("{c}\n")).collect())
+            })?;
+            (variant, None)
+        }
+        None => {
+            let choice = select_variant(
+                ctx,
+                Some(Path::new(&created_cv_dir)),
+                &cfg.prefix,
+                variant_flag,
+                job_title,
+            )?;
+            if let Some(job_match) = &choice.job_match {
+                print!("{job_match}");
+            }
+            let compaction_level = compile_cv(runner, &created_cv_dir, &choice.variant, &cfg)?;
+            (choice.variant, compaction_level)
+        }
+    };
+    info!("Selected CV variant: {variant}");
+    let pdf_basename = format!("{}-{variant}.pdf", cfg.prefix);
+
+    let cover_letter_basename = if ctx.wants_cover_letter() {
+        compile_cover_letter(runner, &created_cv_dir, &variant, &cfg)?;
+        Some(format!(
+            "{}-{}.pdf",
+            cfg.prefix,
+            cover_letter_document(&variant)
+        ))
+    } else {
+        None
+    };
+    let metadata = PdfMetadata::from_context(ctx, &cfg.prefix, job_title, company_name, &variant);
+    for basename in std::iter::once(pdf_basename.as_str()).chain(cover_letter_basename.as_deref()) {
+        stamp_pdf_metadata(&Path::new(&created_cv_dir).join(basename), &metadata)?;
+    }
+
+    // Read before the cleanup below removes the working copy of the template.
+    let template_ref = template_revision(ctx, runner, &created_cv_dir);
 
-    let output_pdf =
-        remove_created_dir_from_pro(ctx, job_title, company_name, &created_cv_dir, &pdf_basename)?;
+    let published = remove_created_dir_from_pro(
+        ctx,
+        job_title,
+        company_name,
+        &variant,
+        &created_cv_dir,
+        &pdf_basename,
+        cover_letter_basename.as_deref(),
+    )?;
 
-    Ok(output_pdf)
+    Ok(BuiltCv {
+        pdf_path: published.cv,
+        variant,
+        template_ref,
+        cover_letter_path: published.cover_letter,
+        compaction_level,
+    })
 }
 
 /// Checks if the device is connected to Tailscale.
@@ -117,11 +224,14 @@ fn is_tailscale_connected(runner: &dyn CommandRunner) -> io::Result<bool> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::command_runner::testing::FakeRunner;
+    use crate::command_runner::testing::{
+        DocumentWritingRunner, FakeRunner, hermetic_build_section,
+    };
 
     /// A fake builder that "compiles" by writing the expected PDF into `cwd`
     /// and emitting a contract-compliant 2-page transcript line, so the
-    /// page-count guard in `compile_cv` is satisfied.
+    /// page-count guard in `compile_cv` is satisfied. It also checks that the
+    /// per-application macros were written before the build.
     struct PdfWritingRunner {
         pdf_name: String,
     }
@@ -129,7 +239,7 @@ mod tests {
     impl CommandRunner for PdfWritingRunner {
         fn status(&self, _program: &str, _args: &[&str], cwd: Option<&str>) -> io::Result<bool> {
             if let Some(dir) = cwd {
-                std::fs::write(format!("{dir}/{}", self.pdf_name), b"%PDF-1.4")?;
+                crate::pdf_pages::write_test_pdf(&Path::new(dir).join(&self.pdf_name), 2);
             }
             Ok(true)
         }
@@ -146,7 +256,11 @@ mod tests {
             cwd: Option<&str>,
         ) -> io::Result<crate::command_runner::CommandOutcome> {
             if let Some(dir) = cwd {
-                std::fs::write(format!("{dir}/{}", self.pdf_name), b"%PDF-1.4")?;
+                let macros = std::fs::read_to_string(
+                    Path::new(dir).join(crate::application_tex::APPLICATION_TEX),
+                )?;
+                assert!(macros.contains(r"\renewcommand{\company}{ACME}"));
+                crate::pdf_pages::write_test_pdf(&Path::new(dir).join(&self.pdf_name), 2);
             }
             Ok(crate::command_runner::CommandOutcome {
                 success: true,
@@ -173,10 +287,11 @@ mod tests {
         let ini = format!(
             "[cv]\ncv_template_path = \"{tpl}\"\ncv_file_prefix = \"TestCV\"\n\
              [variant]\ndefault = \"senior-devops\"\n\
-             [build]\nbuilder = \"just\"\nrecipe = \"build\"\n\
+             {build}\
              [destination]\ncv_path = \"{dest}\"\noutput_pdf = \"{out}\"\n\
              [db]\nengine = \"sqlite\"\ndb_file = \"x.db\"\n",
             tpl = template.display(),
+            build = hermetic_build_section(),
             dest = dest.display(),
             out = out.display()
         );
@@ -184,7 +299,7 @@ mod tests {
         std::fs::write(&ini_path, ini).unwrap();
 
         let ui = UserInput {
-            action: UserAction::List(cli_structure::FilterArgs::default()),
+            action: UserAction::List(cli_structure::ListArgs::default()),
             save_to_database: false,
             view_generated_cv: false,
             dry_run: false,
@@ -199,11 +314,121 @@ mod tests {
             pdf_name: "TestCV-senior-devops.pdf".to_string(),
         };
         // "Senior DevOps" infers the senior-devops variant.
-        let output_pdf = prepare_cv(&ctx, &runner, "Senior DevOps", "ACME", None).unwrap();
+        let application = ApplicationDetails {
+            job_title: "Senior DevOps",
+            company: "ACME",
+            quote: "",
+            application_date: "18-Oct-2026",
+        };
+        let built = prepare_cv(&ctx, &runner, &application, None).unwrap();
 
-        let out_path = std::path::Path::new(&output_pdf);
+        let out_path = std::path::Path::new(&built.pdf_path);
         assert!(out_path.is_file());
         assert_eq!(out_path.extension().and_then(|e| e.to_str()), Some("pdf"));
+        assert_eq!(built.variant, "senior-devops");
+        // A plain (non-git) template without a configured ref has no revision.
+        assert_eq!(built.template_ref, None);
+        let document = lopdf::Document::load(out_path).unwrap();
+        let info_id = document
+            .trailer
+            .get(b"Info")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let title = document
+            .get_dictionary(info_id)
+            .unwrap()
+            .get(b"Title")
+            .unwrap();
+        assert_eq!(
+            lopdf::decode_text_string(title).unwrap(),
+            "Senior DevOps — ACME"
+        );
+    }
+
+    #[test]
+    fn test_every_pdf_the_dry_run_plans_to_stamp_is_published_stamped() {
+        use crate::insert_plan::{PlanStep, plan_insert};
+
+        let td = tempfile::TempDir::new().unwrap();
+        let base = td.path();
+        let template = base.join("template");
+        std::fs::create_dir_all(&template).unwrap();
+        std::fs::write(template.join("TestCV-senior-sre.tex"), "x").unwrap();
+        std::fs::write(template.join("TestCV-cover-senior-sre.tex"), "x").unwrap();
+        let ini = format!(
+            "[cv]\ncv_template_path = \"{tpl}\"\ncv_file_prefix = \"TestCV\"\n\
+             author = \"Ada Lovelace\"\n\
+             {build}\
+             [destination]\ncv_path = \"{dest}\"\noutput_pdf = \"{out}\"\n\
+             [db]\nengine = \"sqlite\"\ndb_file = \"x.db\"\n",
+            tpl = template.display(),
+            build = hermetic_build_section(),
+            dest = base.join("dest").display(),
+            out = base.join("out").display()
+        );
+        let ini_path = base.join("conf.ini");
+        std::fs::write(&ini_path, ini).unwrap();
+        let ctx = build_context(&UserInput {
+            action: UserAction::Insert(cli_structure::InsertArgs {
+                job_title: "Senior SRE".to_string(),
+                company_name: "ACME".to_string(),
+                cover_letter: true,
+                ..cli_structure::InsertArgs::default()
+            }),
+            save_to_database: false,
+            view_generated_cv: false,
+            dry_run: false,
+            config_ini: ini_path.to_str().unwrap().to_string(),
+            engine: "sqlite".to_string(),
+            repo: None,
+            branch: None,
+        });
+
+        let plan = plan_insert(&ctx).unwrap();
+        let application = ApplicationDetails {
+            job_title: "Senior SRE",
+            company: "ACME",
+            quote: "",
+            application_date: "18-Oct-2026",
+        };
+        prepare_cv(&ctx, &DocumentWritingRunner, &application, None).unwrap();
+
+        let stamps: Vec<_> = plan
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                PlanStep::StampMetadata { pdf, metadata } => Some((pdf, metadata)),
+                _ => None,
+            })
+            .collect();
+        // The CV and the cover letter.
+        assert_eq!(stamps.len(), 2);
+        for step in &plan.steps {
+            let PlanStep::CopyFile { from, to } = step else {
+                continue;
+            };
+            let (_, metadata) = stamps
+                .iter()
+                .find(|(pdf, _)| *pdf == from)
+                .unwrap_or_else(|| std::panic!("{from} is published without a planned stamp"));
+            let document = lopdf::Document::load(to).unwrap();
+            let info_id = document
+                .trailer
+                .get(b"Info")
+                .unwrap()
+                .as_reference()
+                .unwrap();
+            let info = document.get_dictionary(info_id).unwrap();
+            for (key, expected) in [
+                (b"Title".as_slice(), &metadata.title),
+                (b"Author", &metadata.author),
+            ] {
+                let value = lopdf::decode_text_string(info.get(key).unwrap()).unwrap();
+                assert_eq!(&value, expected, "{to}");
+            }
+        }
+        assert_eq!(stamps[0].1.author, "Ada Lovelace");
     }
 
     #[test]
@@ -0,0 +1,223 @@
+//! The schema migrations, embedded in the binary.
+//!
+//! Each engine has its own copy of the migrations under `migrations/<engine>`,
+//! with the same directory names (hence the same versions): the SQL only
+//! differs where the dialects do, e.g. `SERIAL` against
+//! `INTEGER PRIMARY KEY AUTOINCREMENT` for the `cv` id. Diesel records the
+//! applied versions in `__diesel_schema_migrations`, so a database set up with
+//! `diesel-cli` from the same directories is picked up where it stands, and
+//! one whose `cv` table predates the migration history (created by hand, or
+//! by an older release) has the initial migration recorded as applied rather
+//! than run again.
+
+use crate::database::DbConnection;
+use diesel::RunQueryDsl;
+use diesel::backend::Backend;
+use diesel::migration::MigrationSource;
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+const POSTGRES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+
+/// One embedded migration and whether the database has applied it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationState {
+    /// The migration directory name, e.g. `2024-06-06-052525_cvs`.
+    pub name: String,
+    pub applied: bool,
+}
+
+/// Apply every pending migration, oldest first, each in its own transaction.
+/// Returns the names of the migrations applied; empty when the schema was
+/// already up to date.
+pub fn run_pending_migrations(
+    conn: &mut DbConnection,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    baseline_existing_schema(conn)?;
+    let applied = match conn {
+        DbConnection::Postgresql(pg) => run_pending(pg, POSTGRES_MIGRATIONS),
+        DbConnection::Sqlite(sqlite) => run_pending(sqlite, SQLITE_MIGRATIONS),
+    };
+    applied.map_err(|e| format!("Could not migrate the database schema: {e}").into())
+}
+
+/// A fresh in-memory `SQLite` database with every migration applied: the
+/// schema the tests run against.
+pub fn migrated_in_memory_sqlite() -> Result<DbConnection, Box<dyn std::error::Error>> {
+    let mut conn = crate::database::establish_connection("sqlite", ":memory:")?;
+    run_pending_migrations(&mut conn)?;
+    Ok(conn)
+}
+
+/// Every embedded migration for the connection's engine, oldest first.
+pub fn migration_status(
+    conn: &mut DbConnection,
+) -> Result<Vec<MigrationState>, Box<dyn std::error::Error>> {
+    let status = match conn {
+        DbConnection::Postgresql(pg) => status(pg, &POSTGRES_MIGRATIONS),
+        DbConnection::Sqlite(sqlite) => status(sqlite, &SQLITE_MIGRATIONS),
+    };
+    status.map_err(|e| format!("Could not read the applied migrations: {e}").into())
+}
+
+/// The names of the migrations the database has not applied yet.
+pub fn pending_migrations(
+    conn: &mut DbConnection,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Ok(migration_status(conn)?
+        .into_iter()
+        .filter(|state| !state.applied)
+        .map(|state| state.name)
+        .collect())
+}
+
+/// Record the initial migration as applied when the database already has a
+/// `cv` table but no applied migration: running it would fail with "table cv
+/// already exists". Returns the name recorded, if any.
+pub fn baseline_existing_schema(
+    conn: &mut DbConnection,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let states = migration_status(conn)?;
+    let Some(initial) = states.first() else {
+        return Ok(None);
+    };
+    let has_cv_table = diesel::sql_query("SELECT 1 FROM cv LIMIT 1")
+        .execute(conn)
+        .is_ok();
+    if states.iter().any(|state| state.applied) || !has_cv_table {
+        return Ok(None);
+    }
+
+    let version: String = initial
+        .name
+        .chars()
+        .take_while(|&c| c != '_')
+        .filter(char::is_ascii_digit)
+        .collect();
+    // Only digits: safe to inline, and placeholders differ between engines.
+    diesel::sql_query(format!(
+        "INSERT INTO __diesel_schema_migrations (version) VALUES ('{version}')"
+    ))
+    .execute(conn)
+    .map_err(|e| format!("Could not record the existing schema as migrated: {e}"))?;
+    log::info!(
+        "Recorded the existing cv table as migration {}",
+        initial.name
+    );
+    Ok(Some(initial.name.clone()))
+}
+
+type HarnessResult<T> = diesel::migration::Result<T>;
+
+fn run_pending<DB: Backend>(
+    conn: &mut impl MigrationHarness<DB>,
+    source: EmbeddedMigrations,
+) -> HarnessResult<Vec<String>> {
+    let pending: Vec<String> = status(conn, &source)?
+        .into_iter()
+        .filter(|state| !state.applied)
+        .map(|state| state.name)
+        .collect();
+    conn.run_pending_migrations(source)?;
+    Ok(pending)
+}
+
+fn status<DB: Backend>(
+    conn: &mut impl MigrationHarness<DB>,
+    source: &EmbeddedMigrations,
+) -> HarnessResult<Vec<MigrationState>> {
+    let applied = conn.applied_migrations()?;
+    let mut migrations = MigrationSource::<DB>::migrations(source)?;
+    migrations.sort_unstable_by(|a, b| a.name().version().cmp(&b.name().version()));
+    Ok(migrations
+        .iter()
+        .map(|migration| MigrationState {
+            name: migration.name().to_string(),
+            applied: applied.contains(&migration.name().version().as_owned()),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::prelude::*;
+
+    fn fresh_sqlite() -> DbConnection {
+        DbConnection::Sqlite(SqliteConnection::establish(":memory:").unwrap())
+    }
+
+    #[test]
+    fn test_fresh_sqlite_database_gets_every_migration() {
+        let mut conn = fresh_sqlite();
+        assert!(
+            migration_status(&mut conn)
+                .unwrap()
+                .iter()
+                .all(|s| !s.applied)
+        );
+
+        let applied = run_pending_migrations(&mut conn).unwrap();
+
+        assert_eq!(applied.first().unwrap(), "2024-06-06-052525_cvs");
+        assert_eq!(applied.len(), migration_status(&mut conn).unwrap().len());
+        assert!(pending_migrations(&mut conn).unwrap().is_empty());
+        assert!(run_pending_migrations(&mut conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_ids_are_assigned_by_the_database() {
+        let mut conn = fresh_sqlite();
+        run_pending_migrations(&mut conn).unwrap();
+
+        let first =
+            crate::database::save_new_cv_to_db(&mut conn, "/a.pdf", "SRE", "ACME", None, "2026")
+                .unwrap();
+        let second =
+            crate::database::save_new_cv_to_db(&mut conn, "/b.pdf", "Dev", "ACME", None, "2026")
+                .unwrap();
+
+        assert_eq!((first.id, second.id), (1, 2));
+    }
+
+    #[test]
+    fn test_a_cv_table_without_migration_history_is_baselined() {
+        let mut conn = fresh_sqlite();
+        diesel::sql_query(
+            "CREATE TABLE cv (id INTEGER PRIMARY KEY AUTOINCREMENT, application_date VARCHAR, \
+             job_title VARCHAR NOT NULL, company VARCHAR NOT NULL, quote VARCHAR NOT NULL, \
+             pdf_cv_path VARCHAR NOT NULL, generated BOOLEAN NOT NULL DEFAULT TRUE)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+        diesel::sql_query(
+            "INSERT INTO cv (job_title, company, quote, pdf_cv_path) \
+             VALUES ('SRE', 'ACME', '', '/a.pdf')",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        let applied = run_pending_migrations(&mut conn).unwrap();
+
+        assert!(!applied.contains(&"2024-06-06-052525_cvs".to_string()));
+        assert!(pending_migrations(&mut conn).unwrap().is_empty());
+        let rows = crate::database::load_all_applications(&mut conn).unwrap();
+        assert_eq!(
+            (rows[0].job_title.as_str(), rows[0].status.as_str()),
+            ("SRE", "drafted")
+        );
+        assert_eq!(baseline_existing_schema(&mut conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_both_engines_ship_the_same_migrations() {
+        let names = |source: EmbeddedMigrations| -> Vec<String> {
+            MigrationSource::<diesel::sqlite::Sqlite>::migrations(&source)
+                .unwrap()
+                .iter()
+                .map(|migration| migration.name().to_string())
+                .collect()
+        };
+        assert_eq!(names(POSTGRES_MIGRATIONS), names(SQLITE_MIGRATIONS));
+    }
+}
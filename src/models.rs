@@ -1,7 +1,8 @@
 use crate::schema::cv;
 use diesel::prelude::*;
+use serde::Serialize;
 
-#[derive(Queryable, Selectable, Debug)]
+#[derive(Queryable, Selectable, Debug, Default, Serialize)]
 #[diesel(table_name = cv)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 pub struct Cv {
@@ -12,6 +13,27 @@ pub struct Cv {
     pub quote: String,
     pub pdf_cv_path: String,
     pub generated: bool,
+    /// Lifecycle stage, stored as an `ApplicationStatus` name.
+    pub status: String,
+    /// The CV variant that was built, e.g. `senior-sre`. `None` for rows saved
+    /// before variants were recorded.
+    pub variant: Option<String>,
+    /// The template revision the PDF was built from: the commit SHA when the
+    /// template is a git checkout, else the configured ref, if any.
+    pub template_ref: Option<String>,
+    /// The cover letter built alongside the CV (`insert --cover-letter`).
+    pub cover_letter_path: Option<String>,
+    /// The `[build] compaction_levels` entry the CV was built at to fit the
+    /// page contract. `None` when it fit as is.
+    pub compaction_level: Option<String>,
+    /// The identity of the application across databases (`db sync`).
+    pub uuid: String,
+    /// When the row was last written, RFC 3339 in UTC.
+    pub updated_at: String,
+    /// The `updated_at` both copies agreed on at the last `db sync`; `None`
+    /// for a row never synced.
+    #[serde(skip)]
+    pub synced_at: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -23,4 +45,46 @@ pub struct NewCv<'a> {
     pub quote: &'a str,
     pub pdf_cv_path: &'a str,
     pub generated: bool,
+    pub variant: Option<&'a str>,
+    pub template_ref: Option<&'a str>,
+    pub cover_letter_path: Option<&'a str>,
+    pub compaction_level: Option<&'a str>,
+    pub uuid: &'a str,
+}
+
+/// A partial edit of a stored application: every `None` field is left untouched
+/// by the generated `UPDATE`, so callers only describe what actually changes.
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = cv)]
+pub struct CvChanges<'a> {
+    pub application_date: Option<&'a str>,
+    pub job_title: Option<&'a str>,
+    pub company: Option<&'a str>,
+    pub quote: Option<&'a str>,
+    pub pdf_cv_path: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub variant: Option<&'a str>,
+    /// `Some(None)` clears the revision of a CV rebuilt from an unversioned
+    /// template.
+    pub template_ref: Option<Option<&'a str>>,
+    /// `Some(None)` clears the cover letter of a CV rebuilt without one.
+    pub cover_letter_path: Option<Option<&'a str>>,
+    /// `Some(None)` clears the level of a CV rebuilt without compaction.
+    pub compaction_level: Option<Option<&'a str>>,
+}
+
+impl CvChanges<'_> {
+    /// `true` when no field would be written — diesel rejects an empty changeset.
+    pub fn is_empty(&self) -> bool {
+        self.application_date.is_none()
+            && self.job_title.is_none()
+            && self.company.is_none()
+            && self.quote.is_none()
+            && self.pdf_cv_path.is_none()
+            && self.status.is_none()
+            && self.variant.is_none()
+            && self.template_ref.is_none()
+            && self.cover_letter_path.is_none()
+            && self.compaction_level.is_none()
+    }
 }
@@ -0,0 +1,234 @@
+//! A CV over the page contract: how far it overflows, and the retries that
+//! make it fit.
+//!
+//! A template can honour a compaction macro (`\compactlevel` by default) —
+//! tighter spacing, a smaller font, fewer bullet points — and declare the
+//! levels to try in `[build] compaction_levels`. When the CV breaks the page
+//! contract, each level in turn is written into `application.tex` and the CV
+//! rebuilt, until it fits; the level used is recorded with the application.
+
+use crate::application_tex::write_application_macro;
+use std::fmt;
+use std::path::Path;
+
+/// The macro a template reads its compaction level from, unless
+/// `[build] compaction_macro` names another.
+pub const DEFAULT_COMPACTION_MACRO: &str = "compactlevel";
+
+/// A successful build whose PDF has more pages than the contract allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageOverflow {
+    pub pages: u32,
+    pub max_pages: u32,
+    /// Roughly how many lines of text spill onto the last page, when the PDF
+    /// could be read.
+    pub last_page_lines: Option<u32>,
+}
+
+impl fmt::Display for PageOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let over = self.pages.saturating_sub(self.max_pages);
+        write!(
+            f,
+            "CV is {} pages; the contract allows at most {}: {over} page{} over",
+            self.pages,
+            self.max_pages,
+            if over == 1 { "" } else { "s" }
+        )?;
+        if let Some(lines) = self.last_page_lines {
+            write!(
+                f,
+                ", about {lines} line{} on page {}",
+                if lines == 1 { "" } else { "s" },
+                self.pages
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PageOverflow {}
+
+/// The compaction knob of a template: a macro and the levels to try, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compaction {
+    pub macro_name: String,
+    pub levels: Vec<String>,
+}
+
+impl Compaction {
+    /// From `[build] compaction_macro` and the comma-separated
+    /// `[build] compaction_levels`; `None` (no retry) without levels.
+    pub fn from_config(
+        macro_name: Option<String>,
+        levels: Option<String>,
+    ) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let levels: Vec<String> = levels
+            .iter()
+            .flat_map(|levels| levels.split(','))
+            .map(str::trim)
+            .filter(|level| !level.is_empty())
+            .map(str::to_string)
+            .collect();
+        if levels.is_empty() {
+            return Ok(None);
+        }
+        let macro_name = macro_name
+            .map(|name| name.trim().trim_start_matches('\\').to_string())
+            .unwrap_or_else(|| DEFAULT_COMPACTION_MACRO.to_string());
+        if macro_name.is_empty() || !macro_name.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!(
+                "[build] compaction_macro '{macro_name}' is not a LaTeX macro name (letters only)"
+            )
+            .into());
+        }
+        Ok(Some(Self { macro_name, levels }))
+    }
+}
+
+/// Run `build` (which returns the page count, or a `PageOverflow` error) and,
+/// while it overflows, retry at each level of `compaction`, writing it into
+/// the `application.tex` of `cv_dir` first.
+///
+/// Returns the level the CV fit at, `None` when it fit as is. Any other error,
+/// or an overflow without compaction levels, is returned unchanged; an
+/// overflow at the last level reports it.
+pub fn fit_page_contract(
+    cv_dir: &Path,
+    compaction: Option<&Compaction>,
+    mut build: impl FnMut() -> Result<u32, Box<dyn std::error::Error>>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut overflow = match build() {
+        Ok(_) => return Ok(None),
+        Err(e) => e.downcast::<PageOverflow>()?,
+    };
+    let Some(compaction) = compaction else {
+        return Err(overflow);
+    };
+
+    for level in &compaction.levels {
+        println!(
+            "⚠️  {overflow}; retrying with \\{}={level}",
+            compaction.macro_name
+        );
+        write_application_macro(cv_dir, &compaction.macro_name, level)?;
+        match build() {
+            Ok(pages) => {
+                println!(
+                    "✅ The CV fits in {pages} pages at \\{}={level}",
+                    compaction.macro_name
+                );
+                return Ok(Some(level.clone()));
+            }
+            Err(e) => overflow = e.downcast::<PageOverflow>()?,
+        }
+    }
+    Err(format!(
+        "{overflow}, even at the last compaction level \\{}={}",
+        compaction.macro_name,
+        compaction.levels.last().map_or("", String::as_str)
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application_tex::APPLICATION_TEX;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn overflow(pages: u32) -> Box<dyn std::error::Error> {
+        Box::new(PageOverflow {
+            pages,
+            max_pages: 2,
+            last_page_lines: Some(4),
+        })
+    }
+
+    fn compaction() -> Compaction {
+        Compaction::from_config(None, Some("1, 2,3".to_string()))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_overflow_reports_pages_and_lines_over() {
+        assert_eq!(
+            overflow(3).to_string(),
+            "CV is 3 pages; the contract allows at most 2: 1 page over, about 4 lines on page 3"
+        );
+    }
+
+    #[test]
+    fn test_compaction_is_read_from_the_config() {
+        assert_eq!(
+            compaction(),
+            Compaction {
+                macro_name: "compactlevel".to_string(),
+                levels: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            }
+        );
+        assert_eq!(Compaction::from_config(None, None).unwrap(), None);
+        assert_eq!(
+            Compaction::from_config(Some("\\tight".to_string()), Some("a".to_string()))
+                .unwrap()
+                .unwrap()
+                .macro_name,
+            "tight"
+        );
+        assert!(
+            Compaction::from_config(Some("compact level".to_string()), Some("1".to_string()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fit_retries_each_level_until_the_cv_fits() {
+        let td = TempDir::new().unwrap();
+        let mut pages = vec![3, 3, 2].into_iter();
+        let mut levels_seen = Vec::new();
+
+        let level = fit_page_contract(td.path(), Some(&compaction()), || {
+            let macros = fs::read_to_string(td.path().join(APPLICATION_TEX)).unwrap_or_default();
+            levels_seen.push(macros.lines().last().map(str::to_string));
+            match pages.next().unwrap() {
+                2 => Ok(2),
+                over => Err(overflow(over)),
+            }
+        })
+        .unwrap();
+
+        assert_eq!(level.as_deref(), Some("2"));
+        assert_eq!(levels_seen[0], None);
+        assert_eq!(
+            levels_seen[2].as_deref(),
+            Some(r"\providecommand{\compactlevel}{}\renewcommand{\compactlevel}{2}")
+        );
+    }
+
+    #[test]
+    fn test_fit_gives_up_after_the_last_level() {
+        let td = TempDir::new().unwrap();
+
+        let err = fit_page_contract(td.path(), Some(&compaction()), || Err(overflow(3)))
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("1 page over"), "got: {err}");
+        assert!(err.ends_with("even at the last compaction level \\compactlevel=3"));
+    }
+
+    #[test]
+    fn test_fit_without_compaction_or_overflow_passes_errors_through() {
+        let td = TempDir::new().unwrap();
+
+        assert_eq!(fit_page_contract(td.path(), None, || Ok(1)).unwrap(), None);
+        let err = fit_page_contract(td.path(), None, || Err(overflow(3))).unwrap_err();
+        assert!(err.downcast_ref::<PageOverflow>().is_some());
+        let err =
+            fit_page_contract(td.path(), Some(&compaction()), || Err("boom".into())).unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+        assert!(!td.path().join(APPLICATION_TEX).exists());
+    }
+}
@@ -0,0 +1,244 @@
+//! Company names and job titles made safe as path components.
+//!
+//! A job title such as "DevOps/SRE (m/f/d)" or a company such as
+//! "Café: Zürich" ends up in directory and file names (see
+//! `destination_layout`). `slugify` turns any such value into one portable
+//! path component, and `unique_file_path` / `unique_dir_path` pick a fresh
+//! name when the target already exists, instead of overwriting it.
+
+use std::path::{Path, PathBuf};
+
+/// The longest slug, in bytes: three of them plus a date still fit the
+/// 255-byte file name limit of common filesystems.
+pub const MAX_SLUG_LEN: usize = 60;
+
+/// The slug of a value with nothing usable in it.
+const EMPTY_SLUG: &str = "unnamed";
+
+/// Names Windows reserves for devices, whatever the extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// `value` as a single path component: Latin letters transliterated to ASCII,
+/// other letters and digits kept, `&` spelled `and`, and every run of
+/// separators, reserved or control characters turned into one `-`. The
+/// result keeps the case of `value`, never starts or ends with `-` or `.`,
+/// is at most `MAX_SLUG_LEN` bytes and is never empty.
+pub fn slugify(value: &str) -> String {
+    let mut slug = String::with_capacity(value.len());
+    for c in value.chars() {
+        if let Some(ascii) = transliterate(c) {
+            slug.push_str(ascii);
+        } else if c.is_alphanumeric() || matches!(c, '.' | '_' | '+') {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let mut slug = trim_edges(&slug).to_string();
+    if slug.len() > MAX_SLUG_LEN {
+        let mut end = MAX_SLUG_LEN;
+        while !slug.is_char_boundary(end) {
+            end -= 1;
+        }
+        slug = trim_edges(&slug[..end]).to_string();
+    }
+    if slug.is_empty() {
+        return EMPTY_SLUG.to_string();
+    }
+    // `CON_.txt`, not `CON.txt_`: the stem is what Windows checks, and a
+    // slug must come out of `slugify` unchanged.
+    let stem_len = slug.find('.').unwrap_or(slug.len());
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(&slug[..stem_len]))
+    {
+        if slug.len() == MAX_SLUG_LEN {
+            slug.pop();
+            slug.truncate(slug.trim_end_matches(['-', '.']).len());
+        }
+        slug.insert(stem_len, '_');
+    }
+    slug
+}
+
+/// `path`, or when a file already exists there, the first free
+/// `<stem>-2.<ext>`, `<stem>-3.<ext>`, ... next to it. `companion` names the
+/// file that goes with a candidate (a CV's cover letter): a candidate whose
+/// companion exists is taken too, so the pair is numbered together.
+pub fn unique_file_path(path: &Path, companion: impl Fn(&Path) -> Option<PathBuf>) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    first_free(
+        path,
+        |n| format!("{stem}-{n}{extension}"),
+        |candidate| candidate.exists() || companion(candidate).is_some_and(|other| other.exists()),
+    )
+}
+
+/// `path`, or when it already exists, the first free `<name>-2`, `<name>-3`,
+/// ... next to it. Unlike `unique_file_path`, a dot in the name is no
+/// extension.
+pub fn unique_dir_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    first_free(path, |n| format!("{name}-{n}"), Path::exists)
+}
+
+fn first_free(
+    path: &Path,
+    numbered: impl Fn(u32) -> String,
+    taken: impl Fn(&Path) -> bool,
+) -> PathBuf {
+    if !taken(path) {
+        return path.to_path_buf();
+    }
+    (2..)
+        .map(|n| path.with_file_name(numbered(n)))
+        .find(|candidate| !taken(candidate))
+        .expect("some numbered name is free")
+}
+
+fn trim_edges(slug: &str) -> &str {
+    slug.trim_matches(|c| c == '-' || c == '.')
+}
+
+/// The ASCII spelling of a Latin letter with diacritics (and of `&`).
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        '&' => "and",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'ç' | 'ć' | 'č' => "c",
+        'Ð' | 'Ď' | 'Đ' => "D",
+        'ð' | 'ď' | 'đ' => "d",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'Ğ' => "G",
+        'ğ' => "g",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'İ' => "I",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'Ł' | 'Ľ' => "L",
+        'ł' | 'ľ' => "l",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ő' => "O",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ř' => "R",
+        'ř' => "r",
+        'Ś' | 'Ş' | 'Š' | 'Ș' => "S",
+        'ś' | 'ş' | 'š' | 'ș' => "s",
+        'ß' => "ss",
+        'Ţ' | 'Ť' | 'Ț' => "T",
+        'ţ' | 'ť' | 'ț' => "t",
+        'Þ' => "Th",
+        'þ' => "th",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ů' | 'Ű' => "U",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'Ý' | 'Ÿ' => "Y",
+        'ý' | 'ÿ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_slugify_examples() {
+        assert_eq!(slugify("Senior DevOps Engineer"), "Senior-DevOps-Engineer");
+        assert_eq!(slugify("DevOps/SRE (m/f/d)"), "DevOps-SRE-m-f-d");
+        assert_eq!(slugify("Café: Zürich"), "Cafe-Zurich");
+        assert_eq!(slugify("Smith & Sons"), "Smith-and-Sons");
+        assert_eq!(slugify("Łódź Straße"), "Lodz-Strasse");
+        assert_eq!(slugify("C++ dev"), "C++-dev");
+        assert_eq!(slugify("東京 Inc."), "東京-Inc");
+        assert_eq!(slugify("..\\evil"), "evil");
+        assert_eq!(slugify(" \t\n"), "unnamed");
+        assert_eq!(slugify("con"), "con_");
+        assert_eq!(slugify("Lpt1.txt"), "Lpt1_.txt");
+    }
+
+    #[test]
+    fn test_slugify_caps_the_length_at_a_char_boundary() {
+        let slug = slugify(&"ü東".repeat(40));
+        assert!(slug.len() <= MAX_SLUG_LEN);
+        assert!(slug.starts_with("u東u東"));
+    }
+
+    #[test]
+    fn test_unique_file_path_numbers_taken_names() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+        assert_eq!(unique_file_path(&pdf, |_| None), pdf);
+
+        fs::write(&pdf, b"x").unwrap();
+        fs::write(td.path().join("cv-2.pdf"), b"x").unwrap();
+
+        assert_eq!(unique_file_path(&pdf, |_| None), td.path().join("cv-3.pdf"));
+    }
+
+    #[test]
+    fn test_unique_file_path_skips_a_name_whose_companion_is_taken() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+        fs::write(td.path().join("cv-notes.txt"), b"x").unwrap();
+        let notes = |candidate: &Path| {
+            let stem = candidate.file_stem().unwrap().to_string_lossy();
+            Some(candidate.with_file_name(format!("{stem}-notes.txt")))
+        };
+
+        assert_eq!(unique_file_path(&pdf, notes), td.path().join("cv-2.pdf"));
+    }
+
+    #[test]
+    fn test_unique_dir_path_keeps_dots_in_the_name() {
+        let td = TempDir::new().unwrap();
+        let dir = td.path().join("2026-10-18_Acme.io_SRE");
+        fs::create_dir(&dir).unwrap();
+
+        assert_eq!(
+            unique_dir_path(&dir),
+            td.path().join("2026-10-18_Acme.io_SRE-2")
+        );
+    }
+
+    /// Any input, however hostile, becomes one short, non-empty path
+    /// component, and slugging a slug changes nothing.
+    #[test]
+    fn test_slugify_is_a_single_safe_component() {
+        use proptest::prelude::*;
+        proptest!(|(value in ".*")| {
+            let slug = slugify(&value);
+            prop_assert!(!slug.is_empty());
+            prop_assert!(slug.len() <= MAX_SLUG_LEN);
+            let unsafe_char =
+                |c: char| c.is_control() || c.is_whitespace() || "/\\<>:\"|?*".contains(c);
+            prop_assert!(!slug.chars().any(unsafe_char), "unsafe character in {}", slug);
+            prop_assert!(!slug.starts_with(['-', '.']) && !slug.ends_with(['-', '.']));
+            prop_assert_eq!(Path::new(&slug).components().count(), 1);
+            prop_assert_eq!(slugify(&slug), slug);
+        });
+    }
+}
@@ -0,0 +1,277 @@
+//! Document metadata of the published PDFs.
+//!
+//! TeX engines leave their own defaults in a PDF's Info dictionary (an empty
+//! title, "xdvipdfmx" as the producer, ...), which is what an applicant
+//! tracking system or a file manager shows for the CV. Before a PDF leaves the
+//! working directory its Info dictionary and XMP packet are rewritten with the
+//! author, `<job title> — <company>` as the title, the variant as keywords and
+//! the application date.
+
+use crate::config_parse::get_variable_from_config_file;
+use crate::global_conf::AppContext;
+use chrono::{DateTime, Local};
+use lopdf::{Dictionary, Document, Object, Stream, dictionary, text_string};
+use std::path::Path;
+
+/// The program named as the creator of the stamped PDFs.
+const CREATOR: &str = "rusty_cv_creator";
+
+/// What a published PDF says about itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfMetadata {
+    pub author: String,
+    pub title: String,
+    pub keywords: String,
+    pub creation_date: DateTime<Local>,
+}
+
+impl PdfMetadata {
+    /// The metadata of the CV built from `variant` for `job_title` at
+    /// `company`, created on `today`.
+    pub fn for_application(
+        author: &str,
+        job_title: &str,
+        company: &str,
+        variant: &str,
+        today: &DateTime<Local>,
+    ) -> Self {
+        Self {
+            author: author.trim().to_string(),
+            title: format!("{} — {}", job_title.trim(), company.trim()),
+            keywords: variant.to_string(),
+            creation_date: *today,
+        }
+    }
+
+    /// The metadata `insert` stamps on the PDFs built from `variant`: the
+    /// author from the config (see [`pdf_author`]) and today's date. Shared by
+    /// the real build and its `--dry-run` plan.
+    pub fn from_context(
+        ctx: &AppContext,
+        prefix: &str,
+        job_title: &str,
+        company: &str,
+        variant: &str,
+    ) -> Self {
+        let author = get_variable_from_config_file(ctx, "cv", "author").ok();
+        Self::for_application(
+            &pdf_author(author.as_deref(), prefix),
+            job_title,
+            company,
+            variant,
+            ctx.get_today(),
+        )
+    }
+}
+
+/// The author to stamp: `[cv] author` when set, otherwise `cv_file_prefix`
+/// split into words (`PivaFrancesco` → `Piva Francesco`).
+pub fn pdf_author(configured: Option<&str>, prefix: &str) -> String {
+    if let Some(author) = configured.map(str::trim).filter(|a| !a.is_empty()) {
+        return author.to_string();
+    }
+    let mut author = String::with_capacity(prefix.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in prefix.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            author.push(' ');
+        } else {
+            if c.is_uppercase() && previous.is_some_and(char::is_lowercase) {
+                author.push(' ');
+            }
+            author.push(c);
+        }
+        previous = Some(c);
+    }
+    author.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Rewrite the Info dictionary and the XMP metadata of the PDF at `path` in
+/// place.
+pub fn stamp_pdf_metadata(
+    path: &Path,
+    metadata: &PdfMetadata,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut document = Document::load(path)
+        .map_err(|e| format!("Could not read the PDF {}: {e}", path.display()))?;
+
+    let date = pdf_date(&metadata.creation_date);
+    let entries = [
+        ("Title", text_string(&metadata.title)),
+        ("Author", text_string(&metadata.author)),
+        ("Keywords", text_string(&metadata.keywords)),
+        ("Creator", text_string(CREATOR)),
+        ("CreationDate", Object::string_literal(date.as_str())),
+        ("ModDate", Object::string_literal(date.as_str())),
+    ];
+    let info = info_dictionary(&mut document)?;
+    for (key, value) in entries {
+        info.set(key, value);
+    }
+
+    let xmp = Stream::new(
+        dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+        xmp_packet(metadata).into_bytes(),
+    );
+    let xmp_id = document.add_object(xmp);
+    document.catalog_mut()?.set("Metadata", xmp_id);
+
+    document
+        .save(path)
+        .map_err(|e| format!("Could not write the PDF {}: {e}", path.display()))?;
+    Ok(())
+}
+
+/// The document's Info dictionary, created when the PDF has none.
+fn info_dictionary(document: &mut Document) -> Result<&mut Dictionary, lopdf::Error> {
+    let info_id = match document.trailer.get(b"Info").and_then(Object::as_reference) {
+        Ok(id) => id,
+        Err(_) => {
+            let id = document.add_object(Dictionary::new());
+            document.trailer.set("Info", id);
+            id
+        }
+    };
+    document.get_dictionary_mut(info_id)
+}
+
+/// `date` as a PDF date string, `D:YYYYMMDDHHmmSS+HH'mm'`.
+fn pdf_date(date: &DateTime<Local>) -> String {
+    let offset = date.offset().local_minus_utc();
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.abs() / 60;
+    format!(
+        "D:{}{sign}{:02}'{:02}'",
+        date.format("%Y%m%d%H%M%S"),
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// The XMP packet carrying the same metadata as the Info dictionary: PDF/A
+/// validators and some readers only look at this one.
+fn xmp_packet(metadata: &PdfMetadata) -> String {
+    let date = metadata.creation_date.to_rfc3339();
+    format!(
+        r#"<?xpacket begin="{bom}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:pdf="http://ns.adobe.com/pdf/1.3/"
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">{title}</rdf:li></rdf:Alt></dc:title>
+   <dc:creator><rdf:Seq><rdf:li>{author}</rdf:li></rdf:Seq></dc:creator>
+   <dc:subject><rdf:Bag><rdf:li>{keywords}</rdf:li></rdf:Bag></dc:subject>
+   <pdf:Keywords>{keywords}</pdf:Keywords>
+   <xmp:CreatorTool>{CREATOR}</xmp:CreatorTool>
+   <xmp:CreateDate>{date}</xmp:CreateDate>
+   <xmp:ModifyDate>{date}</xmp:ModifyDate>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#,
+        bom = '\u{feff}',
+        title = escape_xml(&metadata.title),
+        author = escape_xml(&metadata.author),
+        keywords = escape_xml(&metadata.keywords),
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use lopdf::decode_text_string;
+    use tempfile::TempDir;
+
+    fn metadata() -> PdfMetadata {
+        PdfMetadata::for_application(
+            "Piva Francesco",
+            "SRE ",
+            "Smith & Sons",
+            "senior-sre",
+            &Local.with_ymd_and_hms(2026, 10, 18, 9, 30, 0).unwrap(),
+        )
+    }
+
+    fn info_entry(document: &Document, key: &str) -> String {
+        let info_id = document
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let info = document.get_dictionary(info_id).unwrap();
+        decode_text_string(info.get(key.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_pdf_author_prefers_the_config_then_splits_the_prefix() {
+        assert_eq!(pdf_author(Some(" Jane Doe "), "PivaFrancesco"), "Jane Doe");
+        assert_eq!(pdf_author(None, "PivaFrancesco"), "Piva Francesco");
+        assert_eq!(pdf_author(Some(""), "jane_doe-CV"), "jane doe CV");
+    }
+
+    #[test]
+    fn test_stamp_writes_the_info_dictionary_and_xmp() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+        crate::pdf_pages::write_test_pdf(&pdf, 2);
+
+        stamp_pdf_metadata(&pdf, &metadata()).unwrap();
+
+        let document = Document::load(&pdf).unwrap();
+        assert_eq!(info_entry(&document, "Title"), "SRE — Smith & Sons");
+        assert_eq!(info_entry(&document, "Author"), "Piva Francesco");
+        assert_eq!(info_entry(&document, "Keywords"), "senior-sre");
+        assert!(info_entry(&document, "CreationDate").starts_with("D:20261018093000"));
+
+        let xmp_id = document
+            .catalog()
+            .unwrap()
+            .get(b"Metadata")
+            .and_then(Object::as_reference)
+            .unwrap();
+        let xmp = document.get_object(xmp_id).unwrap().as_stream().unwrap();
+        let xmp = String::from_utf8(xmp.content.clone()).unwrap();
+        assert!(xmp.contains(r#"<rdf:li xml:lang="x-default">SRE — Smith &amp; Sons</rdf:li>"#));
+        assert!(xmp.contains("<xmp:CreateDate>2026-10-18T09:30:00"));
+        assert_eq!(crate::pdf_pages::pdf_page_count(&pdf).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_stamp_replaces_an_existing_info_dictionary() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+        crate::pdf_pages::write_test_pdf(&pdf, 1);
+        let mut document = Document::load(&pdf).unwrap();
+        let info_id = document.add_object(dictionary! {
+            "Title" => Object::string_literal("cv"),
+            "Producer" => Object::string_literal("xdvipdfmx"),
+        });
+        document.trailer.set("Info", info_id);
+        document.save(&pdf).unwrap();
+
+        stamp_pdf_metadata(&pdf, &metadata()).unwrap();
+
+        let document = Document::load(&pdf).unwrap();
+        assert_eq!(info_entry(&document, "Title"), "SRE — Smith & Sons");
+        assert_eq!(info_entry(&document, "Producer"), "xdvipdfmx");
+    }
+
+    #[test]
+    fn test_stamp_rejects_a_file_that_is_not_a_pdf() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+        std::fs::write(&pdf, b"%PDF-1.4").unwrap();
+
+        assert!(stamp_pdf_metadata(&pdf, &metadata()).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Page count of a built PDF, read from the file itself.
+//!
+//! The TeX transcript only carries an `Output written on ... (N pages` line
+//! when the builder is tectonic (or xelatex) and its output reaches the
+//! captured streams. Counting the pages of `<prefix>-<variant>.pdf` works for
+//! any builder — latexmk, typst or a custom command — so it is the
+//! authoritative source, and the transcript is only the fallback.
+
+use lopdf::Document;
+use lopdf::content::Operation;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// The number of pages of the PDF at `path`, from its page tree.
+pub fn pdf_page_count(path: &Path) -> Result<u32, Box<dyn std::error::Error>> {
+    let document = Document::load(path)
+        .map_err(|e| format!("Could not read the PDF {}: {e}", path.display()))?;
+    match document.get_pages().len() {
+        0 => Err(format!("The PDF {} has no pages", path.display()).into()),
+        pages => Ok(u32::try_from(pages)?),
+    }
+}
+
+/// Roughly how many lines of text the last page of the PDF at `path` holds:
+/// the distinct baselines its text is shown at. Used to tell how far a CV
+/// overflows onto its last page.
+pub fn last_page_text_lines(path: &Path) -> Result<u32, Box<dyn std::error::Error>> {
+    let document = Document::load(path)
+        .map_err(|e| format!("Could not read the PDF {}: {e}", path.display()))?;
+    let (_, &page_id) = document
+        .get_pages()
+        .iter()
+        .next_back()
+        .ok_or_else(|| format!("The PDF {} has no pages", path.display()))?;
+    let content = document.get_and_decode_page_content(page_id)?;
+
+    let operand = |operation: &Operation, index: usize| {
+        operation
+            .operands
+            .get(index)
+            .and_then(|object| object.as_float().ok())
+            .unwrap_or(0.0)
+    };
+    let (mut baseline, mut leading) = (0.0_f32, 0.0_f32);
+    let mut baselines = BTreeSet::new();
+    for operation in &content.operations {
+        match operation.operator.as_str() {
+            "BT" => baseline = 0.0,
+            "Tm" => baseline = operand(operation, 5),
+            "Td" => baseline += operand(operation, 1),
+            "TD" => {
+                leading = -operand(operation, 1);
+                baseline += operand(operation, 1);
+            }
+            "TL" => leading = operand(operation, 0),
+            "T*" => baseline -= leading,
+            "'" | "\"" => {
+                baseline -= leading;
+                baselines.insert(baseline.round() as i64);
+            }
+            "Tj" | "TJ" => {
+                baselines.insert(baseline.round() as i64);
+            }
+            _ => {}
+        }
+    }
+    Ok(u32::try_from(baselines.len())?)
+}
+
+/// Write a minimal, valid PDF with `pages` empty pages to `path`.
+#[cfg(test)]
+pub fn write_test_pdf(path: &Path, pages: u32) {
+    write_test_pdf_with_lines(path, pages, 0);
+}
+
+/// Write a PDF with `pages` pages, the last one holding `last_page_lines`
+/// lines of text, to `path`.
+#[cfg(test)]
+pub fn write_test_pdf_with_lines(path: &Path, pages: u32, last_page_lines: u32) {
+    use lopdf::content::Content;
+    use lopdf::{Object, Stream, dictionary};
+
+    let mut operations = vec![Operation::new("BT", vec![])];
+    for line in 0..last_page_lines {
+        let step = if line == 0 { 800 } else { -12 };
+        operations.push(Operation::new("Td", vec![0.into(), step.into()]));
+        operations.push(Operation::new(
+            "Tj",
+            vec![Object::string_literal(format!("line {line}"))],
+        ));
+    }
+    operations.push(Operation::new("ET", vec![]));
+    let content = Content { operations }.encode().unwrap();
+
+    let mut document = Document::with_version("1.5");
+    let pages_id = document.new_object_id();
+    let content_id = document.add_object(Stream::new(dictionary! {}, content));
+    let kids: Vec<Object> = (0..pages)
+        .map(|page| {
+            let mut page_dictionary = dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            };
+            if page + 1 == pages {
+                page_dictionary.set("Contents", content_id);
+            }
+            document.add_object(page_dictionary).into()
+        })
+        .collect();
+    document.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => pages,
+        }),
+    );
+    let catalog_id = document.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    document.trailer.set("Root", catalog_id);
+    document.save(path).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pdf_page_count_reads_the_page_tree() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+
+        write_test_pdf(&pdf, 3);
+
+        assert_eq!(pdf_page_count(&pdf).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_last_page_text_lines_counts_the_baselines() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+
+        write_test_pdf_with_lines(&pdf, 3, 7);
+        assert_eq!(last_page_text_lines(&pdf).unwrap(), 7);
+
+        write_test_pdf(&pdf, 2);
+        assert_eq!(last_page_text_lines(&pdf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_pdf_page_count_rejects_unreadable_files() {
+        let td = TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+        fs::write(&pdf, b"%PDF-1.4 truncated").unwrap();
+
+        assert!(pdf_page_count(&pdf).is_err());
+        assert!(pdf_page_count(&td.path().join("missing.pdf")).is_err());
+
+        write_test_pdf(&pdf, 0);
+        assert!(
+            pdf_page_count(&pdf)
+                .unwrap_err()
+                .to_string()
+                .contains("no pages")
+        );
+    }
+}
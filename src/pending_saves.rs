@@ -0,0 +1,433 @@
+//! The journal of applications that could not be saved to the database.
+//!
+//! When `insert --save-to-database` builds a CV but cannot store its row (the
+//! database is down and there is no local store to fall back to, or that
+//! failed too), the row is appended to a JSON Lines file instead of being
+//! dropped: one application per line, with the PDF path, when and why the
+//! save failed. The journal is replayed by `db flush-pending`, and
+//! automatically whenever `connect_db` succeeds; each application saved is
+//! removed from it, and the file itself once it is empty. Appends and
+//! replays take an exclusive lock on `<journal>.lock` first, so an
+//! application queued by one command while another replays the journal is
+//! never lost when the journal is rewritten.
+//!
+//! It lives at `[db] pending_saves`, else `<db_path>/pending-saves.jsonl`,
+//! else under the user data directory.
+
+use crate::config_parse::get_variable_from_config_file;
+use crate::global_conf::AppContext;
+use crate::helpers::{clean_string_from_quotes, fix_home_directory_path};
+use log::warn;
+use rusty_cv_creator::database::{DbConnection, modified_now, save_application};
+use rusty_cv_creator::models::NewCv;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const JOURNAL_FILE: &str = "pending-saves.jsonl";
+
+/// An application waiting in the journal: the `NewCv` that could not be
+/// saved, owned, plus when and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSave {
+    pub uuid: String,
+    pub application_date: Option<String>,
+    pub job_title: String,
+    pub company: String,
+    pub quote: String,
+    pub pdf_cv_path: String,
+    pub variant: Option<String>,
+    pub template_ref: Option<String>,
+    pub cover_letter_path: Option<String>,
+    pub compaction_level: Option<String>,
+    /// When the save failed, RFC 3339 in UTC.
+    pub failed_at: String,
+    /// The error of the failed save.
+    pub error: String,
+}
+
+impl PendingSave {
+    pub fn new(new_cv: &NewCv, error: &str) -> Self {
+        Self {
+            uuid: new_cv.uuid.to_string(),
+            application_date: new_cv.application_date.map(str::to_string),
+            job_title: new_cv.job_title.to_string(),
+            company: new_cv.company.to_string(),
+            quote: new_cv.quote.to_string(),
+            pdf_cv_path: new_cv.pdf_cv_path.to_string(),
+            variant: new_cv.variant.map(str::to_string),
+            template_ref: new_cv.template_ref.map(str::to_string),
+            cover_letter_path: new_cv.cover_letter_path.map(str::to_string),
+            compaction_level: new_cv.compaction_level.map(str::to_string),
+            failed_at: modified_now(),
+            error: error.to_string(),
+        }
+    }
+
+    fn as_new_cv(&self) -> NewCv<'_> {
+        NewCv {
+            application_date: self.application_date.as_deref(),
+            job_title: &self.job_title,
+            company: &self.company,
+            quote: &self.quote,
+            pdf_cv_path: &self.pdf_cv_path,
+            generated: true,
+            variant: self.variant.as_deref(),
+            template_ref: self.template_ref.as_deref(),
+            cover_letter_path: self.cover_letter_path.as_deref(),
+            compaction_level: self.compaction_level.as_deref(),
+            uuid: &self.uuid,
+        }
+    }
+}
+
+/// Where the journal lives (see the module documentation).
+pub fn pending_saves_path(ctx: &AppContext) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Ok(path) = get_variable_from_config_file(ctx, "db", "pending_saves") {
+        return Ok(PathBuf::from(fix_home_directory_path(
+            &clean_string_from_quotes(&path),
+        )));
+    }
+    if let Ok(db_path) = get_variable_from_config_file(ctx, "db", "db_path") {
+        let dir = fix_home_directory_path(&clean_string_from_quotes(&db_path));
+        return Ok(Path::new(&dir).join(JOURNAL_FILE));
+    }
+    let data_dir = dirs::data_dir()
+        .ok_or("No place for the pending saves journal: set [db] pending_saves or db_path")?;
+    Ok(data_dir.join("rusty-cv-creator").join(JOURNAL_FILE))
+}
+
+/// Append `pending` to the journal at `path`, creating it (and its directory).
+pub fn queue_save(path: &Path, pending: &PendingSave) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let _lock = lock_journal(path)?;
+    let mut journal = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(journal, "{}", serde_json::to_string(pending)?)?;
+    Ok(())
+}
+
+/// The applications in the journal at `path`, oldest first; none when there
+/// is no journal. A line that does not parse is an error rather than skipped:
+/// the journal is the only copy of those applications.
+pub fn read_pending(path: &Path) -> Result<Vec<PendingSave>, Box<dyn std::error::Error>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Could not read {}: {e}", path.display()).into()),
+    };
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                format!(
+                    "{} line {}: not a pending save: {e}",
+                    path.display(),
+                    index + 1
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// The outcome of `flush_pending`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Each application saved and the id of its row.
+    pub saved: Vec<(String, i32)>,
+    /// Each application matching a stored one (same job title, company and
+    /// quote), and the id of that row.
+    pub already_stored: Vec<(String, i32)>,
+    /// Each application that could not be saved again, with the error; they
+    /// stay in the journal.
+    pub failed: Vec<(String, String)>,
+    pub dry_run: bool,
+}
+
+impl FlushReport {
+    fn is_empty(&self) -> bool {
+        self.saved.is_empty() && self.already_stored.is_empty() && self.failed.is_empty()
+    }
+}
+
+impl fmt::Display for FlushReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dry_run {
+            let count = self.saved.len();
+            writeln!(f, "Dry run: {count} pending save(s) would be retried")?;
+            for (application, _) in &self.saved {
+                writeln!(f, "  {application}")?;
+            }
+            return Ok(());
+        }
+        if self.is_empty() {
+            return writeln!(f, "No pending saves");
+        }
+        for (application, id) in &self.saved {
+            writeln!(f, "Saved {application} as application {id}")?;
+        }
+        for (application, id) in &self.already_stored {
+            writeln!(f, "{application} is already stored as application {id}")?;
+        }
+        for (application, error) in &self.failed {
+            writeln!(f, "Still pending: {application}: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Save every application of the journal at `path` through `conn`, then
+/// rewrite the journal with those that failed again (removing it when none
+/// did). The journal stays locked throughout. With `dry_run`, only list them.
+pub fn flush_pending(
+    conn: &mut DbConnection,
+    path: &Path,
+    dry_run: bool,
+) -> Result<FlushReport, Box<dyn std::error::Error>> {
+    let mut report = FlushReport {
+        dry_run,
+        ..FlushReport::default()
+    };
+    if dry_run {
+        report.saved = read_pending(path)?
+            .iter()
+            .map(|save| (describe(save), 0))
+            .collect();
+        return Ok(report);
+    }
+    if !path.exists() {
+        return Ok(report);
+    }
+    let _lock = lock_journal(path)?;
+    let pending = read_pending(path)?;
+    if pending.is_empty() {
+        return Ok(report);
+    }
+
+    let mut remaining = Vec::new();
+    for save in pending {
+        match save_application(conn, &save.as_new_cv()) {
+            Ok(cv) if cv.uuid == save.uuid => report.saved.push((describe(&save), cv.id)),
+            Ok(cv) => report.already_stored.push((describe(&save), cv.id)),
+            Err(e) => {
+                report.failed.push((describe(&save), e.to_string()));
+                remaining.push(save);
+            }
+        }
+    }
+    rewrite_journal(path, &remaining)?;
+    Ok(report)
+}
+
+/// Retry the journal on a fresh connection (`connect_db`). Failures are only
+/// warned about: the connection is still good for what it was opened for.
+pub fn retry_pending_saves(ctx: &AppContext, conn: &mut DbConnection) {
+    if ctx.is_dry_run() {
+        return;
+    }
+    let Ok(path) = pending_saves_path(ctx) else {
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+    match flush_pending(conn, &path, false) {
+        Ok(report) if !report.is_empty() => eprint!("Retried the pending saves:\n{report}"),
+        Ok(_) => {}
+        Err(e) => warn!(
+            "Could not retry the pending saves in {}: {e}",
+            path.display()
+        ),
+    }
+}
+
+fn describe(save: &PendingSave) -> String {
+    format!(
+        "'{}' at '{}' ({})",
+        save.job_title, save.company, save.pdf_cv_path
+    )
+}
+
+/// An exclusive lock on the journal at `path`, released when the returned file
+/// is dropped. It is taken on a separate file, which stays in place, so the
+/// journal itself can be replaced and removed while it is held.
+fn lock_journal(path: &Path) -> std::io::Result<fs::File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+/// Replace the journal with `remaining`, through a temporary file so a crash
+/// cannot truncate it; remove it when nothing remains.
+fn rewrite_journal(
+    path: &Path,
+    remaining: &[PendingSave],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if remaining.is_empty() {
+        fs::remove_file(path)?;
+        return Ok(());
+    }
+    let mut content = String::new();
+    for save in remaining {
+        content.push_str(&serde_json::to_string(save)?);
+        content.push('\n');
+    }
+    let temporary = path.with_extension("jsonl.tmp");
+    fs::write(&temporary, content)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::prelude::*;
+    use rusty_cv_creator::database::save_new_cv_to_db;
+    use rusty_cv_creator::migrations::migrated_in_memory_sqlite;
+    use tempfile::TempDir;
+
+    fn new_cv<'a>(job_title: &'a str, uuid: &'a str) -> NewCv<'a> {
+        NewCv {
+            application_date: Some("2026-10-18"),
+            job_title,
+            company: "ACME",
+            quote: "",
+            pdf_cv_path: "/out/cv.pdf",
+            generated: true,
+            variant: Some("senior-sre"),
+            template_ref: None,
+            cover_letter_path: None,
+            compaction_level: Some("1"),
+            uuid,
+        }
+    }
+
+    #[test]
+    fn test_queued_saves_read_back_in_order() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join("nested").join(JOURNAL_FILE);
+        assert!(read_pending(&path).unwrap().is_empty());
+
+        let first = PendingSave::new(&new_cv("SRE", "uuid-1"), "connection refused");
+        let second = PendingSave::new(&new_cv("Dev", "uuid-2"), "timeout");
+        queue_save(&path, &first).unwrap();
+        queue_save(&path, &second).unwrap();
+
+        assert_eq!(read_pending(&path).unwrap(), [first, second]);
+    }
+
+    #[test]
+    fn test_a_corrupt_journal_line_is_an_error() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join(JOURNAL_FILE);
+        fs::write(&path, "{\"job_title\": \"SRE\"\n").unwrap();
+
+        let error = read_pending(&path).unwrap_err().to_string();
+
+        assert!(error.contains("line 1: not a pending save"), "{error}");
+    }
+
+    #[test]
+    fn test_flush_saves_every_pending_application_and_removes_the_journal() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join(JOURNAL_FILE);
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let stored =
+            save_new_cv_to_db(&mut conn, "/out/old.pdf", "Dev", "ACME", None, "2026").unwrap();
+        queue_save(&path, &PendingSave::new(&new_cv("SRE", "uuid-1"), "down")).unwrap();
+        queue_save(&path, &PendingSave::new(&new_cv("Dev", "uuid-2"), "down")).unwrap();
+
+        let report = flush_pending(&mut conn, &path, false).unwrap();
+
+        assert_eq!(report.saved.len(), 1);
+        assert_eq!(report.already_stored[0].1, stored.id);
+        assert!(report.failed.is_empty());
+        assert!(!path.exists());
+        let saved =
+            rusty_cv_creator::database::find_cv_by_id(&mut conn, report.saved[0].1).unwrap();
+        assert_eq!(
+            (saved.uuid.as_str(), saved.compaction_level.as_deref()),
+            ("uuid-1", Some("1"))
+        );
+        assert_eq!(
+            flush_pending(&mut conn, &path, false).unwrap().to_string(),
+            "No pending saves\n"
+        );
+    }
+
+    #[test]
+    fn test_failed_retries_stay_in_the_journal() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join(JOURNAL_FILE);
+        // No schema: every save fails.
+        let mut conn = DbConnection::Sqlite(SqliteConnection::establish(":memory:").unwrap());
+        let pending = PendingSave::new(&new_cv("SRE", "uuid-1"), "down");
+        queue_save(&path, &pending).unwrap();
+
+        let report = flush_pending(&mut conn, &path, false).unwrap();
+
+        assert_eq!(report.failed.len(), 1);
+        assert!(
+            report
+                .to_string()
+                .starts_with("Still pending: 'SRE' at 'ACME'")
+        );
+        assert_eq!(read_pending(&path).unwrap(), [pending]);
+    }
+
+    #[test]
+    fn test_an_append_waits_for_a_flush_in_progress() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join(JOURNAL_FILE);
+        queue_save(&path, &PendingSave::new(&new_cv("SRE", "uuid-1"), "down")).unwrap();
+        let flush_lock = lock_journal(&path).unwrap();
+
+        let appending = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                queue_save(&path, &PendingSave::new(&new_cv("Dev", "uuid-2"), "down")).unwrap();
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        // What a flush that saved everything does while holding the lock.
+        assert_eq!(read_pending(&path).unwrap().len(), 1);
+        rewrite_journal(&path, &[]).unwrap();
+        drop(flush_lock);
+        appending.join().unwrap();
+
+        let left = read_pending(&path).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].uuid, "uuid-2");
+    }
+
+    #[test]
+    fn test_dry_run_flush_writes_nothing() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join(JOURNAL_FILE);
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        queue_save(&path, &PendingSave::new(&new_cv("SRE", "uuid-1"), "down")).unwrap();
+
+        let report = flush_pending(&mut conn, &path, true).unwrap();
+
+        assert_eq!(
+            report.to_string(),
+            "Dry run: 1 pending save(s) would be retried\n  'SRE' at 'ACME' (/out/cv.pdf)\n"
+        );
+        assert_eq!(read_pending(&path).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,400 @@
+//! `preview`: iterate on the LaTeX template without `insert`.
+//!
+//! The template is copied into a scratch directory (no dated directory, no
+//! database row), one variant is built there and its PDF is kept at a stable
+//! path, which the configured `pdf_viewer` opens once. The template is then
+//! polled for changes and rebuilt on every one of them; each rebuild is held
+//! to the page contract and a failed one prints its TeX errors.
+
+use crate::application_tex::{ApplicationDetails, write_application_tex};
+use crate::cli_structure::PreviewArgs;
+use crate::command_runner::{CommandRunner, SystemRunner};
+use crate::config_parse::get_variable_from_config_file;
+use crate::file_handlers::{
+    BuildConfig, enforce_page_contract, invoke_builder, select_variant, template_settings,
+};
+use crate::global_conf::AppContext;
+use crate::helpers::{ensure_tools_available, view_cv_file};
+use crate::template_source::resolve_template_for_config;
+use crate::tex_diagnostics::BuildFailure;
+use log::{info, warn};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// One file of the template, as far as change detection is concerned.
+type FileStamp = (PathBuf, Option<SystemTime>, u64);
+
+/// A variant of one template, built over and over into `scratch_dir`.
+#[derive(Debug, Clone)]
+pub struct PreviewSession {
+    pub template_dir: PathBuf,
+    pub scratch_dir: PathBuf,
+    pub variant: String,
+    pub cfg: BuildConfig,
+    pub job_title: String,
+    pub company: String,
+    pub quote: String,
+    pub application_date: String,
+}
+
+/// How one rebuild went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviewBuild {
+    /// Built within the page contract.
+    Built { pdf: PathBuf, pages: u32 },
+    /// Built, but the PDF breaks the page contract (it is still shown).
+    ContractBroken { pdf: PathBuf, reason: String },
+    /// The builder failed; what its transcript and log say.
+    BuildFailed(BuildFailure),
+    /// The build could not run at all.
+    Failed(String),
+}
+
+impl PreviewBuild {
+    fn pdf(&self) -> Option<&Path> {
+        match self {
+            PreviewBuild::Built { pdf, .. } | PreviewBuild::ContractBroken { pdf, .. } => Some(pdf),
+            PreviewBuild::BuildFailed(_) | PreviewBuild::Failed(_) => None,
+        }
+    }
+}
+
+pub fn run_preview(
+    ctx: &AppContext,
+    args: &PreviewArgs,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let cfg = BuildConfig::from_context(ctx)?;
+    ensure_tools_available(&cfg.builder.required_tools())?;
+
+    let settings = template_settings(ctx)?;
+    let template_dir = PathBuf::from(resolve_template_for_config(
+        &settings.path,
+        &settings.cache_dir,
+        settings.git_ref.as_deref(),
+        settings.auth,
+        &SystemRunner,
+    )?);
+    let variant = select_variant(
+        ctx,
+        Some(&template_dir),
+        &cfg.prefix,
+        args.variant.as_ref(),
+        &args.job_title,
+    )?
+    .variant;
+
+    let session = PreviewSession {
+        scratch_dir: std::env::temp_dir().join(format!("rusty-cv-preview-{}", cfg.prefix)),
+        template_dir,
+        variant,
+        cfg,
+        job_title: args.job_title.clone(),
+        company: args.company_name.clone(),
+        quote: args.quote.clone().unwrap_or_default(),
+        application_date: ctx.get_today_str(),
+    };
+    println!(
+        "Previewing {} from {} (scratch dir {})",
+        session.variant,
+        session.template_dir.display(),
+        session.scratch_dir.display()
+    );
+
+    let viewer = get_variable_from_config_file(ctx, "optional", "pdf_viewer").ok();
+    let mut viewer_opened = false;
+    let mut snapshot = template_snapshot(&session.template_dir)?;
+    loop {
+        let build = session.rebuild(&SystemRunner);
+        print!("{build}");
+        if let (false, Some(pdf)) = (viewer_opened, build.pdf()) {
+            viewer_opened = true;
+            open_viewer(viewer.as_deref(), pdf);
+        }
+        if args.once {
+            return Ok(String::new());
+        }
+
+        println!(
+            "Watching {} for changes (Ctrl-C to stop)",
+            session.template_dir.display()
+        );
+        loop {
+            std::thread::sleep(Duration::from_millis(args.poll_ms));
+            let current = template_snapshot(&session.template_dir)?;
+            if current != snapshot {
+                snapshot = current;
+                break;
+            }
+        }
+    }
+}
+
+fn open_viewer(viewer: Option<&str>, pdf: &Path) {
+    let pdf = pdf.display().to_string();
+    match viewer {
+        Some(viewer) => {
+            let opened = ensure_tools_available(&[viewer])
+                .map_err(|e| e.to_string())
+                .and_then(|()| view_cv_file(&SystemRunner, &pdf, viewer));
+            if let Err(e) = opened {
+                warn!("Could not open {pdf} with {viewer}: {e}");
+            }
+        }
+        None => println!("No [optional] pdf_viewer configured; open {pdf} yourself"),
+    }
+}
+
+impl PreviewSession {
+    /// The directory the template is copied to and built in.
+    fn build_dir(&self) -> PathBuf {
+        self.scratch_dir.join("build")
+    }
+
+    /// Where the last good PDF is kept, so an open viewer reloads it.
+    pub fn preview_pdf(&self) -> PathBuf {
+        self.scratch_dir
+            .join(format!("{}-{}.pdf", self.cfg.prefix, self.variant))
+    }
+
+    /// Copy the template afresh, build the variant and report how it went.
+    pub fn rebuild(&self, runner: &dyn CommandRunner) -> PreviewBuild {
+        let build_dir = self.build_dir();
+        if let Err(e) = self.refresh_build_dir(&build_dir) {
+            return PreviewBuild::Failed(e.to_string());
+        }
+        let Some(build_dir) = build_dir.to_str() else {
+            return PreviewBuild::Failed(format!(
+                "The scratch dir {} is not valid UTF-8",
+                build_dir.display()
+            ));
+        };
+
+        let outcome = match invoke_builder(runner, build_dir, &self.variant, &self.cfg) {
+            Ok(outcome) => outcome,
+            Err(e) => return PreviewBuild::Failed(e.to_string()),
+        };
+        if !outcome.success {
+            return PreviewBuild::BuildFailed(BuildFailure::collect(
+                &format!("{} {}", self.cfg.builder, self.variant),
+                Path::new(build_dir),
+                &format!("{}-{}", self.cfg.prefix, self.variant),
+                &outcome,
+            ));
+        }
+
+        let built = Path::new(build_dir).join(format!("{}-{}.pdf", self.cfg.prefix, self.variant));
+        let pdf = self.preview_pdf();
+        if let Err(e) = fs::copy(&built, &pdf) {
+            return PreviewBuild::Failed(format!("Could not copy {}: {e}", built.display()));
+        }
+        let strategy = self.cfg.builder.page_count_strategy();
+        match enforce_page_contract(&outcome, &pdf, self.cfg.max_pages, strategy) {
+            Ok(pages) => PreviewBuild::Built { pdf, pages },
+            Err(e) => PreviewBuild::ContractBroken {
+                pdf,
+                reason: e.to_string(),
+            },
+        }
+    }
+
+    fn refresh_build_dir(&self, build_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if build_dir.exists() {
+            fs::remove_dir_all(build_dir)?;
+        }
+        fs::create_dir_all(&self.scratch_dir)?;
+        copy_dir::copy_dir(&self.template_dir, build_dir)?;
+        write_application_tex(
+            build_dir,
+            &ApplicationDetails {
+                job_title: &self.job_title,
+                company: &self.company,
+                quote: &self.quote,
+                application_date: &self.application_date,
+            },
+        )?;
+        info!(
+            "Copied {} to {}",
+            self.template_dir.display(),
+            build_dir.display()
+        );
+        Ok(())
+    }
+}
+
+/// Every file under `dir` (but `.git`) with its modification time and size,
+/// sorted: two equal snapshots mean nothing changed.
+pub fn template_snapshot(dir: &Path) -> std::io::Result<Vec<FileStamp>> {
+    let mut stamps = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                if entry.file_name() != ".git" {
+                    pending.push(path);
+                }
+            } else {
+                stamps.push((path, metadata.modified().ok(), metadata.len()));
+            }
+        }
+    }
+    stamps.sort();
+    Ok(stamps)
+}
+
+impl fmt::Display for PreviewBuild {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreviewBuild::Built { pdf, pages } => {
+                writeln!(f, "✅ Built {} ({pages} pages)", pdf.display())
+            }
+            PreviewBuild::ContractBroken { pdf, reason } => {
+                writeln!(f, "⚠️  Built {}, but {reason}", pdf.display())
+            }
+            PreviewBuild::BuildFailed(failure) => writeln!(f, "❌ {failure}"),
+            PreviewBuild::Failed(reason) => writeln!(f, "❌ {reason}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+    use crate::command_runner::CommandOutcome;
+    use crate::command_runner::testing::FakeRunner;
+    use std::io;
+    use tempfile::TempDir;
+
+    /// Writes the variant's PDF in `cwd` and reports `pages`.
+    struct PdfRunner {
+        pages: u32,
+    }
+
+    impl CommandRunner for PdfRunner {
+        fn status(&self, _program: &str, _args: &[&str], _cwd: Option<&str>) -> io::Result<bool> {
+            Ok(true)
+        }
+        fn output(&self, _program: &str, _args: &[&str]) -> io::Result<(bool, String)> {
+            Ok((true, String::new()))
+        }
+        fn spawn(&self, _program: &str, _args: &[&str]) -> io::Result<()> {
+            Ok(())
+        }
+        fn run_capturing(
+            &self,
+            _program: &str,
+            _args: &[&str],
+            cwd: Option<&str>,
+        ) -> io::Result<CommandOutcome> {
+            let dir = cwd.expect("the build runs in the scratch dir");
+            assert!(Path::new(dir).join("application.tex").is_file());
+            fs::write(format!("{dir}/TestCV-senior-sre.pdf"), b"%PDF-1.4")?;
+            Ok(CommandOutcome {
+                success: true,
+                stdout: format!(
+                    "Output written on TestCV-senior-sre.pdf ({} pages).\n",
+                    self.pages
+                ),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    fn session(base: &Path) -> PreviewSession {
+        let template_dir = base.join("template");
+        fs::create_dir_all(&template_dir).unwrap();
+        fs::write(template_dir.join("TestCV-senior-sre.tex"), "x").unwrap();
+        PreviewSession {
+            template_dir,
+            scratch_dir: base.join("scratch"),
+            variant: "senior-sre".to_string(),
+            cfg: BuildConfig {
+                prefix: "TestCV".to_string(),
+                builder: Builder::from_config("just", None, None, None).unwrap(),
+                max_pages: 2,
+                cover_letter_max_pages: 1,
+                compaction: None,
+            },
+            job_title: "Job Title".to_string(),
+            company: "Company".to_string(),
+            quote: String::new(),
+            application_date: "18-Oct-2026".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rebuild_keeps_the_pdf_at_a_stable_path() {
+        let td = TempDir::new().unwrap();
+        let session = session(td.path());
+
+        let build = session.rebuild(&PdfRunner { pages: 2 });
+
+        assert_eq!(
+            build,
+            PreviewBuild::Built {
+                pdf: session.preview_pdf(),
+                pages: 2
+            }
+        );
+        assert!(session.preview_pdf().is_file());
+        // The template itself is never built in.
+        assert!(!td.path().join("template/application.tex").exists());
+    }
+
+    #[test]
+    fn test_rebuild_reports_a_broken_page_contract() {
+        let td = TempDir::new().unwrap();
+        let session = session(td.path());
+
+        let build = session.rebuild(&PdfRunner { pages: 3 });
+
+        assert!(
+            matches!(&build, PreviewBuild::ContractBroken { reason, .. } if reason.contains("3 pages")),
+            "got: {build:?}"
+        );
+    }
+
+    #[test]
+    fn test_rebuild_prints_the_tex_errors_of_a_failed_build() {
+        let td = TempDir::new().unwrap();
+        let session = session(td.path());
+        let runner = FakeRunner {
+            stderr: "! Undefined control sequence.\nl.7 \\foo\n".to_string(),
+            ..FakeRunner::failing()
+        };
+
+        let build = session.rebuild(&runner);
+
+        let PreviewBuild::BuildFailed(failure) = &build else {
+            panic!("expected a failed build, got: {build:?}");
+        };
+        assert_eq!(
+            failure.diagnostics.errors().next().unwrap().to_string(),
+            "l.7: Undefined control sequence. (\\foo)"
+        );
+        assert!(
+            build
+                .to_string()
+                .contains("Error building CV with: just build senior-sre")
+        );
+    }
+
+    #[test]
+    fn test_template_snapshot_changes_with_the_template() {
+        let td = TempDir::new().unwrap();
+        fs::create_dir(td.path().join(".git")).unwrap();
+        fs::write(td.path().join("cv.tex"), "a").unwrap();
+        let before = template_snapshot(td.path()).unwrap();
+
+        fs::write(td.path().join(".git/index"), "ignored").unwrap();
+        assert_eq!(template_snapshot(td.path()).unwrap(), before);
+
+        fs::write(td.path().join("cv.tex"), "ab").unwrap();
+        assert_ne!(template_snapshot(td.path()).unwrap(), before);
+    }
+}
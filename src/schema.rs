@@ -9,5 +9,20 @@ diesel::table! {
         quote -> Varchar,
         pdf_cv_path -> Varchar,
         generated -> Bool,
+        status -> Varchar,
+        variant -> Nullable<Varchar>,
+        template_ref -> Nullable<Varchar>,
+        cover_letter_path -> Nullable<Varchar>,
+        compaction_level -> Nullable<Varchar>,
+        uuid -> Varchar,
+        updated_at -> Varchar,
+        synced_at -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    cv_deleted (uuid) {
+        uuid -> Varchar,
+        deleted_at -> Varchar,
     }
 }
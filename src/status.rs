@@ -0,0 +1,212 @@
+//! The application status lifecycle: where an application stands after its CV
+//! was generated, and which moves between those stages are legal.
+//!
+//! Lives in the library crate next to the models so the TUI and the CLI agree
+//! on the one set of stages. Stored in the `cv.status` column as the lowercase
+//! name returned by [`ApplicationStatus::as_str`].
+
+use std::fmt;
+use std::str::FromStr;
+
+/// One stage of an application. `Drafted` is the stage of a freshly generated CV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApplicationStatus {
+    #[default]
+    Drafted,
+    Sent,
+    Screening,
+    Interview,
+    Offer,
+    Rejected,
+    Withdrawn,
+    Ghosted,
+}
+
+impl ApplicationStatus {
+    /// Every stage, in lifecycle order.
+    pub const ALL: [ApplicationStatus; 8] = [
+        ApplicationStatus::Drafted,
+        ApplicationStatus::Sent,
+        ApplicationStatus::Screening,
+        ApplicationStatus::Interview,
+        ApplicationStatus::Offer,
+        ApplicationStatus::Rejected,
+        ApplicationStatus::Withdrawn,
+        ApplicationStatus::Ghosted,
+    ];
+
+    /// The name stored in the database and accepted on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApplicationStatus::Drafted => "drafted",
+            ApplicationStatus::Sent => "sent",
+            ApplicationStatus::Screening => "screening",
+            ApplicationStatus::Interview => "interview",
+            ApplicationStatus::Offer => "offer",
+            ApplicationStatus::Rejected => "rejected",
+            ApplicationStatus::Withdrawn => "withdrawn",
+            ApplicationStatus::Ghosted => "ghosted",
+        }
+    }
+
+    /// The stages reachable from this one.
+    ///
+    /// `Ghosted` is not terminal on purpose: a company that went quiet does
+    /// sometimes come back with a screening call or an interview. `Rejected`
+    /// and `Withdrawn` are terminal; correcting a mistaken move is what the
+    /// caller's force override is for.
+    pub fn next_stages(self) -> &'static [ApplicationStatus] {
+        use ApplicationStatus::{
+            Drafted, Ghosted, Interview, Offer, Rejected, Screening, Sent, Withdrawn,
+        };
+        match self {
+            Drafted => &[Sent, Withdrawn],
+            Sent => &[Screening, Interview, Rejected, Withdrawn, Ghosted],
+            Screening => &[Interview, Rejected, Withdrawn, Ghosted],
+            Interview => &[Offer, Rejected, Withdrawn, Ghosted],
+            Offer => &[Rejected, Withdrawn],
+            Ghosted => &[Screening, Interview, Rejected, Withdrawn],
+            Rejected | Withdrawn => &[],
+        }
+    }
+
+    pub fn can_transition_to(self, next: ApplicationStatus) -> bool {
+        self.next_stages().contains(&next)
+    }
+
+    /// Validate the move `self -> next`, returning `next` when it is legal.
+    pub fn transition_to(
+        self,
+        next: ApplicationStatus,
+    ) -> Result<ApplicationStatus, StatusTransitionError> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(StatusTransitionError {
+                from: self,
+                to: next,
+            })
+        }
+    }
+}
+
+impl fmt::Display for ApplicationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApplicationStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        ApplicationStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| {
+                let known: Vec<&str> = ApplicationStatus::ALL.map(ApplicationStatus::as_str).into();
+                format!("Unknown application status '{value}' (expected one of {known:?})")
+            })
+    }
+}
+
+/// A move the lifecycle does not allow, e.g. `rejected -> interview`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusTransitionError {
+    pub from: ApplicationStatus,
+    pub to: ApplicationStatus,
+}
+
+impl fmt::Display for StatusTransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let allowed: Vec<&str> = self
+            .from
+            .next_stages()
+            .iter()
+            .map(|status| status.as_str())
+            .collect();
+        if allowed.is_empty() {
+            write!(
+                f,
+                "Cannot move an application from '{}' to '{}': '{}' is final",
+                self.from, self.to, self.from
+            )
+        } else {
+            write!(
+                f,
+                "Cannot move an application from '{}' to '{}' (allowed: {allowed:?})",
+                self.from, self.to
+            )
+        }
+    }
+}
+
+impl std::error::Error for StatusTransitionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_status_round_trips_through_its_name() {
+        for status in ApplicationStatus::ALL {
+            assert_eq!(status.as_str().parse::<ApplicationStatus>(), Ok(status));
+        }
+        assert_eq!(
+            " Interview ".parse::<ApplicationStatus>(),
+            Ok(ApplicationStatus::Interview)
+        );
+        assert!("hired".parse::<ApplicationStatus>().is_err());
+    }
+
+    #[test]
+    fn a_new_application_starts_drafted() {
+        assert_eq!(ApplicationStatus::default(), ApplicationStatus::Drafted);
+    }
+
+    #[test]
+    fn the_happy_path_is_allowed() {
+        let mut status = ApplicationStatus::Drafted;
+        for next in [
+            ApplicationStatus::Sent,
+            ApplicationStatus::Screening,
+            ApplicationStatus::Interview,
+            ApplicationStatus::Offer,
+        ] {
+            status = status.transition_to(next).unwrap();
+        }
+        assert_eq!(status, ApplicationStatus::Offer);
+    }
+
+    #[test]
+    fn a_ghosted_application_can_come_back() {
+        assert!(ApplicationStatus::Ghosted.can_transition_to(ApplicationStatus::Interview));
+    }
+
+    #[test]
+    fn final_stages_reject_every_move() {
+        for terminal in [ApplicationStatus::Rejected, ApplicationStatus::Withdrawn] {
+            for next in ApplicationStatus::ALL {
+                let err = terminal.transition_to(next).unwrap_err();
+                assert!(err.to_string().contains("is final"), "got: {err}");
+            }
+        }
+    }
+
+    #[test]
+    fn skipping_sent_is_rejected_with_the_allowed_moves() {
+        let err = ApplicationStatus::Drafted
+            .transition_to(ApplicationStatus::Interview)
+            .unwrap_err();
+        assert_eq!(err.from, ApplicationStatus::Drafted);
+        assert!(err.to_string().contains("sent"), "got: {err}");
+    }
+
+    #[test]
+    fn no_stage_transitions_to_itself() {
+        for status in ApplicationStatus::ALL {
+            assert!(!status.can_transition_to(status), "{status} -> {status}");
+        }
+    }
+}
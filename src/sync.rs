@@ -0,0 +1,707 @@
+//! Two-way sync of the stored applications between two databases.
+//!
+//! CVs are generated on laptops that are often offline, so applications are
+//! saved into a local `SQLite` store and reconciled with the shared
+//! `PostgreSQL` database later. Rows are matched by `uuid`, never by `id`
+//! (each database numbers its rows itself). Each copy carries its
+//! `updated_at` and the `synced_at` both copies agreed on at the last sync, so
+//! a copy whose `updated_at` moved past its `synced_at` changed since:
+//!
+//! - changed on one side only: that side's copy wins;
+//! - changed on both sides: a conflict, reported and left untouched unless
+//!   both copies ended up identical or `--prefer` names the side that wins.
+//!
+//! A row present on one side only is copied to the other, unless the other
+//! side recorded its deletion (`cv_deleted`, written by every delete): then
+//! the deletion is synced, unless the copy changed after it (a conflict). A
+//! missing row alone never means a deletion, so a second laptop or a fresh
+//! local store only ever pulls the shared rows.
+//!
+//! Two never-synced rows with the same job title, company and quote (what
+//! `save_application` treats as one application) are the same application
+//! saved twice: the local copy adopts the remote `uuid` instead of being
+//! pushed as a duplicate.
+
+use crate::database::{DbConnection, record_deletion};
+use crate::models::Cv;
+use crate::schema::{cv, cv_deleted};
+use diesel::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// What a sync did, or would do with `dry_run`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub dry_run: bool,
+    /// Copied from the local database to the remote one.
+    pub pushed: Vec<String>,
+    /// Copied from the remote database to the local one.
+    pub pulled: Vec<String>,
+    /// Deleted locally because the remote copy was deleted.
+    pub deleted_locally: Vec<String>,
+    /// Deleted remotely because the local copy was deleted.
+    pub deleted_remotely: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+    /// Identical on both sides.
+    pub unchanged: usize,
+}
+
+/// An application the sync left alone: both sides need a human.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    /// `<job title> at <company> (<uuid>)`.
+    pub application: String,
+    pub reason: String,
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dry_run {
+            writeln!(f, "Dry run: nothing was written")?;
+        }
+        let sections = [
+            ("Pushed to the remote database", &self.pushed),
+            ("Pulled from the remote database", &self.pulled),
+            ("Deleted locally", &self.deleted_locally),
+            ("Deleted remotely", &self.deleted_remotely),
+        ];
+        for (title, applications) in sections {
+            if applications.is_empty() {
+                continue;
+            }
+            writeln!(f, "{title}: {}", applications.len())?;
+            for application in applications {
+                writeln!(f, "  {application}")?;
+            }
+        }
+        if !self.conflicts.is_empty() {
+            writeln!(f, "Conflicts, left untouched: {}", self.conflicts.len())?;
+            for conflict in &self.conflicts {
+                writeln!(f, "  {}: {}", conflict.application, conflict.reason)?;
+            }
+        }
+        writeln!(f, "Unchanged: {}", self.unchanged)
+    }
+}
+
+/// Reconcile the applications of `local` and `remote`. Conflicts are
+/// resolved in favour of `prefer`, if given. With `dry_run` the report is
+/// computed and nothing is written. The writes run in one transaction on each
+/// side, so a failure leaves both databases as they were.
+pub fn sync_databases(
+    local: &mut DbConnection,
+    remote: &mut DbConnection,
+    prefer: Option<SyncSide>,
+    dry_run: bool,
+) -> Result<SyncReport, Box<dyn std::error::Error>> {
+    local.transaction::<_, Box<dyn std::error::Error>, _>(|local| {
+        remote.transaction::<_, Box<dyn std::error::Error>, _>(|remote| {
+            sync_in_transactions(local, remote, prefer, dry_run)
+        })
+    })
+}
+
+fn sync_in_transactions(
+    local: &mut DbConnection,
+    remote: &mut DbConnection,
+    prefer: Option<SyncSide>,
+    dry_run: bool,
+) -> Result<SyncReport, Box<dyn std::error::Error>> {
+    let local_deleted = deletions(local)?;
+    let remote_deleted = deletions(remote)?;
+    let mut local_rows = cv::table.order(cv::id.asc()).load::<Cv>(local)?;
+    let remote_rows = cv::table.order(cv::id.asc()).load::<Cv>(remote)?;
+
+    for (local_id, uuid) in adoptions(&local_rows, &remote_rows) {
+        if !dry_run {
+            diesel::update(cv::table.find(local_id))
+                .set(cv::uuid.eq(&uuid))
+                .execute(local)?;
+        }
+        if let Some(row) = local_rows.iter_mut().find(|row| row.id == local_id) {
+            row.uuid = uuid;
+        }
+    }
+
+    let local_rows: BTreeMap<String, Cv> = local_rows
+        .into_iter()
+        .map(|row| (row.uuid.clone(), row))
+        .collect();
+    let remote_rows: BTreeMap<String, Cv> = remote_rows
+        .into_iter()
+        .map(|row| (row.uuid.clone(), row))
+        .collect();
+    let uuids: BTreeSet<&String> = local_rows.keys().chain(remote_rows.keys()).collect();
+
+    let mut report = SyncReport {
+        dry_run,
+        ..SyncReport::default()
+    };
+    for uuid in uuids {
+        let local_row = local_rows.get(uuid);
+        let remote_row = remote_rows.get(uuid);
+        let deleted = (
+            local_deleted.get(uuid.as_str()).map(String::as_str),
+            remote_deleted.get(uuid.as_str()).map(String::as_str),
+        );
+        let action = match (plan(local_row, remote_row, deleted), prefer) {
+            (SyncAction::Conflict(..), Some(side)) => {
+                resolve(local_rows.get(uuid), remote_rows.get(uuid), side)
+            }
+            (action, _) => action,
+        };
+        if !dry_run {
+            apply(&action, local, remote)?;
+        }
+        record(&mut report, action);
+    }
+    Ok(report)
+}
+
+/// Which copy wins a conflict (`db sync --prefer`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
+/// What to do with one application.
+#[derive(Debug)]
+enum SyncAction<'a> {
+    Unchanged,
+    /// Identical content, only the bookkeeping differs: write `updated_at`
+    /// as both copies' `updated_at` and `synced_at`.
+    MarkSynced(&'a Cv, &'a Cv, String),
+    Push(&'a Cv, bool),
+    Pull(&'a Cv, bool),
+    DeleteLocal(&'a Cv),
+    DeleteRemote(&'a Cv),
+    Conflict(&'a Cv, String),
+}
+
+/// The action for the copies of one application. `deleted` holds when each
+/// side recorded its deletion, if it did.
+fn plan<'a>(
+    local: Option<&'a Cv>,
+    remote: Option<&'a Cv>,
+    deleted: (Option<&str>, Option<&str>),
+) -> SyncAction<'a> {
+    match (local, remote) {
+        (Some(local), Some(remote)) => {
+            let same = same_content(local, remote);
+            if same && local.updated_at == remote.updated_at {
+                if local.synced_at.as_ref() == Some(&local.updated_at)
+                    && remote.synced_at.as_ref() == Some(&remote.updated_at)
+                {
+                    return SyncAction::Unchanged;
+                }
+                return SyncAction::MarkSynced(local, remote, local.updated_at.clone());
+            }
+            match (changed_since_sync(local), changed_since_sync(remote)) {
+                (true, false) => SyncAction::Push(local, true),
+                (false, true) => SyncAction::Pull(remote, true),
+                _ if same => SyncAction::MarkSynced(
+                    local,
+                    remote,
+                    newest(&local.updated_at, &remote.updated_at),
+                ),
+                (true, true) => SyncAction::Conflict(
+                    local,
+                    format!(
+                        "changed on both sides since the last sync (local {}, remote {})",
+                        local.updated_at, remote.updated_at
+                    ),
+                ),
+                // Neither copy moved since its own last sync yet they differ
+                // (a sync interrupted half-way): the newer copy wins.
+                (false, false)
+                    if newest(&local.updated_at, &remote.updated_at) == local.updated_at =>
+                {
+                    SyncAction::Push(local, true)
+                }
+                (false, false) => SyncAction::Pull(remote, true),
+            }
+        }
+        (Some(local), None) => match deleted.1 {
+            None => SyncAction::Push(local, false),
+            Some(deleted_at) if changed_after(local, deleted_at) => SyncAction::Conflict(
+                local,
+                format!(
+                    "deleted remotely at {deleted_at} but changed locally since ({})",
+                    local.updated_at
+                ),
+            ),
+            Some(_) => SyncAction::DeleteLocal(local),
+        },
+        (None, Some(remote)) => match deleted.0 {
+            None => SyncAction::Pull(remote, false),
+            Some(deleted_at) if changed_after(remote, deleted_at) => SyncAction::Conflict(
+                remote,
+                format!(
+                    "deleted locally at {deleted_at} but changed remotely since ({})",
+                    remote.updated_at
+                ),
+            ),
+            Some(_) => SyncAction::DeleteRemote(remote),
+        },
+        (None, None) => SyncAction::Unchanged,
+    }
+}
+
+/// The action of a conflict settled in favour of `side`: its copy, or its
+/// deletion, wins.
+fn resolve<'a>(local: Option<&'a Cv>, remote: Option<&'a Cv>, side: SyncSide) -> SyncAction<'a> {
+    match (local, remote, side) {
+        (Some(local), remote, SyncSide::Local) => SyncAction::Push(local, remote.is_some()),
+        (None, Some(remote), SyncSide::Local) => SyncAction::DeleteRemote(remote),
+        (local, Some(remote), SyncSide::Remote) => SyncAction::Pull(remote, local.is_some()),
+        (Some(local), None, SyncSide::Remote) => SyncAction::DeleteLocal(local),
+        (None, None, _) => SyncAction::Unchanged,
+    }
+}
+
+fn apply(
+    action: &SyncAction,
+    local: &mut DbConnection,
+    remote: &mut DbConnection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        SyncAction::Unchanged | SyncAction::Conflict(..) => {}
+        SyncAction::MarkSynced(local_row, remote_row, updated_at) => {
+            mark_synced(local, &local_row.uuid, updated_at)?;
+            mark_synced(remote, &remote_row.uuid, updated_at)?;
+        }
+        SyncAction::Push(row, exists) => {
+            copy_row(remote, row, *exists)?;
+            mark_synced(local, &row.uuid, &row.updated_at)?;
+        }
+        SyncAction::Pull(row, exists) => {
+            copy_row(local, row, *exists)?;
+            mark_synced(remote, &row.uuid, &row.updated_at)?;
+        }
+        SyncAction::DeleteLocal(row) => delete_by_uuid(local, &row.uuid)?,
+        SyncAction::DeleteRemote(row) => delete_by_uuid(remote, &row.uuid)?,
+    }
+    Ok(())
+}
+
+fn record(report: &mut SyncReport, action: SyncAction) {
+    match action {
+        SyncAction::Unchanged | SyncAction::MarkSynced(..) => report.unchanged += 1,
+        SyncAction::Push(row, _) => report.pushed.push(describe(row)),
+        SyncAction::Pull(row, _) => report.pulled.push(describe(row)),
+        SyncAction::DeleteLocal(row) => report.deleted_locally.push(describe(row)),
+        SyncAction::DeleteRemote(row) => report.deleted_remotely.push(describe(row)),
+        SyncAction::Conflict(row, reason) => report.conflicts.push(SyncConflict {
+            application: describe(row),
+            reason,
+        }),
+    }
+}
+
+/// The never-synced local rows that are a never-synced remote row saved a
+/// second time, as `(local id, remote uuid)`.
+fn adoptions(local_rows: &[Cv], remote_rows: &[Cv]) -> Vec<(i32, String)> {
+    let local_uuids: BTreeSet<&str> = local_rows.iter().map(|row| row.uuid.as_str()).collect();
+    let mut adopted = BTreeSet::new();
+    local_rows
+        .iter()
+        .filter(|row| row.synced_at.is_none())
+        .filter_map(|row| {
+            let twin = remote_rows.iter().find(|remote| {
+                remote.synced_at.is_none()
+                    && !local_uuids.contains(remote.uuid.as_str())
+                    && !adopted.contains(&remote.uuid)
+                    && (&remote.job_title, &remote.company, &remote.quote)
+                        == (&row.job_title, &row.company, &row.quote)
+            })?;
+            adopted.insert(twin.uuid.clone());
+            Some((row.id, twin.uuid.clone()))
+        })
+        .collect()
+}
+
+/// Whether `row` was written after its deletion at `deleted_at` elsewhere.
+fn changed_after(row: &Cv, deleted_at: &str) -> bool {
+    newest(&row.updated_at, deleted_at) == row.updated_at && row.updated_at != deleted_at
+}
+
+/// The recorded deletions of `conn`, as `uuid -> deleted_at`.
+fn deletions(conn: &mut DbConnection) -> QueryResult<BTreeMap<String, String>> {
+    Ok(cv_deleted::table
+        .load::<(String, String)>(conn)?
+        .into_iter()
+        .collect())
+}
+
+fn changed_since_sync(row: &Cv) -> bool {
+    row.synced_at.as_ref() != Some(&row.updated_at)
+}
+
+fn newest(a: &str, b: &str) -> String {
+    let parse = |value: &str| chrono::DateTime::parse_from_rfc3339(value).ok();
+    match (parse(a), parse(b)) {
+        (Some(a_time), Some(b_time)) if b_time > a_time => b.to_string(),
+        (Some(_), Some(_)) => a.to_string(),
+        _ => a.max(b).to_string(),
+    }
+}
+
+/// Every stored field but the database-local ones (`id`) and the sync
+/// bookkeeping.
+fn same_content(a: &Cv, b: &Cv) -> bool {
+    (
+        &a.application_date,
+        &a.job_title,
+        &a.company,
+        &a.quote,
+        &a.pdf_cv_path,
+        a.generated,
+        &a.status,
+        &a.variant,
+        &a.template_ref,
+        &a.cover_letter_path,
+        &a.compaction_level,
+    ) == (
+        &b.application_date,
+        &b.job_title,
+        &b.company,
+        &b.quote,
+        &b.pdf_cv_path,
+        b.generated,
+        &b.status,
+        &b.variant,
+        &b.template_ref,
+        &b.cover_letter_path,
+        &b.compaction_level,
+    )
+}
+
+fn describe(row: &Cv) -> String {
+    format!("{} at {} ({})", row.job_title, row.company, row.uuid)
+}
+
+/// A whole application as written to the other database, synced as of its
+/// `updated_at`.
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = cv)]
+#[diesel(treat_none_as_null = true)]
+struct SyncedRow<'a> {
+    application_date: Option<&'a str>,
+    job_title: &'a str,
+    company: &'a str,
+    quote: &'a str,
+    pdf_cv_path: &'a str,
+    generated: bool,
+    status: &'a str,
+    variant: Option<&'a str>,
+    template_ref: Option<&'a str>,
+    cover_letter_path: Option<&'a str>,
+    compaction_level: Option<&'a str>,
+    uuid: &'a str,
+    updated_at: &'a str,
+    synced_at: Option<&'a str>,
+}
+
+impl<'a> From<&'a Cv> for SyncedRow<'a> {
+    fn from(row: &'a Cv) -> Self {
+        Self {
+            application_date: row.application_date.as_deref(),
+            job_title: &row.job_title,
+            company: &row.company,
+            quote: &row.quote,
+            pdf_cv_path: &row.pdf_cv_path,
+            generated: row.generated,
+            status: &row.status,
+            variant: row.variant.as_deref(),
+            template_ref: row.template_ref.as_deref(),
+            cover_letter_path: row.cover_letter_path.as_deref(),
+            compaction_level: row.compaction_level.as_deref(),
+            uuid: &row.uuid,
+            updated_at: &row.updated_at,
+            synced_at: Some(&row.updated_at),
+        }
+    }
+}
+
+/// Write `row` into `conn`, over the copy with its `uuid` when `exists`. A
+/// recorded deletion of it on that side is dropped: the row is back.
+fn copy_row(
+    conn: &mut DbConnection,
+    row: &Cv,
+    exists: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let synced = SyncedRow::from(row);
+    if exists {
+        diesel::update(cv::table.filter(cv::uuid.eq(&row.uuid)))
+            .set(&synced)
+            .execute(conn)?;
+    } else {
+        diesel::insert_into(cv::table)
+            .values(&synced)
+            .execute(conn)?;
+    }
+    diesel::delete(cv_deleted::table.find(&row.uuid)).execute(conn)?;
+    Ok(())
+}
+
+fn mark_synced(
+    conn: &mut DbConnection,
+    uuid: &str,
+    updated_at: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    diesel::update(cv::table.filter(cv::uuid.eq(uuid)))
+        .set((cv::updated_at.eq(updated_at), cv::synced_at.eq(updated_at)))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Delete the copy `uuid`, recording the deletion so it reaches the other
+/// databases synced with this one.
+fn delete_by_uuid(conn: &mut DbConnection, uuid: &str) -> Result<(), Box<dyn std::error::Error>> {
+    diesel::delete(cv::table.filter(cv::uuid.eq(uuid))).execute(conn)?;
+    record_deletion(conn, uuid)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{delete_cv_by_id, save_new_cv_to_db, update_cv_in_db};
+    use crate::migrations::migrated_in_memory_sqlite;
+    use crate::models::CvChanges;
+
+    fn save(conn: &mut DbConnection, job_title: &str, company: &str) -> Cv {
+        save_new_cv_to_db(conn, "/cv.pdf", job_title, company, None, "18-Oct-2026").unwrap()
+    }
+
+    fn set_status(conn: &mut DbConnection, row: &Cv, status: &str) {
+        let id = cv::table
+            .filter(cv::uuid.eq(&row.uuid))
+            .select(cv::id)
+            .first::<i32>(conn)
+            .unwrap();
+        let changes = CvChanges {
+            status: Some(status),
+            ..CvChanges::default()
+        };
+        update_cv_in_db(conn, id, &changes).unwrap();
+    }
+
+    fn rows(conn: &mut DbConnection) -> Vec<Cv> {
+        cv::table.order(cv::uuid.asc()).load::<Cv>(conn).unwrap()
+    }
+
+    #[test]
+    fn test_sync_copies_new_rows_both_ways_then_is_a_no_op() {
+        let (mut local, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        let offline = save(&mut local, "SRE", "ACME");
+        let shared = save(&mut remote, "Platform", "Globex");
+
+        let report = sync_databases(&mut local, &mut remote, None, false).unwrap();
+
+        assert_eq!(report.pushed, [describe(&offline)]);
+        assert_eq!(report.pulled, [describe(&shared)]);
+        let (local_rows, remote_rows) = (rows(&mut local), rows(&mut remote));
+        assert_eq!(local_rows.len(), 2);
+        for (l, r) in local_rows.iter().zip(&remote_rows) {
+            assert_eq!(l.uuid, r.uuid);
+            assert!(same_content(l, r));
+            assert_eq!(l.synced_at.as_ref(), Some(&l.updated_at));
+        }
+
+        let again = sync_databases(&mut local, &mut remote, None, false).unwrap();
+        assert_eq!(
+            again,
+            SyncReport {
+                unchanged: 2,
+                ..SyncReport::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_a_change_on_one_side_wins_and_on_both_is_a_conflict() {
+        let (mut local, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        let first = save(&mut local, "SRE", "ACME");
+        let second = save(&mut local, "Platform", "Globex");
+        sync_databases(&mut local, &mut remote, None, false).unwrap();
+
+        set_status(&mut remote, &first, "sent");
+        set_status(&mut local, &second, "sent");
+        set_status(&mut remote, &second, "rejected");
+        let report = sync_databases(&mut local, &mut remote, None, false).unwrap();
+
+        assert_eq!(report.pulled, [describe(&first)]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].application, describe(&second));
+        assert!(report.conflicts[0].reason.contains("changed on both sides"));
+        let statuses = |conn: &mut DbConnection| -> Vec<(String, String)> {
+            rows(conn)
+                .into_iter()
+                .map(|row| (row.job_title, row.status))
+                .collect()
+        };
+        let mut local_statuses = statuses(&mut local);
+        local_statuses.sort();
+        assert_eq!(
+            local_statuses,
+            [
+                ("Platform".to_string(), "sent".to_string()),
+                ("SRE".to_string(), "sent".to_string())
+            ]
+        );
+        assert!(statuses(&mut remote).contains(&("Platform".to_string(), "rejected".to_string())));
+    }
+
+    #[test]
+    fn test_prefer_settles_the_conflicts() {
+        let (mut local, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        let both = save(&mut local, "SRE", "ACME");
+        sync_databases(&mut local, &mut remote, None, false).unwrap();
+        set_status(&mut local, &both, "sent");
+        set_status(&mut remote, &both, "rejected");
+
+        let report =
+            sync_databases(&mut local, &mut remote, Some(SyncSide::Remote), false).unwrap();
+
+        assert_eq!(report.pulled, [describe(&both)]);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(rows(&mut local)[0].status, "rejected");
+        let again = sync_databases(&mut local, &mut remote, None, false).unwrap();
+        assert_eq!(again.unchanged, 1);
+    }
+
+    #[test]
+    fn test_deletions_are_synced_unless_the_other_copy_changed() {
+        let (mut local, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        let gone = save(&mut local, "SRE", "ACME");
+        let edited = save(&mut local, "Platform", "Globex");
+        sync_databases(&mut local, &mut remote, None, false).unwrap();
+
+        delete_cv_by_id(&mut local, gone.id).unwrap();
+        delete_cv_by_id(&mut local, edited.id).unwrap();
+        set_status(&mut remote, &edited, "sent");
+        let report = sync_databases(&mut local, &mut remote, None, false).unwrap();
+
+        assert_eq!(report.deleted_remotely, [describe(&gone)]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert!(report.conflicts[0].reason.starts_with("deleted locally"));
+        assert_eq!(rows(&mut remote).len(), 1);
+    }
+
+    #[test]
+    fn test_two_local_stores_never_delete_the_shared_rows() {
+        let (mut laptop, mut desktop, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        let shared = save(&mut remote, "Platform", "Globex");
+        let from_laptop = save(&mut laptop, "SRE", "ACME");
+        sync_databases(&mut laptop, &mut remote, None, false).unwrap();
+
+        // A store that never synced misses every synced row of the remote.
+        let from_desktop = save(&mut desktop, "Dev", "Initech");
+        let report = sync_databases(&mut desktop, &mut remote, None, false).unwrap();
+
+        assert!(report.deleted_remotely.is_empty());
+        assert_eq!(report.pushed, [describe(&from_desktop)]);
+        assert_eq!(report.pulled.len(), 2);
+        let report = sync_databases(&mut laptop, &mut remote, None, false).unwrap();
+        assert!(report.deleted_remotely.is_empty() && report.deleted_locally.is_empty());
+        assert_eq!(report.pulled, [describe(&from_desktop)]);
+        for conn in [&mut laptop, &mut desktop, &mut remote] {
+            let uuids: BTreeSet<String> = rows(conn).into_iter().map(|row| row.uuid).collect();
+            assert_eq!(
+                uuids,
+                [&shared, &from_laptop, &from_desktop]
+                    .map(|row| row.uuid.clone())
+                    .into()
+            );
+        }
+
+        // A deletion on the laptop reaches the desktop through the remote.
+        let laptop_id = rows(&mut laptop)
+            .into_iter()
+            .find(|row| row.uuid == shared.uuid)
+            .unwrap()
+            .id;
+        delete_cv_by_id(&mut laptop, laptop_id).unwrap();
+        sync_databases(&mut laptop, &mut remote, None, false).unwrap();
+        let report = sync_databases(&mut desktop, &mut remote, None, false).unwrap();
+        assert_eq!(report.deleted_locally.len(), 1);
+        assert_eq!(rows(&mut desktop).len(), 2);
+    }
+
+    #[test]
+    fn test_a_failed_sync_writes_nothing() {
+        let (mut local, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        save(&mut local, "SRE", "ACME");
+        save(&mut local, "Platform", "Globex");
+        // The second push fails: the remote cannot take another row.
+        diesel::sql_query(
+            "CREATE TRIGGER one_row BEFORE INSERT ON cv \
+             WHEN (SELECT count(*) FROM cv) > 0 BEGIN SELECT RAISE(ABORT, 'full'); END",
+        )
+        .execute(&mut remote)
+        .unwrap();
+
+        assert!(sync_databases(&mut local, &mut remote, None, false).is_err());
+
+        assert!(rows(&mut remote).is_empty());
+        assert!(rows(&mut local).iter().all(|row| row.synced_at.is_none()));
+    }
+
+    #[test]
+    fn test_the_same_application_saved_on_both_sides_is_linked_not_duplicated() {
+        let (mut local, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        save(&mut local, "SRE", "ACME");
+        let shared = save(&mut remote, "SRE", "ACME");
+
+        let report = sync_databases(&mut local, &mut remote, None, false).unwrap();
+
+        assert!(report.pushed.is_empty() && report.pulled.is_empty());
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(rows(&mut local)[0].uuid, shared.uuid);
+        assert_eq!(rows(&mut remote).len(), 1);
+    }
+
+    #[test]
+    fn test_dry_run_reports_without_writing() {
+        let (mut local, mut remote) = (
+            migrated_in_memory_sqlite().unwrap(),
+            migrated_in_memory_sqlite().unwrap(),
+        );
+        let offline = save(&mut local, "SRE", "ACME");
+
+        let report = sync_databases(&mut local, &mut remote, None, true).unwrap();
+
+        assert_eq!(report.pushed, [describe(&offline)]);
+        assert!(
+            report
+                .to_string()
+                .starts_with("Dry run: nothing was written\n")
+        );
+        assert!(rows(&mut remote).is_empty());
+        assert_eq!(rows(&mut local)[0].synced_at, None);
+    }
+}
@@ -13,7 +13,7 @@
 //! cache reuse / offline fallback (TS-04) are later slices and intentionally
 //! absent here.
 
-use crate::command_runner::{CommandOutcome, CommandRunner};
+use crate::command_runner::{CommandOutcome, CommandRunner, PlannedCommand};
 use crate::helpers::ensure_tools_available;
 use log::{info, warn};
 use std::fs;
@@ -161,6 +161,65 @@ pub fn resolve_template_for_config(
     detect_template_source(value, cache_dir)?.resolve(runner)
 }
 
+/// What [`resolve_template_for_config`] would do, worked out without cloning,
+/// fetching or probing the remote — the template step of `insert --dry-run`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePlan {
+    /// A local template directory, used in place.
+    Local { path: String },
+    /// A git source resolved into the cache entry `entry`. The commands assume
+    /// the remote is reachable; offline, an existing entry is reused as is.
+    Git {
+        url: String,
+        git_ref: Option<String>,
+        entry: String,
+        cached: bool,
+        commands: Vec<PlannedCommand>,
+    },
+}
+
+impl TemplatePlan {
+    /// The local directory the template would be copied from.
+    pub fn template_dir(&self) -> &str {
+        match self {
+            TemplatePlan::Local { path } => path,
+            TemplatePlan::Git { entry, .. } => entry,
+        }
+    }
+}
+
+/// Dry-run counterpart of [`resolve_template_for_config`]: classify `value`
+/// the same way, but only describe the git work instead of running it.
+pub fn plan_template_for_config(
+    value: &str,
+    cache_dir: &str,
+    git_ref: Option<&str>,
+    auth: AuthMode,
+) -> Result<TemplatePlan, Box<dyn std::error::Error>> {
+    if !Path::new(value).is_dir() && is_git_url(value) {
+        let mut repository =
+            GitHubRepository::new(value.to_string(), cache_dir.to_string()).with_auth(auth);
+        if let Some(reference) = git_ref {
+            repository = repository.with_ref(reference);
+        }
+        let entry = repository.clone_destination();
+        let cached = Path::new(&entry).is_dir();
+        return Ok(TemplatePlan::Git {
+            url: value.to_string(),
+            git_ref: git_ref.map(str::to_string),
+            commands: repository.planned_commands(&entry, cached),
+            entry,
+            cached,
+        });
+    }
+    // Anything left is a local directory or rejected, naming the value, exactly
+    // as the real resolver would.
+    detect_template_source(value, cache_dir)?;
+    Ok(TemplatePlan::Local {
+        path: value.to_string(),
+    })
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Auth (TS-02), ref pinning (TS-03), cache / offline (TS-04) and typed errors
 // (TS-D1) — wired into the production resolver `resolve_template_for_config`
@@ -471,6 +530,48 @@ impl GitHubRepository {
         }
     }
 
+    /// The git commands [`resolve_cached`](Self::resolve_cached) runs when the
+    /// remote is reachable: probe, clone or fetch, then the pinned-ref checkout.
+    fn planned_commands(&self, entry: &str, cached: bool) -> Vec<PlannedCommand> {
+        let flags = auth_invocation_flags(self.auth, &self.url);
+        let with_flags = |tail: &[&str]| -> Vec<String> {
+            flags
+                .iter()
+                .map(String::as_str)
+                .chain(tail.iter().copied())
+                .map(str::to_string)
+                .collect()
+        };
+        let git = |args: Vec<String>, cwd: Option<&str>| PlannedCommand {
+            program: "git".to_string(),
+            args,
+            cwd: cwd.map(str::to_string),
+        };
+
+        let mut commands = vec![git(with_flags(&["ls-remote", &self.url]), None)];
+        if cached {
+            commands.push(git(
+                with_flags(&["fetch", "--prune", "--tags"]),
+                Some(entry),
+            ));
+        } else {
+            commands.push(git(with_flags(&["clone", &self.url, entry]), None));
+        }
+        if let Some(git_ref) = &self.git_ref {
+            commands.push(PlannedCommand::new(
+                "git",
+                &["checkout", git_ref],
+                Some(entry),
+            ));
+            commands.push(PlannedCommand::new(
+                "git",
+                &["rev-parse", "HEAD"],
+                Some(entry),
+            ));
+        }
+        commands
+    }
+
     /// Probe whether the remote is reachable under the configured auth by listing
     /// its refs. A captured io failure or a non-zero exit reads as unreachable, so
     /// the cache decision falls back to reuse-or-abort (TS-04).
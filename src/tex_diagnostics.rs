@@ -0,0 +1,469 @@
+//! What went wrong in a failed TeX build, read from its transcript and `.log`.
+//!
+//! A failed build's captured output and the `<prefix>-<variant>.log` the engine
+//! leaves in the working directory are parsed into `Diagnostic`s — errors with
+//! their file and line, missing packages, overfull/underfull boxes — and a
+//! `BuildFailure` prints a short summary of them. The working directory is
+//! kept, with the full transcript saved next to the log, so nothing has to be
+//! rebuilt to dig further.
+
+use crate::command_runner::CommandOutcome;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Errors, missing packages and box warnings shown in a summary; the rest is
+/// counted.
+const SHOWN_ERRORS: usize = 10;
+const SHOWN_BOX_WARNINGS: usize = 5;
+/// Transcript lines shown when no diagnostic could be recognised.
+const TAIL_LINES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Error,
+    /// A `.sty`/`.cls` (or other input) file that could not be found.
+    MissingPackage,
+    OverfullBox,
+    UnderfullBox,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+    /// The source TeX was reading, from the `l.<line> <context>` line.
+    pub context: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(kind: DiagnosticKind, message: &str) -> Self {
+        Self {
+            kind,
+            file: None,
+            line: None,
+            message: message.trim().to_string(),
+            context: None,
+        }
+    }
+
+    fn is_warning(&self) -> bool {
+        matches!(
+            self.kind,
+            DiagnosticKind::OverfullBox | DiagnosticKind::UnderfullBox
+        )
+    }
+
+    /// The same problem reported twice (e.g. `! Undefined control sequence.`
+    /// in the log and `cv.tex:12: Undefined control sequence.` on stderr).
+    fn same_as(&self, other: &Diagnostic) -> bool {
+        let message = |d: &Diagnostic| d.message.trim_end_matches('.').to_string();
+        self.kind == other.kind
+            && message(self) == message(other)
+            && (self.line.is_none() || other.line.is_none() || self.line == other.line)
+    }
+}
+
+/// The diagnostics of one build, in the order they were first reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TexDiagnostics {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl TexDiagnostics {
+    /// Parse a transcript or log, adding what is new to `self`.
+    pub fn add_text(&mut self, text: &str) {
+        let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+        let mut index = 0;
+        while index < lines.len() {
+            let (diagnostic, consumed) = parse_at(&lines, index);
+            if let Some(diagnostic) = diagnostic {
+                self.push(diagnostic);
+            }
+            index += consumed.max(1);
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut diagnostics = Self::default();
+        diagnostics.add_text(text);
+        diagnostics
+    }
+
+    /// Add `diagnostic`, or complete the one already reporting it with its
+    /// file, line and context.
+    fn push(&mut self, diagnostic: Diagnostic) {
+        match self.diagnostics.iter_mut().find(|d| d.same_as(&diagnostic)) {
+            Some(known) => {
+                known.file = known.file.take().or(diagnostic.file);
+                known.line = known.line.or(diagnostic.line);
+                known.context = known.context.take().or(diagnostic.context);
+            }
+            None => self.diagnostics.push(diagnostic),
+        }
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| !d.is_warning())
+    }
+
+    pub fn box_warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.is_warning())
+    }
+
+    pub fn count(&self, kind: DiagnosticKind) -> usize {
+        self.diagnostics.iter().filter(|d| d.kind == kind).count()
+    }
+}
+
+/// The diagnostic starting at `lines[index]`, if any, and how many lines it
+/// spans.
+fn parse_at(lines: &[&str], index: usize) -> (Option<Diagnostic>, usize) {
+    let line = lines[index];
+    if let Some(message) = line.strip_prefix('!') {
+        let mut diagnostic = error_or_missing_package(message);
+        // The `l.<n> <context>` line follows within a few lines of the message.
+        for (offset, next) in lines.iter().enumerate().skip(index + 1).take(6) {
+            if next.starts_with('!') || next.starts_with("error:") {
+                return (Some(diagnostic), offset - index);
+            }
+            if let Some((number, context)) = next.strip_prefix("l.").and_then(line_number) {
+                diagnostic.line = Some(number);
+                diagnostic.context = (!context.trim().is_empty()).then(|| context.trim().into());
+                return (Some(diagnostic), offset - index + 1);
+            }
+        }
+        return (Some(diagnostic), 1);
+    }
+
+    let unprefixed = line.strip_prefix("error:").map(str::trim);
+    if let Some((file, number, message)) = file_line_error(unprefixed.unwrap_or(line)) {
+        let mut diagnostic = error_or_missing_package(message);
+        diagnostic.file = Some(file.to_string());
+        diagnostic.line = Some(number);
+        return (Some(diagnostic), 1);
+    }
+    if let Some(message) = unprefixed {
+        let mut diagnostic = error_or_missing_package(message);
+        // typst points at the source on the next lines: `┌─ cv.typ:12:5`.
+        for next in lines.iter().skip(index + 1).take(2) {
+            if let Some((_, location)) = next.split_once("┌─") {
+                let mut parts = location.trim().split(':');
+                diagnostic.file = parts.next().map(str::to_string);
+                diagnostic.line = parts.next().and_then(|n| n.parse().ok());
+            }
+        }
+        return (Some(diagnostic), 1);
+    }
+
+    for (prefix, kind) in [
+        ("Overfull \\", DiagnosticKind::OverfullBox),
+        ("Underfull \\", DiagnosticKind::UnderfullBox),
+    ] {
+        if line.starts_with(prefix) {
+            let (message, location) = match line.find(" in paragraph at lines ") {
+                Some(at) => (&line[..at], Some(&line[at + 23..])),
+                None => match line.find(" detected at line ") {
+                    Some(at) => (&line[..at], Some(&line[at + 18..])),
+                    None => (line, None),
+                },
+            };
+            let mut diagnostic = Diagnostic::new(kind, message);
+            diagnostic.line = location.and_then(line_number).map(|(number, _)| number);
+            return (Some(diagnostic), 1);
+        }
+    }
+    (None, 1)
+}
+
+/// `! LaTeX Error: File `moderncv.sty' not found.` is a missing package; any
+/// other message an error.
+fn error_or_missing_package(message: &str) -> Diagnostic {
+    let message = message.trim();
+    let missing = message
+        .split_once("File `")
+        .and_then(|(_, rest)| rest.split_once("' not found"))
+        .map(|(file, _)| file);
+    match missing {
+        Some(file) => Diagnostic::new(DiagnosticKind::MissingPackage, file),
+        None => Diagnostic::new(DiagnosticKind::Error, message),
+    }
+}
+
+/// `./sections/skills.tex:12: Undefined control sequence.` (`-file-line-error`
+/// and tectonic style) as file, line and message.
+fn file_line_error(line: &str) -> Option<(&str, u32, &str)> {
+    let (file, rest) = line.split_once(':')?;
+    let (number, message) = rest.split_once(':')?;
+    let number = number.parse().ok()?;
+    let has_extension = Path::new(file)
+        .extension()
+        .is_some_and(|extension| extension.len() <= 4);
+    (has_extension && !file.contains(' ')).then_some((file, number, message.trim()))
+}
+
+/// The leading number of `text` and what follows it.
+fn line_number(text: &str) -> Option<(u32, &str)> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    Some((text[..end].parse().ok()?, &text[end..]))
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{file}:{line}: ")?,
+            (Some(file), None) => write!(f, "{file}: ")?,
+            (None, Some(line)) => write!(f, "l.{line}: ")?,
+            (None, None) => {}
+        }
+        match self.kind {
+            DiagnosticKind::MissingPackage => write!(f, "missing package {}", self.message)?,
+            _ => write!(f, "{}", self.message)?,
+        }
+        if let Some(context) = &self.context {
+            write!(f, " ({context})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A build that failed, with what its transcript and log say about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildFailure {
+    /// The builder invocation, e.g. `just build senior-sre`.
+    pub command: String,
+    /// The working directory, left in place for inspection.
+    pub working_dir: PathBuf,
+    pub diagnostics: TexDiagnostics,
+    /// The end of the transcript, shown when no diagnostic was recognised.
+    pub tail: Vec<String>,
+    /// The saved transcript and the engine's `.log`, when there is one.
+    pub logs: Vec<PathBuf>,
+}
+
+impl BuildFailure {
+    /// Collect the diagnostics of the failed build of `document` (the driver's
+    /// file stem, `<prefix>-<variant>`) in `working_dir`, and save the
+    /// transcript there as `<document>-transcript.log`.
+    pub fn collect(
+        command: &str,
+        working_dir: &Path,
+        document: &str,
+        outcome: &CommandOutcome,
+    ) -> Self {
+        let transcript = format!("{}\n{}", outcome.stdout, outcome.stderr);
+        let mut diagnostics = TexDiagnostics::parse(&transcript);
+        let mut logs = Vec::new();
+
+        let saved = working_dir.join(format!("{document}-transcript.log"));
+        match fs::write(&saved, &transcript) {
+            Ok(()) => logs.push(saved),
+            Err(e) => log::warn!(
+                "Could not save the build transcript to {}: {e}",
+                saved.display()
+            ),
+        }
+        let engine_log = working_dir.join(format!("{document}.log"));
+        if let Ok(bytes) = fs::read(&engine_log) {
+            diagnostics.add_text(&String::from_utf8_lossy(&bytes));
+            logs.push(engine_log);
+        }
+
+        let lines: Vec<&str> = transcript
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+        let tail = lines[lines.len().saturating_sub(TAIL_LINES)..]
+            .iter()
+            .map(|line| (*line).to_string())
+            .collect();
+        Self {
+            command: command.to_string(),
+            working_dir: working_dir.to_path_buf(),
+            diagnostics,
+            tail,
+            logs,
+        }
+    }
+}
+
+impl fmt::Display for BuildFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let diagnostics = &self.diagnostics;
+        writeln!(f, "Error building CV with: {}", self.command)?;
+        writeln!(
+            f,
+            "  {} error(s), {} missing package(s), {} overfull and {} underfull box(es)",
+            diagnostics.count(DiagnosticKind::Error),
+            diagnostics.count(DiagnosticKind::MissingPackage),
+            diagnostics.count(DiagnosticKind::OverfullBox),
+            diagnostics.count(DiagnosticKind::UnderfullBox),
+        )?;
+        write_capped(f, "✗", diagnostics.errors(), SHOWN_ERRORS)?;
+        write_capped(f, "⚠", diagnostics.box_warnings(), SHOWN_BOX_WARNINGS)?;
+        if diagnostics.errors().next().is_none() && !self.tail.is_empty() {
+            writeln!(f, "  No TeX error recognised; the transcript ends with:")?;
+            for line in &self.tail {
+                writeln!(f, "    {line}")?;
+            }
+        }
+        write!(
+            f,
+            "  The working directory is kept: {}",
+            self.working_dir.display()
+        )?;
+        for log in &self.logs {
+            write!(f, "\n  Full log: {}", log.display())?;
+        }
+        Ok(())
+    }
+}
+
+fn write_capped<'a>(
+    f: &mut fmt::Formatter<'_>,
+    marker: &str,
+    diagnostics: impl Iterator<Item = &'a Diagnostic>,
+    shown: usize,
+) -> fmt::Result {
+    let diagnostics: Vec<&Diagnostic> = diagnostics.collect();
+    for diagnostic in diagnostics.iter().take(shown) {
+        writeln!(f, "  {marker} {diagnostic}")?;
+    }
+    if diagnostics.len() > shown {
+        writeln!(f, "  ... and {} more", diagnostics.len() - shown)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for BuildFailure {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const TECTONIC_TRANSCRIPT: &str = "This is XeTeX\n\
+         ! Undefined control sequence.\n\
+         <recently read> \\foo\n\
+         l.12 \\foo\n\
+         ! Missing $ inserted.\n\
+         error: TestCV-sre.tex:12: Undefined control sequence\n\
+         error: halted on potentially-recoverable error as specified\n\
+         error: halted on potentially-recoverable error as specified\n";
+
+    #[test]
+    fn test_errors_are_paired_with_their_line_and_merged() {
+        let diagnostics = TexDiagnostics::parse(TECTONIC_TRANSCRIPT);
+
+        let errors: Vec<String> = diagnostics.errors().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "TestCV-sre.tex:12: Undefined control sequence. (\\foo)",
+                "Missing $ inserted.",
+                "halted on potentially-recoverable error as specified",
+            ]
+        );
+        assert!(
+            TexDiagnostics::parse("Output written on x.pdf (1 page).")
+                .diagnostics
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_missing_packages_and_boxes_are_recognised() {
+        let log = "(./TestCV-sre.tex\n\
+             ! LaTeX Error: File `moderncv.sty' not found.\n\
+             Type X to quit or <RETURN> to proceed,\n\
+             l.3 \\usepackage\n\
+             Overfull \\hbox (12.3pt too wide) in paragraph at lines 40--42\n\
+             Underfull \\vbox (badness 10000) detected at line 57\n";
+
+        let diagnostics = TexDiagnostics::parse(log);
+
+        assert_eq!(diagnostics.count(DiagnosticKind::MissingPackage), 1);
+        assert_eq!(
+            diagnostics.diagnostics[0].to_string(),
+            "l.3: missing package moderncv.sty (\\usepackage)"
+        );
+        let boxes: Vec<(DiagnosticKind, Option<u32>)> = diagnostics
+            .box_warnings()
+            .map(|d| (d.kind, d.line))
+            .collect();
+        assert_eq!(
+            boxes,
+            [
+                (DiagnosticKind::OverfullBox, Some(40)),
+                (DiagnosticKind::UnderfullBox, Some(57))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typst_errors_point_at_their_source() {
+        let diagnostics =
+            TexDiagnostics::parse("error: unknown variable: foo\n   ┌─ TestCV-sre.typ:12:5\n");
+
+        assert_eq!(
+            diagnostics.errors().next().unwrap().to_string(),
+            "TestCV-sre.typ:12: unknown variable: foo"
+        );
+    }
+
+    #[test]
+    fn test_build_failure_reads_the_log_and_saves_the_transcript() {
+        let td = TempDir::new().unwrap();
+        fs::write(
+            td.path().join("TestCV-sre.log"),
+            "Overfull \\hbox (3.0pt too wide) in paragraph at lines 8--9\n",
+        )
+        .unwrap();
+        let outcome = CommandOutcome {
+            success: false,
+            stdout: String::new(),
+            stderr: TECTONIC_TRANSCRIPT.to_string(),
+        };
+
+        let failure = BuildFailure::collect("just build sre", td.path(), "TestCV-sre", &outcome);
+
+        assert_eq!(failure.diagnostics.count(DiagnosticKind::OverfullBox), 1);
+        let transcript = td.path().join("TestCV-sre-transcript.log");
+        assert_eq!(
+            failure.logs,
+            [transcript.clone(), td.path().join("TestCV-sre.log")]
+        );
+        assert!(
+            fs::read_to_string(transcript)
+                .unwrap()
+                .contains("Missing $")
+        );
+        let summary = failure.to_string();
+        assert!(summary.starts_with("Error building CV with: just build sre\n"));
+        assert!(summary.contains("3 error(s), 0 missing package(s), 1 overfull"));
+        assert!(summary.contains("The working directory is kept"));
+    }
+
+    #[test]
+    fn test_build_failure_without_diagnostics_shows_the_transcript_end() {
+        let td = TempDir::new().unwrap();
+        let outcome = CommandOutcome {
+            success: false,
+            stdout: "just: recipe `build` failed\n".to_string(),
+            stderr: String::new(),
+        };
+
+        let summary =
+            BuildFailure::collect("just build sre", td.path(), "TestCV-sre", &outcome).to_string();
+
+        assert!(
+            summary.contains("No TeX error recognised"),
+            "got: {summary}"
+        );
+        assert!(summary.contains("    just: recipe `build` failed"));
+    }
+}
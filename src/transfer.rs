@@ -0,0 +1,474 @@
+//! Export and import of the stored applications, for backups, moving between
+//! `SQLite` and `PostgreSQL`, or seeding a test database.
+//!
+//! The JSON export is one versioned document:
+//!
+//! ```json
+//! {"format": "rusty-cv-creator/applications", "version": 1,
+//!  "exported_at": "2026-10-18T10:00:00.000000Z", "applications": [...]}
+//! ```
+//!
+//! Each application carries every column but the database-assigned `id` and
+//! the `db sync` bookkeeping (`synced_at`). The CSV export has the same
+//! columns, one row per application, under a header row naming them.
+//!
+//! An import skips the applications the database already has, by the rule of
+//! `save_application`: the same job title, company and quote. An application
+//! whose `uuid` is taken is skipped as well. Everything else is inserted in a
+//! single transaction. In a hand-written file `uuid`, `updated_at`, `status`
+//! and `generated` may be left out.
+
+use crate::database::{DbConnection, check_if_entry_exists, modified_now};
+use crate::models::Cv;
+use crate::schema::cv;
+use crate::status::ApplicationStatus;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use uuid::Uuid;
+
+/// The `format` of a JSON export.
+pub const FORMAT_NAME: &str = "rusty-cv-creator/applications";
+/// The `version` of the JSON export this program writes and reads.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The file formats of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFormat {
+    Json,
+    Csv,
+}
+
+/// One application in an export.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = cv)]
+pub struct ExportedApplication {
+    #[serde(default)]
+    pub uuid: String,
+    pub application_date: Option<String>,
+    pub job_title: String,
+    pub company: String,
+    #[serde(default)]
+    pub quote: String,
+    pub pdf_cv_path: String,
+    #[serde(default = "generated_by_default")]
+    pub generated: bool,
+    #[serde(default)]
+    pub status: String,
+    pub variant: Option<String>,
+    pub template_ref: Option<String>,
+    pub cover_letter_path: Option<String>,
+    pub compaction_level: Option<String>,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+fn generated_by_default() -> bool {
+    true
+}
+
+impl From<Cv> for ExportedApplication {
+    fn from(row: Cv) -> Self {
+        Self {
+            uuid: row.uuid,
+            application_date: row.application_date,
+            job_title: row.job_title,
+            company: row.company,
+            quote: row.quote,
+            pdf_cv_path: row.pdf_cv_path,
+            generated: row.generated,
+            status: row.status,
+            variant: row.variant,
+            template_ref: row.template_ref,
+            cover_letter_path: row.cover_letter_path,
+            compaction_level: row.compaction_level,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportDocument {
+    format: String,
+    version: u32,
+    #[serde(default)]
+    exported_at: String,
+    applications: Vec<ExportedApplication>,
+}
+
+/// Every stored application, oldest first, in `format`.
+pub fn export_applications(
+    conn: &mut DbConnection,
+    format: TransferFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // NOTE: `MultiConnection` forbids `as_select`; default (all-columns)
+    // selection matches `Cv`'s field order against the `cv` table.
+    let applications: Vec<ExportedApplication> = cv::table
+        .order(cv::id)
+        .load::<Cv>(conn)?
+        .into_iter()
+        .map(ExportedApplication::from)
+        .collect();
+
+    match format {
+        TransferFormat::Json => {
+            let document = ExportDocument {
+                format: FORMAT_NAME.to_string(),
+                version: FORMAT_VERSION,
+                exported_at: modified_now(),
+                applications,
+            };
+            Ok(serde_json::to_string_pretty(&document)? + "\n")
+        }
+        TransferFormat::Csv => to_csv(&applications),
+    }
+}
+
+/// `rows` as CSV under a header row of their serde field names, in order. An
+/// empty list still gets the header.
+pub fn to_csv<T: Serialize + Default>(rows: &[T]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if rows.is_empty() {
+        // `serialize` only writes the header with the first record: write a
+        // blank one and keep the header line alone.
+        writer.serialize(T::default())?;
+        let csv = String::from_utf8(writer.into_inner()?)?;
+        return Ok(csv.lines().next().unwrap_or_default().to_string() + "\n");
+    }
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Read the applications of an export. A JSON document of another format or
+/// version is refused rather than guessed at.
+pub fn parse_export(
+    input: &str,
+    format: TransferFormat,
+) -> Result<Vec<ExportedApplication>, Box<dyn std::error::Error>> {
+    match format {
+        TransferFormat::Json => {
+            let document: ExportDocument = serde_json::from_str(input)
+                .map_err(|e| format!("Not an export of the applications: {e}"))?;
+            if document.format != FORMAT_NAME {
+                return Err(format!(
+                    "Not an export of the applications: the format is '{}', not '{FORMAT_NAME}'",
+                    document.format
+                )
+                .into());
+            }
+            if document.version != FORMAT_VERSION {
+                return Err(format!(
+                    "Unsupported export version {} (this program reads version {FORMAT_VERSION})",
+                    document.version
+                )
+                .into());
+            }
+            Ok(document.applications)
+        }
+        TransferFormat::Csv => csv::Reader::from_reader(input.as_bytes())
+            .deserialize()
+            .enumerate()
+            // Row 1 is the header.
+            .map(|(index, row)| row.map_err(|e| format!("CSV row {}: {e}", index + 2).into()))
+            .collect(),
+    }
+}
+
+/// What an import did, or would do with `dry_run`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// The applications inserted.
+    pub imported: Vec<String>,
+    /// The applications skipped, and why.
+    pub duplicates: Vec<(String, String)>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dry_run {
+            writeln!(f, "Dry run: nothing was written")?;
+        }
+        let verb = if self.dry_run {
+            "Would import"
+        } else {
+            "Imported"
+        };
+        writeln!(f, "{verb}: {}", self.imported.len())?;
+        for application in &self.imported {
+            writeln!(f, "  {application}")?;
+        }
+        writeln!(f, "Skipped as duplicates: {}", self.duplicates.len())?;
+        for (application, reason) in &self.duplicates {
+            writeln!(f, "  {application}: {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Insert the `applications` the database does not have yet, in one
+/// transaction. With `dry_run` the report is computed and nothing is written.
+pub fn import_applications(
+    conn: &mut DbConnection,
+    applications: &[ExportedApplication],
+    dry_run: bool,
+) -> Result<ImportReport, Box<dyn std::error::Error>> {
+    let applications = applications
+        .iter()
+        .enumerate()
+        .map(|(index, application)| {
+            normalize(application)
+                .map_err(|e| format!("Application {} in the import: {e}", index + 1))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    conn.transaction::<_, Box<dyn std::error::Error>, _>(|conn| {
+        let mut report = ImportReport {
+            dry_run,
+            ..ImportReport::default()
+        };
+        let mut seen = HashSet::new();
+        for application in &applications {
+            let name = describe(application);
+            if let Some(reason) = duplicate_of(conn, application, &mut seen)? {
+                report.duplicates.push((name, reason));
+                continue;
+            }
+            if !dry_run {
+                diesel::insert_into(cv::table)
+                    .values(application)
+                    .execute(conn)?;
+            }
+            report.imported.push(name);
+        }
+        Ok(report)
+    })
+}
+
+/// Fill in what a hand-written application may leave out, and check the rest.
+fn normalize(
+    application: &ExportedApplication,
+) -> Result<ExportedApplication, Box<dyn std::error::Error>> {
+    if application.job_title.trim().is_empty() || application.company.trim().is_empty() {
+        return Err("the job title and the company must not be empty".into());
+    }
+    let mut application = application.clone();
+    if application.uuid.trim().is_empty() {
+        application.uuid = Uuid::new_v4().to_string();
+    }
+    if application.updated_at.trim().is_empty() {
+        application.updated_at = modified_now();
+    }
+    application.status = if application.status.trim().is_empty() {
+        ApplicationStatus::default()
+    } else {
+        application.status.parse()?
+    }
+    .to_string();
+    Ok(application)
+}
+
+/// Why `application` is not imported, if it is not: the import itself
+/// (`seen`) or the database already has it. The import is checked first, so a
+/// dry run reports a repeated application the same way as a real one.
+fn duplicate_of(
+    conn: &mut DbConnection,
+    application: &ExportedApplication,
+    seen: &mut HashSet<(String, String, String)>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let ExportedApplication {
+        job_title,
+        company,
+        quote,
+        ..
+    } = application;
+    if !seen.insert((job_title.clone(), company.clone(), quote.clone())) {
+        return Ok(Some("listed earlier in the import".to_string()));
+    }
+    if let Some(id) = check_if_entry_exists(conn, job_title, company, quote) {
+        return Ok(Some(format!("already stored as application {id}")));
+    }
+    let same_uuid = cv::table
+        .filter(cv::uuid.eq(&application.uuid))
+        .select(cv::id)
+        .first::<i32>(conn)
+        .optional()?;
+    if let Some(id) = same_uuid {
+        return Ok(Some(format!("its uuid is taken by application {id}")));
+    }
+    Ok(None)
+}
+
+fn describe(application: &ExportedApplication) -> String {
+    format!("{} at {}", application.job_title, application.company)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::save_new_cv_to_db;
+    use crate::migrations::migrated_in_memory_sqlite;
+
+    fn seeded() -> DbConnection {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let quote = "Hello, \"world\"".to_string();
+        save_new_cv_to_db(
+            &mut conn,
+            "/a.pdf",
+            "SRE",
+            "ACME",
+            Some(&quote),
+            "2026-10-01",
+        )
+        .unwrap();
+        save_new_cv_to_db(&mut conn, "/b.pdf", "Dev", "Initech", None, "2026-10-02").unwrap();
+        conn
+    }
+
+    fn stored(conn: &mut DbConnection) -> Vec<ExportedApplication> {
+        let json = export_applications(conn, TransferFormat::Json).unwrap();
+        parse_export(&json, TransferFormat::Json).unwrap()
+    }
+
+    #[test]
+    fn test_json_round_trip_keeps_every_column() {
+        let mut source = seeded();
+        let json = export_applications(&mut source, TransferFormat::Json).unwrap();
+        assert!(json.contains("\"format\": \"rusty-cv-creator/applications\""));
+        assert!(json.contains("\"version\": 1"));
+
+        let mut target = migrated_in_memory_sqlite().unwrap();
+        let report = import_applications(
+            &mut target,
+            &parse_export(&json, TransferFormat::Json).unwrap(),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(report.imported, ["SRE at ACME", "Dev at Initech"]);
+        assert_eq!(stored(&mut target), stored(&mut source));
+    }
+
+    #[test]
+    fn test_csv_round_trip_keeps_every_column() {
+        let mut source = seeded();
+        let csv = export_applications(&mut source, TransferFormat::Csv).unwrap();
+        assert!(csv.starts_with("uuid,application_date,job_title,company,quote,"));
+
+        let mut target = migrated_in_memory_sqlite().unwrap();
+        import_applications(
+            &mut target,
+            &parse_export(&csv, TransferFormat::Csv).unwrap(),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(stored(&mut target), stored(&mut source));
+    }
+
+    #[test]
+    fn test_an_empty_csv_export_is_the_header_alone() {
+        let mut empty = migrated_in_memory_sqlite().unwrap();
+        let header = export_applications(&mut empty, TransferFormat::Csv).unwrap();
+
+        let csv = export_applications(&mut seeded(), TransferFormat::Csv).unwrap();
+        assert_eq!(
+            csv.lines().next().map(|line| line.to_string() + "\n"),
+            Some(header.clone())
+        );
+        assert!(
+            parse_export(&header, TransferFormat::Csv)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_import_skips_stored_and_repeated_applications() {
+        let mut conn = seeded();
+        let mut applications = stored(&mut conn);
+        // A new application, listed twice, and a stored one under a new uuid.
+        let mut new = applications[1].clone();
+        new.uuid = String::new();
+        new.job_title = "Platform".to_string();
+        applications.push(new.clone());
+        applications.push(new);
+        applications[0].uuid = Uuid::new_v4().to_string();
+
+        let report = import_applications(&mut conn, &applications, false).unwrap();
+
+        assert_eq!(report.imported, ["Platform at Initech"]);
+        assert_eq!(
+            report.duplicates,
+            [
+                ("SRE at ACME", "already stored as application 1"),
+                ("Dev at Initech", "already stored as application 2"),
+                ("Platform at Initech", "listed earlier in the import"),
+            ]
+            .map(|(name, reason)| (name.to_string(), reason.to_string()))
+        );
+        assert_eq!(stored(&mut conn).len(), 3);
+    }
+
+    #[test]
+    fn test_dry_run_import_reports_without_writing() {
+        let mut source = seeded();
+        let applications = stored(&mut source);
+        let mut target = migrated_in_memory_sqlite().unwrap();
+
+        let report = import_applications(&mut target, &applications, true).unwrap();
+
+        assert_eq!(
+            report.to_string(),
+            "Dry run: nothing was written\nWould import: 2\n  SRE at ACME\n  Dev at Initech\n\
+             Skipped as duplicates: 0\n"
+        );
+        assert!(stored(&mut target).is_empty());
+    }
+
+    #[test]
+    fn test_hand_written_applications_get_the_defaults() {
+        let json = r#"{"format": "rusty-cv-creator/applications", "version": 1,
+            "applications": [{"job_title": "SRE", "company": "ACME",
+            "application_date": "2026-10-18", "pdf_cv_path": "/cv.pdf"}]}"#;
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+
+        import_applications(
+            &mut conn,
+            &parse_export(json, TransferFormat::Json).unwrap(),
+            false,
+        )
+        .unwrap();
+
+        let row = &stored(&mut conn)[0];
+        assert_eq!((row.status.as_str(), row.generated), ("drafted", true));
+        assert!(Uuid::parse_str(&row.uuid).is_ok());
+        assert!(!row.updated_at.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_imports_are_refused() {
+        let other =
+            r#"{"format": "rusty-cv-creator/applications", "version": 2, "applications": []}"#;
+        let error = parse_export(other, TransferFormat::Json).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Unsupported export version 2")
+        );
+
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let mut application = stored(&mut seeded())[0].clone();
+        application.status = "hired".to_string();
+        let error = import_applications(&mut conn, &[application], false).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Application 1 in the import: Unknown application status 'hired'")
+        );
+        assert!(stored(&mut conn).is_empty());
+    }
+}
@@ -8,6 +8,9 @@ pub struct ApplicationRow {
     pub company: String,
     pub job_title: String,
     pub pdf_path: String,
+    pub status: String,
+    /// Empty for rows saved before the variant was recorded.
+    pub variant: String,
 }
 
 impl From<Cv> for ApplicationRow {
@@ -18,6 +21,8 @@ impl From<Cv> for ApplicationRow {
             company: cv.company,
             job_title: cv.job_title,
             pdf_path: cv.pdf_cv_path,
+            status: cv.status,
+            variant: cv.variant.unwrap_or_default(),
         }
     }
 }
@@ -68,6 +73,8 @@ impl AppState {
             .filter(|r| {
                 r.company.to_lowercase().contains(&lower)
                     || r.job_title.to_lowercase().contains(&lower)
+                    || r.status.to_lowercase().contains(&lower)
+                    || r.variant.to_lowercase().contains(&lower)
             })
             .collect()
     }
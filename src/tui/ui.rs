@@ -12,13 +12,21 @@ pub fn render(frame: &mut Frame, state: &AppState) {
 fn render_table(frame: &mut Frame, area: Rect, state: &AppState) {
     let filtered = state.filtered_rows();
 
-    let header = Row::new(["ID", "Date", "Company", "Job Title", "PDF Path"])
-        .style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )
-        .height(1);
+    let header = Row::new([
+        "ID",
+        "Date",
+        "Company",
+        "Job Title",
+        "Variant",
+        "Status",
+        "PDF Path",
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    )
+    .height(1);
 
     let rows: Vec<Row> = filtered
         .iter()
@@ -34,6 +42,8 @@ fn render_table(frame: &mut Frame, area: Rect, state: &AppState) {
                 row.date.clone(),
                 row.company.clone(),
                 row.job_title.clone(),
+                row.variant.clone(),
+                row.status.clone(),
                 row.pdf_path.clone(),
             ])
             .style(style)
@@ -49,8 +59,10 @@ fn render_table(frame: &mut Frame, area: Rect, state: &AppState) {
     let widths = [
         Constraint::Length(6),
         Constraint::Length(12),
-        Constraint::Percentage(25),
-        Constraint::Percentage(25),
+        Constraint::Percentage(20),
+        Constraint::Percentage(20),
+        Constraint::Length(24),
+        Constraint::Length(10),
         Constraint::Fill(1),
     ];
 
@@ -1,69 +1,309 @@
-use crate::cli_structure::FilterArgs;
+use crate::artifacts::artifacts_archive_path;
+use crate::cli_structure::{FilterArgs, RemoveArgs};
 use crate::config_parse::connect_db;
-use crate::file_handlers;
+use crate::destination_layout::DestinationLayout;
 use crate::global_conf::AppContext;
 use crate::helpers::my_fzf;
-use diesel::prelude::*;
-use log::{error, info, warn};
-use rusty_cv_creator::database::{DbConnection, read_cv_paths};
-use std::path::Path;
+use log::{info, warn};
+use rusty_cv_creator::database::{
+    DbConnection, delete_cv_by_id, find_applications, find_cv_by_id, find_cv_by_pdf_path,
+};
+use rusty_cv_creator::models::Cv;
+use std::fs;
+use std::io::ErrorKind;
 
+/// Let the user pick one PDF among the stored applications matching `filters`.
 pub fn show_cvs(
     conn: &mut DbConnection,
     filters: &FilterArgs,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    // TODO: apply filters
-    warn!("TODO: apply these filters: {filters:?}");
-    let pdfs = read_cv_paths(conn)?;
-    Ok(my_fzf(pdfs))
+    let candidates = matching_applications(conn, filters)?;
+    Ok(my_fzf(fzf_entries(&candidates)))
 }
 
-pub fn remove_cv(ctx: &AppContext, filters: &FilterArgs) -> Result<(), Box<dyn std::error::Error>> {
-    use rusty_cv_creator::schema::cv::dsl::{cv, pdf_cv_path};
+/// The stored applications matching `filters`; none at all is an error, so a
+/// command never opens an empty picker.
+fn matching_applications(
+    conn: &mut DbConnection,
+    filters: &FilterArgs,
+) -> Result<Vec<Cv>, Box<dyn std::error::Error>> {
+    let filter = filters.to_application_filter()?;
+    let candidates = find_applications(conn, &filter)?;
+    if candidates.is_empty() {
+        return Err(format!("No application matches the filters: {filters:?}").into());
+    }
+    info!("{} application(s) match the filters", candidates.len());
+    Ok(candidates)
+}
+
+/// The picker input: one PDF path per line.
+fn fzf_entries(candidates: &[Cv]) -> Vec<String> {
+    candidates
+        .iter()
+        .flat_map(|cv| [cv.pdf_cv_path.clone(), "\n".to_string()])
+        .collect()
+}
+
+/// The stored application a command acts on: `id` when given, otherwise the
+/// user's pick among the stored PDFs matching `filters`.
+pub fn pick_application(
+    conn: &mut DbConnection,
+    id: Option<i32>,
+    filters: &FilterArgs,
+) -> Result<Cv, Box<dyn std::error::Error>> {
+    if let Some(id) = id {
+        return find_cv_by_id(conn, id);
+    }
+
+    let picked = show_cvs(conn, filters)?;
+    find_cv_by_pdf_path(conn, picked.trim())
+}
 
+/// Remove one stored application and its published files.
+///
+/// The candidates are the rows matching the filters. With `--yes` a single
+/// match is removed without asking and several matches are refused; otherwise
+/// the user picks one interactively.
+pub fn remove_cv(ctx: &AppContext, args: &RemoveArgs) -> Result<(), Box<dyn std::error::Error>> {
     let mut conn = connect_db(ctx)?;
+    let sibling_copy = DestinationLayout::from_context(ctx)?.sibling_copy;
 
-    let cv_remove = show_cvs(&mut conn, filters)?;
+    let candidates = matching_applications(&mut conn, &args.filters)?;
+    let target = if args.yes {
+        single_match(candidates)?
+    } else {
+        let picked = my_fzf(fzf_entries(&candidates));
+        candidates
+            .into_iter()
+            .find(|cv| cv.pdf_cv_path == picked.trim())
+            .ok_or_else(|| format!("No stored application for the picked PDF: {picked}"))?
+    };
 
-    let pattern = format!("%{cv_remove}%");
+    for removed in delete_application(&mut conn, &target)? {
+        println!("Removed {removed}");
+    }
+    if sibling_copy {
+        // Not recorded with the row: the working directory it sat next to
+        // depends on the layout at build time.
+        println!(
+            "Kept: the copies of the PDFs next to the working directory ([destination] \
+             sibling_copy), which are not recorded with the application"
+        );
+    }
+    Ok(())
+}
 
-    let _ = diesel::delete(cv.filter(pdf_cv_path.like(pattern)))
-        .execute(&mut conn)
-        .expect("Error deleting cvs");
+/// The only candidate, or an error telling the user to narrow the filters.
+fn single_match(mut candidates: Vec<Cv>) -> Result<Cv, Box<dyn std::error::Error>> {
+    if candidates.len() == 1 {
+        return Ok(candidates.remove(0));
+    }
 
-    let dir_of_cv_path = Path::new(&cv_remove).parent().unwrap();
+    let ids: Vec<i32> = candidates.iter().map(|cv| cv.id).collect();
+    Err(format!(
+        "{} applications match the filters (ids {ids:?}); narrow them down or drop --yes \
+         to pick one",
+        candidates.len()
+    )
+    .into())
+}
 
-    if let Ok(()) = file_handlers::remove_cv_dir(dir_of_cv_path) {
-        info!("Removed dir_of_cv_path");
-        Ok(())
-    } else {
-        error!("Couldn't remove dir: {}", dir_of_cv_path.display());
-        Err(format!("Couldn't remove dir: {}", dir_of_cv_path.display())
-            .to_string()
-            .into())
+/// Delete the row, then its published files: the PDF, the cover letter and
+/// the artifacts archive next to the PDF. Only those files are deleted — never
+/// the directory holding them, which is shared by every CV of that year. A
+/// file that is already gone is not an error. Returns the files removed.
+fn delete_application(
+    conn: &mut DbConnection,
+    target: &Cv,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if !delete_cv_by_id(conn, target.id)? {
+        return Err(format!("No application with id {}", target.id).into());
     }
+    info!("Removed application {} from the database", target.id);
+
+    let mut removed = Vec::new();
+    for file in published_files(target) {
+        match fs::remove_file(&file) {
+            Ok(()) => {
+                info!("Removed {file}");
+                removed.push(file);
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                if file == target.pdf_cv_path {
+                    warn!("The PDF was already gone: {file}");
+                }
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Removed application {} but could not delete {file}: {e:}",
+                    target.id
+                )
+                .into());
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// The files published for an application: its PDF, its cover letter if one
+/// was built, and the artifacts archive `[build] keep_artifacts` may have left.
+fn published_files(target: &Cv) -> Vec<String> {
+    let mut files = vec![target.pdf_cv_path.clone()];
+    files.extend(target.cover_letter_path.clone());
+    files.push(artifacts_archive_path(&target.pdf_cv_path));
+    files
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rusty_cv_creator::database::save_new_cv_to_db;
+    use rusty_cv_creator::migrations::migrated_in_memory_sqlite;
 
     #[test]
     fn test_show_cvs_runs_query_path() {
         // `my_fzf` is interactive and panics without a real selection, so we
         // only assert that the read/query path executes without a DB error.
-        let mut conn = DbConnection::Sqlite(SqliteConnection::establish(":memory:").unwrap());
-        diesel::sql_query(
-            "CREATE TABLE cv (id INTEGER PRIMARY KEY AUTOINCREMENT, application_date VARCHAR, \
-             job_title VARCHAR NOT NULL, company VARCHAR NOT NULL, quote VARCHAR NOT NULL, \
-             pdf_cv_path VARCHAR NOT NULL, generated BOOLEAN NOT NULL DEFAULT 1)",
-        )
-        .execute(&mut conn)
-        .unwrap();
+        let mut conn = migrated_in_memory_sqlite().unwrap();
 
         let filters = FilterArgs::default();
         let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
             show_cvs(&mut conn, &filters)
         }));
     }
+
+    #[test]
+    fn test_matching_applications_narrows_by_company_and_date() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_new_cv_to_db(&mut conn, "/a.pdf", "SRE", "ACME", None, " 3-May-2024").unwrap();
+        save_new_cv_to_db(&mut conn, "/b.pdf", "SRE", "ACME", None, " 3-Jun-2024").unwrap();
+        save_new_cv_to_db(&mut conn, "/c.pdf", "SRE", "Globex", None, " 3-May-2024").unwrap();
+
+        let filters = FilterArgs {
+            company_name: Some("acme".to_string()),
+            date: Some("2024-05".to_string()),
+            ..FilterArgs::default()
+        };
+        let matches = matching_applications(&mut conn, &filters).unwrap();
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pdf_cv_path, "/a.pdf");
+    }
+
+    #[test]
+    fn test_matching_applications_errors_when_nothing_matches() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_new_cv_to_db(&mut conn, "/a.pdf", "SRE", "ACME", None, " 3-May-2024").unwrap();
+
+        let filters = FilterArgs {
+            company_name: Some("Initech".to_string()),
+            ..FilterArgs::default()
+        };
+        assert!(matching_applications(&mut conn, &filters).is_err());
+    }
+
+    #[test]
+    fn test_fzf_entries_lists_one_path_per_line() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_new_cv_to_db(&mut conn, "/a.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+        save_new_cv_to_db(&mut conn, "/b.pdf", "Dev", "ACME", None, "2024-01-01").unwrap();
+        let cvs = matching_applications(&mut conn, &FilterArgs::default()).unwrap();
+
+        assert_eq!(fzf_entries(&cvs).concat(), "/a.pdf\n/b.pdf\n");
+    }
+
+    #[test]
+    fn test_single_match_refuses_ambiguity() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        save_new_cv_to_db(&mut conn, "/a.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+        save_new_cv_to_db(&mut conn, "/b.pdf", "Dev", "ACME", None, "2024-01-01").unwrap();
+        let cvs = matching_applications(&mut conn, &FilterArgs::default()).unwrap();
+
+        let err = single_match(cvs).unwrap_err().to_string();
+        assert!(err.contains("2 applications match"), "got: {err}");
+
+        let one = matching_applications(
+            &mut conn,
+            &FilterArgs {
+                job_title: Some("sre".to_string()),
+                ..FilterArgs::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(single_match(one).unwrap().pdf_cv_path, "/a.pdf");
+    }
+
+    #[test]
+    fn test_delete_application_removes_the_row_and_only_its_pdf() {
+        let td = tempfile::TempDir::new().unwrap();
+        let pdf = td.path().join("cv.pdf");
+        let neighbour = td.path().join("other.pdf");
+        fs::write(&pdf, b"%PDF").unwrap();
+        fs::write(&neighbour, b"%PDF").unwrap();
+
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let cv = save_new_cv_to_db(
+            &mut conn,
+            pdf.to_str().unwrap(),
+            "SRE",
+            "ACME",
+            None,
+            "2024-01-01",
+        )
+        .unwrap();
+
+        delete_application(&mut conn, &cv).unwrap();
+
+        assert!(find_cv_by_id(&mut conn, cv.id).is_err());
+        assert!(!pdf.exists());
+        assert!(
+            neighbour.exists(),
+            "the directory's other PDFs must survive"
+        );
+    }
+
+    #[test]
+    fn test_delete_application_removes_the_cover_letter_and_the_artifacts() {
+        let td = tempfile::TempDir::new().unwrap();
+        let path = |name: &str| td.path().join(name).display().to_string();
+        for name in ["cv.pdf", "cv-cover-letter.pdf", "cv-artifacts.tar.gz"] {
+            fs::write(path(name), b"x").unwrap();
+        }
+        let cover_letter = path("cv-cover-letter.pdf");
+        let new_cv = rusty_cv_creator::models::NewCv {
+            application_date: Some("2024-01-01"),
+            job_title: "SRE",
+            company: "ACME",
+            quote: "",
+            pdf_cv_path: &path("cv.pdf"),
+            generated: true,
+            variant: None,
+            template_ref: None,
+            cover_letter_path: Some(&cover_letter),
+            compaction_level: None,
+            uuid: "5f0c6d1e-0a4b-4c2d-9e8f-1a2b3c4d5e6f",
+        };
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let cv = rusty_cv_creator::database::save_application(&mut conn, &new_cv).unwrap();
+
+        let removed = delete_application(&mut conn, &cv).unwrap();
+
+        assert_eq!(
+            removed,
+            [
+                path("cv.pdf"),
+                path("cv-cover-letter.pdf"),
+                path("cv-artifacts.tar.gz")
+            ]
+        );
+        assert_eq!(fs::read_dir(td.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_delete_application_tolerates_a_missing_pdf() {
+        let mut conn = migrated_in_memory_sqlite().unwrap();
+        let cv =
+            save_new_cv_to_db(&mut conn, "/gone.pdf", "SRE", "ACME", None, "2024-01-01").unwrap();
+        assert!(delete_application(&mut conn, &cv).is_ok());
+    }
 }
@@ -0,0 +1,534 @@
+//! The CV variants a template offers, and the job-title keywords that select
+//! each one.
+//!
+//! Read from, in order of precedence: the `[variants]` section of the user's
+//! INI, a `rusty-cv-variants.ini` manifest shipped at the root of the template,
+//! or the built-in catalogue (the four roles of the original template). A
+//! declared catalogue must match the template: every variant needs its driver
+//! file `<prefix>-<variant>.tex` (or `.typ`, for the typst builder).
+//!
+//! Each entry reads `<variant> = [<priority>:] <keyword>[*<weight>], ...`.
+//! Variants are tried from the highest priority down (ties by name); the first
+//! one whose keyword occurs in the job title wins. The weights (default 1) only
+//! matter when scoring a job description (`insert --job-description`).
+
+use crate::global_conf::AppContext;
+use crate::helpers::clean_string_from_quotes;
+use configparser::ini::Ini;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File name of the catalogue manifest a template may ship at its root.
+pub const VARIANT_MANIFEST: &str = "rusty-cv-variants.ini";
+
+/// INI section (in the user config or the manifest) declaring the catalogue.
+const VARIANTS_SECTION: &str = "variants";
+
+/// A built-in variant: name, priority and weighted keywords.
+type BuiltinRule = (&'static str, i32, &'static [(&'static str, u32)]);
+
+/// The original template's roles. `engineering-manager` comes first on
+/// purpose: the manager variant's title also contains "DevOps" and "Platform",
+/// so "Engineering Manager - DevOps" must not be classified as `senior-devops`.
+///
+/// The title keywords weigh 3; the lighter ones are what a posting for the
+/// role typically talks about.
+const BUILTIN: [BuiltinRule; 4] = [
+    (
+        "engineering-manager",
+        40,
+        &[
+            ("manager", 3),
+            ("management", 3),
+            ("head of", 3),
+            ("lead", 3),
+            ("people management", 2),
+            ("direct reports", 2),
+            ("hiring", 2),
+            ("mentoring", 2),
+            ("career growth", 1),
+            ("roadmap", 1),
+            ("stakeholders", 1),
+        ],
+    ),
+    (
+        "senior-platform-engineer",
+        30,
+        &[
+            ("platform", 3),
+            ("developer experience", 2),
+            ("self-service", 2),
+            ("golden path", 2),
+            ("kubernetes", 1),
+            ("helm", 1),
+            ("service mesh", 1),
+            ("backstage", 1),
+        ],
+    ),
+    (
+        "senior-sre",
+        20,
+        &[
+            ("sre", 3),
+            ("site reliability", 3),
+            ("reliability", 3),
+            ("slo", 2),
+            ("error budget", 2),
+            ("on-call", 2),
+            ("incident", 2),
+            ("postmortem", 2),
+            ("observability", 1),
+            ("prometheus", 1),
+            ("grafana", 1),
+        ],
+    ),
+    (
+        "senior-devops",
+        10,
+        &[
+            ("devops", 3),
+            ("dev ops", 3),
+            ("ci/cd", 2),
+            ("pipeline", 2),
+            ("infrastructure as code", 2),
+            ("terraform", 1),
+            ("ansible", 1),
+            ("jenkins", 1),
+            ("github actions", 1),
+            ("docker", 1),
+        ],
+    ),
+];
+
+/// One selectable variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantRule {
+    pub name: String,
+    pub priority: i32,
+    /// Lowercase substrings of a job title that select this variant.
+    pub keywords: Vec<Keyword>,
+}
+
+/// A catalogue keyword and its weight when scoring a job description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyword {
+    pub term: String,
+    pub weight: u32,
+}
+
+/// Where a catalogue was read from, for messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogueSource {
+    Config,
+    Manifest,
+    Builtin,
+}
+
+/// The variants on offer, highest priority first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantCatalogue {
+    rules: Vec<VariantRule>,
+    source: CatalogueSource,
+}
+
+impl VariantCatalogue {
+    pub fn builtin() -> Self {
+        let rules = BUILTIN
+            .iter()
+            .map(|(name, priority, keywords)| VariantRule {
+                name: (*name).to_string(),
+                priority: *priority,
+                keywords: keywords
+                    .iter()
+                    .map(|(term, weight)| Keyword {
+                        term: (*term).to_string(),
+                        weight: *weight,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self::new(rules, CatalogueSource::Builtin)
+    }
+
+    fn new(mut rules: Vec<VariantRule>, source: CatalogueSource) -> Self {
+        rules.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.name.cmp(&b.name)));
+        Self { rules, source }
+    }
+
+    /// Parse a `[variants]` section. `None` when `ini` has no such section.
+    pub fn from_ini(ini: &Ini, source: CatalogueSource) -> Option<Result<Self, String>> {
+        let section = ini.get_map_ref().get(VARIANTS_SECTION)?;
+        Some(Self::from_section(section, source))
+    }
+
+    fn from_section(
+        section: &HashMap<String, Option<String>>,
+        source: CatalogueSource,
+    ) -> Result<Self, String> {
+        let rules = section
+            .iter()
+            .map(|(name, value)| parse_rule(name, value.as_deref().unwrap_or("")))
+            .collect::<Result<Vec<_>, _>>()?;
+        if rules.is_empty() {
+            return Err("The [variants] section declares no variant".to_string());
+        }
+        Ok(Self::new(rules, source))
+    }
+
+    /// The catalogue for this run: the INI `[variants]` section, else the
+    /// manifest in `template_dir`, else the built-in one.
+    ///
+    /// With a `template_dir` the catalogue is checked against its driver files:
+    /// a declared variant without one is an error, while the built-in catalogue
+    /// is narrowed to the variants the template provides.
+    pub fn load(
+        ctx: &AppContext,
+        template_dir: Option<&Path>,
+        prefix: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let catalogue = match Self::from_ini(ctx.config(), CatalogueSource::Config) {
+            Some(parsed) => parsed?,
+            None => match template_dir.map(read_manifest).transpose()?.flatten() {
+                Some(catalogue) => catalogue,
+                None => Self::builtin(),
+            },
+        };
+
+        match template_dir {
+            Some(dir) => catalogue.validated_against(dir, prefix),
+            None => Ok(catalogue),
+        }
+    }
+
+    /// Check the catalogue against the driver files present in `template_dir`.
+    pub fn validated_against(
+        self,
+        template_dir: &Path,
+        prefix: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // `.typ` drivers are built by `[build] builder = typst`.
+        let has_driver = |rule: &VariantRule| {
+            ["tex", "typ"].iter().any(|extension| {
+                template_dir
+                    .join(format!("{prefix}-{}.{extension}", rule.name))
+                    .is_file()
+            })
+        };
+
+        if self.source == CatalogueSource::Builtin {
+            let rules: Vec<VariantRule> = self.rules.into_iter().filter(has_driver).collect();
+            if rules.is_empty() {
+                return Err(format!(
+                    "No {prefix}-<variant>.tex driver file found in {}",
+                    template_dir.display()
+                )
+                .into());
+            }
+            return Ok(Self { rules, ..self });
+        }
+
+        let missing: Vec<String> = self
+            .rules
+            .iter()
+            .filter(|rule| !has_driver(rule))
+            .map(|rule| format!("{prefix}-{}.tex", rule.name))
+            .collect();
+        if missing.is_empty() {
+            Ok(self)
+        } else {
+            Err(format!(
+                "The variant catalogue ({}) names variants the template at {} does not provide: \
+                 missing {missing:?}",
+                self.source.describe(),
+                template_dir.display()
+            )
+            .into())
+        }
+    }
+
+    pub fn rules(&self) -> &[VariantRule] {
+        &self.rules
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.rules.iter().map(|rule| rule.name.as_str()).collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.rules.iter().any(|rule| rule.name == name)
+    }
+
+    /// The highest-priority variant with a keyword in `job_title`.
+    pub fn infer_from_job_title(&self, job_title: &str) -> Option<&str> {
+        let job_title = job_title.to_lowercase();
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.keywords
+                    .iter()
+                    .any(|k| job_title.contains(k.term.as_str()))
+            })
+            .map(|rule| rule.name.as_str())
+    }
+}
+
+impl CatalogueSource {
+    fn describe(self) -> &'static str {
+        match self {
+            CatalogueSource::Config => "[variants] in the config file",
+            CatalogueSource::Manifest => VARIANT_MANIFEST,
+            CatalogueSource::Builtin => "built-in",
+        }
+    }
+}
+
+/// Read the manifest shipped in `template_dir`, if there is one.
+fn read_manifest(
+    template_dir: &Path,
+) -> Result<Option<VariantCatalogue>, Box<dyn std::error::Error>> {
+    let path = template_dir.join(VARIANT_MANIFEST);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let mut manifest = Ini::new();
+    manifest
+        .load(&path)
+        .map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+    match VariantCatalogue::from_ini(&manifest, CatalogueSource::Manifest) {
+        Some(parsed) => {
+            info!("✅ Using the variant catalogue from {}", path.display());
+            Ok(Some(
+                parsed.map_err(|e| format!("{}: {e}", path.display()))?,
+            ))
+        }
+        None => {
+            warn!("{} has no [variants] section; ignoring it", path.display());
+            Ok(None)
+        }
+    }
+}
+
+/// Parse `[<priority>:] <keyword>[*<weight>], ...` for the variant `name`.
+fn parse_rule(name: &str, value: &str) -> Result<VariantRule, String> {
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid variant name '{name}' (use letters, digits, '-' and '_')"
+        ));
+    }
+
+    let value = clean_string_from_quotes(value);
+    let (priority, keywords) = match value.split_once(':') {
+        Some((priority, keywords)) => (
+            priority.trim().parse::<i32>().map_err(|_| {
+                format!(
+                    "Variant '{name}': priority '{}' is not a number",
+                    priority.trim()
+                )
+            })?,
+            keywords,
+        ),
+        None => (0, value.as_str()),
+    };
+
+    Ok(VariantRule {
+        name: name.to_string(),
+        priority,
+        keywords: keywords
+            .split(',')
+            .map(|keyword| parse_keyword(name, keyword))
+            .filter(|keyword| !matches!(keyword, Ok(Keyword { term, .. }) if term.is_empty()))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Parse `<keyword>[*<weight>]`; the weight defaults to 1.
+fn parse_keyword(name: &str, keyword: &str) -> Result<Keyword, String> {
+    let (term, weight) = match keyword.rsplit_once('*') {
+        Some((term, weight)) => (
+            term,
+            weight.trim().parse::<u32>().map_err(|_| {
+                format!(
+                    "Variant '{name}': weight '{}' of '{}' is not a number",
+                    weight.trim(),
+                    term.trim()
+                )
+            })?,
+        ),
+        None => (keyword, 1),
+    };
+    Ok(Keyword {
+        term: term.trim().to_lowercase(),
+        weight,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn catalogue_from(text: &str) -> Result<VariantCatalogue, String> {
+        let mut ini = Ini::new();
+        ini.read(text.to_string()).unwrap();
+        VariantCatalogue::from_ini(&ini, CatalogueSource::Config).unwrap()
+    }
+
+    fn template_with(drivers: &[&str]) -> TempDir {
+        let td = TempDir::new().unwrap();
+        for driver in drivers {
+            fs::write(td.path().join(format!("CV-{driver}.tex")), "x").unwrap();
+        }
+        td
+    }
+
+    #[test]
+    fn test_builtin_manager_wins_over_devops() {
+        assert_eq!(
+            VariantCatalogue::builtin().infer_from_job_title("Engineering Manager - DevOps"),
+            Some("engineering-manager")
+        );
+    }
+
+    #[test]
+    fn test_builtin_keywords() {
+        let catalogue = VariantCatalogue::builtin();
+        assert_eq!(
+            catalogue.infer_from_job_title("Senior Platform Engineer"),
+            Some("senior-platform-engineer")
+        );
+        assert_eq!(
+            catalogue.infer_from_job_title("Site Reliability Engineer"),
+            Some("senior-sre")
+        );
+        assert_eq!(
+            catalogue.infer_from_job_title("DevOps Specialist"),
+            Some("senior-devops")
+        );
+        assert_eq!(catalogue.infer_from_job_title("Accountant"), None);
+    }
+
+    #[test]
+    fn test_section_priorities_decide_between_matches() {
+        let catalogue = catalogue_from(
+            "[variants]\n\
+             backend = 10: rust, backend\n\
+             data = 20: data, rust\n\
+             generalist\n",
+        )
+        .unwrap();
+
+        assert_eq!(catalogue.names(), ["data", "backend", "generalist"]);
+        assert_eq!(
+            catalogue.infer_from_job_title("Rust Backend Engineer"),
+            Some("data")
+        );
+        assert_eq!(
+            catalogue.infer_from_job_title("Backend Engineer"),
+            Some("backend")
+        );
+        assert!(catalogue.contains("generalist"));
+        assert_eq!(catalogue.infer_from_job_title("Generalist"), None);
+    }
+
+    #[test]
+    fn test_section_rejects_bad_entries() {
+        let err = catalogue_from("[variants]\nbackend = high: rust\n").unwrap_err();
+        assert!(err.contains("priority 'high'"), "got: {err}");
+
+        let err = catalogue_from("[variants]\nback end = rust\n").unwrap_err();
+        assert!(err.contains("Invalid variant name"), "got: {err}");
+
+        let err = catalogue_from("[variants]\nbackend = rust*lots\n").unwrap_err();
+        assert!(err.contains("weight 'lots' of 'rust'"), "got: {err}");
+    }
+
+    #[test]
+    fn test_keyword_weights_default_to_one() {
+        let catalogue = catalogue_from("[variants]\nbackend = 5: Rust*4, tokio\n").unwrap();
+
+        assert_eq!(
+            catalogue.rules()[0].keywords,
+            [
+                Keyword {
+                    term: "rust".to_string(),
+                    weight: 4
+                },
+                Keyword {
+                    term: "tokio".to_string(),
+                    weight: 1
+                },
+            ]
+        );
+        assert_eq!(
+            catalogue.infer_from_job_title("Rust Engineer"),
+            Some("backend")
+        );
+    }
+
+    #[test]
+    fn test_declared_catalogue_requires_every_driver_file() {
+        let template = template_with(&["backend"]);
+        let catalogue = catalogue_from("[variants]\nbackend = rust\ndata = data\n").unwrap();
+
+        let err = catalogue
+            .validated_against(template.path(), "CV")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("CV-data.tex"), "got: {err}");
+        assert!(!err.contains("CV-backend.tex"), "got: {err}");
+    }
+
+    #[test]
+    fn test_builtin_catalogue_narrows_to_the_template_drivers() {
+        let template = template_with(&["senior-sre"]);
+
+        let catalogue = VariantCatalogue::builtin()
+            .validated_against(template.path(), "CV")
+            .unwrap();
+
+        assert_eq!(catalogue.names(), ["senior-sre"]);
+        fs::write(template.path().join("CV-engineering-manager.typ"), "x").unwrap();
+        assert_eq!(
+            VariantCatalogue::builtin()
+                .validated_against(template.path(), "CV")
+                .unwrap()
+                .names()
+                .len(),
+            2,
+            "a typst driver counts as a driver"
+        );
+        assert!(
+            VariantCatalogue::builtin()
+                .validated_against(TempDir::new().unwrap().path(), "CV")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_manifest_in_the_template_is_read() {
+        let template = template_with(&["backend"]);
+        fs::write(
+            template.path().join(VARIANT_MANIFEST),
+            "[variants]\nbackend = 5: rust\n",
+        )
+        .unwrap();
+
+        let catalogue = read_manifest(template.path()).unwrap().unwrap();
+
+        assert_eq!(catalogue.source, CatalogueSource::Manifest);
+        assert_eq!(catalogue.rules[0].priority, 5);
+        assert!(
+            read_manifest(TempDir::new().unwrap().path())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -1,28 +1,12 @@
 // DISTILL: tui-job-applications — unit-level specifications
 // Imports from rusty_cv_creator::tui::* resolve against scaffold stubs in src/tui/.
 
-use diesel::prelude::*;
-use rusty_cv_creator::database::{
-    DbConnection, establish_connection, load_all_applications, save_new_cv_to_db,
-};
+use rusty_cv_creator::database::{establish_connection, load_all_applications, save_new_cv_to_db};
+use rusty_cv_creator::migrations::migrated_in_memory_sqlite;
 use rusty_cv_creator::models::Cv;
 use rusty_cv_creator::tui::events::open_pdf;
 use rusty_cv_creator::tui::state::{AppState, ApplicationRow, Mode};
 
-// ─── Helper: in-memory SQLite DbConnection with the `cv` table ────────────────
-
-fn sqlite_with_cv_table() -> DbConnection {
-    let mut conn = DbConnection::Sqlite(SqliteConnection::establish(":memory:").unwrap());
-    diesel::sql_query(
-        "CREATE TABLE cv (id INTEGER PRIMARY KEY AUTOINCREMENT, application_date VARCHAR, \
-         job_title VARCHAR NOT NULL, company VARCHAR NOT NULL, quote VARCHAR NOT NULL, \
-         pdf_cv_path VARCHAR NOT NULL, generated BOOLEAN NOT NULL DEFAULT 1)",
-    )
-    .execute(&mut conn)
-    .unwrap();
-    conn
-}
-
 // ─── Helper: make a test ApplicationRow ──────────────────────────────────────
 
 fn make_row(id: i32, company: &str, job_title: &str, pdf_path: &str) -> ApplicationRow {
@@ -32,6 +16,8 @@ fn make_row(id: i32, company: &str, job_title: &str, pdf_path: &str) -> Applicat
         company: company.to_string(),
         job_title: job_title.to_string(),
         pdf_path: pdf_path.to_string(),
+        status: "drafted".to_string(),
+        variant: "senior-sre".to_string(),
     }
 }
 
@@ -50,6 +36,14 @@ fn make_cv(
         pdf_cv_path: pdf_cv_path.to_string(),
         quote: String::new(),
         generated: true,
+        status: "drafted".to_string(),
+        variant: Some("senior-sre".to_string()),
+        template_ref: None,
+        cover_letter_path: None,
+        compaction_level: None,
+        uuid: format!("00000000-0000-4000-8000-{id:012}"),
+        updated_at: "2024-01-01T00:00:00.000000Z".to_string(),
+        synced_at: None,
     }
 }
 
@@ -72,6 +66,7 @@ fn us01_s01_maps_all_fields_correctly() {
     assert_eq!(row.company, "Acme Corp");
     assert_eq!(row.job_title, "Rust Engineer");
     assert_eq!(row.pdf_path, "/home/user/cvs/acme.pdf");
+    assert_eq!(row.variant, "senior-sre");
 }
 
 /// @us-01 @in-memory
@@ -105,7 +100,7 @@ fn us01_s04_empty_state_when_no_rows() {
 /// load_all_applications seeds and reads back every stored CV through the seam.
 #[test]
 fn us01_s05_load_all_applications_returns_seeded_rows() {
-    let mut conn = sqlite_with_cv_table();
+    let mut conn = migrated_in_memory_sqlite().unwrap();
     save_new_cv_to_db(
         &mut conn,
         "/home/user/cvs/acme.pdf",
@@ -129,7 +124,7 @@ fn us01_s05_load_all_applications_returns_seeded_rows() {
 /// load_all_applications returns an empty vec when no CVs are stored.
 #[test]
 fn us01_s06_load_all_applications_empty_when_no_rows() {
-    let mut conn = sqlite_with_cv_table();
+    let mut conn = migrated_in_memory_sqlite().unwrap();
     let cvs = load_all_applications(&mut conn).unwrap();
     assert!(cvs.is_empty());
 }